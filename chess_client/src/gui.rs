@@ -1,18 +1,23 @@
-use chess_client::types::PlayerColor;
+use chess_client::types::{GameOptions, PlayerColor};
 use quad_net::quad_socket::client::QuadSocket;
 
 mod connect;
 mod game;
+mod game_setup;
 mod main_menu;
 mod style;
+mod theme;
 
 pub use connect::connect;
 pub use game::game_scene;
+pub use game_setup::game_setup;
 pub use main_menu::main_menu;
 pub use style::GuiResources;
+pub use theme::PieceTheme;
 
 pub enum Scene {
     MainMenu,
-    Connect,
-    QuickGame(PlayerColor, QuadSocket),
+    GameSetup,
+    Connect(GameOptions),
+    QuickGame(GameOptions, PlayerColor, QuadSocket),
 }
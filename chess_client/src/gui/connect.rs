@@ -1,14 +1,19 @@
 use super::Scene;
-use chess_client::types::{PlayerMessage, ServerResponse};
+use chess_client::types::{Difficulty, GameOptions, ServerResponse};
 use macroquad::{
     color::{BLACK, WHITE},
+    math::vec2,
     prelude::{get_time, info},
     text::draw_text,
+    ui::root_ui,
     window::{clear_background, next_frame, screen_height, screen_width},
 };
 use quad_net::quad_socket::client::QuadSocket;
 
-pub async fn connect() -> Scene {
+/// Difficulty of the computer when taking up the server's offer of a bot game
+const BOT_GAME_DIFFICULTY: Difficulty = Difficulty::Medium;
+
+pub async fn connect(mut options: GameOptions) -> Scene {
     let mut socket = QuadSocket::connect("ws://localhost:8091").unwrap();
     #[cfg(target_arch = "wasm32")]
     {
@@ -18,15 +23,38 @@ pub async fn connect() -> Scene {
         }
     }
     info!("socket connection accepted");
-    socket.send_bin(&PlayerMessage::GameVsHuman);
+    socket.send_bin(&options.to_message());
 
+    let mut bot_game_offered = false;
     loop {
-        if let Some(ServerResponse::GameStarted(color)) = socket.try_recv_bin() {
-            return Scene::QuickGame(color, socket);
-        } else {
-            draw_loading_screen("Searching for opponent");
-            next_frame().await;
+        match socket.try_recv_bin::<ServerResponse>() {
+            Some(ServerResponse::GameStarted(color)) => {
+                return Scene::QuickGame(options, color, socket);
+            }
+            Some(ServerResponse::BotGameOffered) => bot_game_offered = true,
+            _ => {}
+        }
+        draw_loading_screen("Searching for opponent");
+
+        if bot_game_offered {
+            let button_x_pos = screen_width() / 2.0 - 160.0;
+            if root_ui().button(
+                vec2(button_x_pos, screen_height() / 2.0 + 40.0),
+                "Play the computer instead",
+            ) {
+                options.difficulty = Some(BOT_GAME_DIFFICULTY);
+                socket.send_bin(&options.to_message());
+                bot_game_offered = false;
+            }
+            if root_ui().button(
+                vec2(button_x_pos, screen_height() / 2.0 + 80.0),
+                "Keep waiting",
+            ) {
+                socket.send_bin(&options.to_message());
+                bot_game_offered = false;
+            }
         }
+        next_frame().await;
     }
 }
 
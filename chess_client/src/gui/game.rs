@@ -1,22 +1,68 @@
-use super::Scene;
-use chess_client::types::{Move, PlayerColor, PlayerMessage, ServerResponse, Square};
-use chess_engine::piece::Piece;
+use super::{PieceTheme, Scene};
+use chess_client::types::{
+    parse_move_input, to_client_move, to_client_square, to_engine_move, to_engine_square,
+    GameOptions, Move, PlayerColor, PlayerMessage, PromotionPiece, ServerResponse, Square,
+};
+use chess_engine::{color::Color, gamestate::Gamestate, piece::Piece};
 use macroquad::{
-    color::{LIGHTGRAY, WHITE},
-    input::{is_mouse_button_down, is_mouse_button_pressed, mouse_position, MouseButton},
-    math::{Rect, Vec2},
-    prelude::info,
+    color::{Color as DrawColor, BLACK, DARKGRAY, LIGHTGRAY, RED, WHITE},
+    experimental::collections::storage,
+    input::{
+        get_char_pressed, is_key_pressed, is_mouse_button_down, is_mouse_button_pressed,
+        mouse_position, KeyCode, MouseButton,
+    },
+    math::Vec2,
+    prelude::{info, warn},
+    shapes::{draw_circle, draw_rectangle},
+    text::draw_text,
     texture::{draw_texture_ex, load_texture, DrawTextureParams, Texture2D},
     window::{clear_background, next_frame, screen_height, screen_width},
 };
 use quad_net::quad_socket::client::QuadSocket;
 
+const CAPTION_FONT_SIZE: f32 = 30.0;
+const CONSOLE_FONT_SIZE: f32 = 24.0;
+/// Longest text the move console takes, comfortably more than any SAN or UCI move
+const MAX_CONSOLE_INPUT: usize = 16;
+
+/// Drawn over the start and end squares of the last move
+const LAST_MOVE_HIGHLIGHT: DrawColor = DrawColor::new(0.95, 0.85, 0.2, 0.45);
+/// Drawn under a King that is in check
+const CHECK_HIGHLIGHT: DrawColor = DrawColor::new(0.9, 0.1, 0.1, 0.6);
+/// Drawn over the square of the piece that is selected or being dragged
+const SELECTED_HIGHLIGHT: DrawColor = DrawColor::new(0.2, 0.6, 0.3, 0.45);
+/// Dots drawn on every square the selected piece can move to
+const LEGAL_TARGET_COLOR: DrawColor = DrawColor::new(0.1, 0.1, 0.1, 0.35);
+/// Background of the pieces offered when promoting
+const PROMOTION_BACKGROUND: DrawColor = DrawColor::new(0.95, 0.95, 0.95, 0.95);
+
+/// Pieces offered when a pawn reaches the last rank, stacked from the promotion square
+/// towards the middle of the board
+const PROMOTION_CHOICES: [PromotionPiece; 4] = [
+    PromotionPiece::Queen,
+    PromotionPiece::Rook,
+    PromotionPiece::Bishop,
+    PromotionPiece::Knight,
+];
+
+#[derive(Clone, Copy)]
 enum MouseState {
     Unclicked,
-    Clicked {
+    /// The button is held on a piece, which follows the cursor until it is dropped
+    Dragging {
         clicked_square: Square,
         piece: Piece,
     },
+    /// A piece was clicked without being dragged, the next click picks where it goes
+    Selected {
+        clicked_square: Square,
+        piece: Piece,
+    },
+    /// A pawn move onto the last rank waiting for the player to pick what it becomes
+    Promoting {
+        from: Square,
+        to: Square,
+    },
 }
 
 #[derive(Default)]
@@ -41,45 +87,98 @@ impl ScreenDimensions {
     }
 }
 
+/// Text typed to play moves from the keyboard instead of with the mouse, in SAN ("Nf3") or
+/// UCI ("g1f3")
+#[derive(Default)]
+struct MoveConsole {
+    input: String,
+    /// Why the last submitted text couldn't be played, cleared as soon as typing resumes
+    error: Option<String>,
+}
+
+impl MoveConsole {
+    /// Read this frame's key presses, returning the text once Enter is pressed
+    fn update(&mut self) -> Option<String> {
+        while let Some(c) = get_char_pressed() {
+            // NOTE: moves are plain ASCII, anything else can only be a typo
+            let is_move_char = c.is_ascii_graphic() || c == ' ';
+            if is_move_char && self.input.len() < MAX_CONSOLE_INPUT {
+                self.input.push(c);
+                self.error = None;
+            }
+        }
+        if is_key_pressed(KeyCode::Backspace) {
+            self.input.pop();
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            self.input.clear();
+            self.error = None;
+        }
+        if is_key_pressed(KeyCode::Enter) && !self.input.trim().is_empty() {
+            return Some(std::mem::take(&mut self.input));
+        }
+        None
+    }
+
+    fn draw(&self, dimensions: &ScreenDimensions) {
+        let y_pos = dimensions.height - CONSOLE_FONT_SIZE;
+        let (text, color) = match self.input.is_empty() {
+            true => ("type a move, e.g. Nf3 or g1f3".to_string(), DARKGRAY),
+            false => (format!("> {}", self.input), BLACK),
+        };
+        draw_text(&text, 0.0, y_pos, CONSOLE_FONT_SIZE, color);
+        if let Some(error) = &self.error {
+            draw_text(
+                error,
+                0.0,
+                y_pos + CONSOLE_FONT_SIZE * 0.8,
+                CONSOLE_FONT_SIZE,
+                RED,
+            );
+        }
+    }
+}
+
+/// The client's view of the game. The engine Gamestate mirrors the server's so that moves
+/// can be checked and shown as soon as they are dropped, and board is what gets drawn.
 struct GameState {
     player_color: PlayerColor,
-    turn: PlayerColor,
+    engine_game: Gamestate,
+    /// Own move that was played locally but not yet confirmed by the server
+    pending_move: Option<Move>,
     board: [[Option<Piece>; 8]; 8],
 }
 
 impl GameState {
     fn new(player_color: PlayerColor) -> GameState {
-        let mut board: [[Option<Piece>; 8]; 8] = [[None; 8]; 8];
-        board[0][0] = 'R'.try_into().ok();
-        board[0][1] = 'N'.try_into().ok();
-        board[0][2] = 'B'.try_into().ok();
-        board[0][3] = 'Q'.try_into().ok();
-        board[0][4] = 'K'.try_into().ok();
-        board[0][5] = 'B'.try_into().ok();
-        board[0][6] = 'N'.try_into().ok();
-        board[0][7] = 'R'.try_into().ok();
-        for col in &mut board[1] {
-            *col = 'P'.try_into().ok();
-        }
-        for col in &mut board[6] {
-            *col = 'p'.try_into().ok();
-        }
-        board[7][0] = 'r'.try_into().ok();
-        board[7][1] = 'n'.try_into().ok();
-        board[7][2] = 'b'.try_into().ok();
-        board[7][3] = 'q'.try_into().ok();
-        board[7][4] = 'k'.try_into().ok();
-        board[7][5] = 'b'.try_into().ok();
-        board[7][6] = 'n'.try_into().ok();
-        board[7][7] = 'r'.try_into().ok();
-        let turn = PlayerColor::White;
-        GameState {
+        let mut gamestate = GameState {
             player_color,
-            turn,
-            board,
+            engine_game: Gamestate::default(),
+            pending_move: None,
+            board: [[None; 8]; 8],
+        };
+        gamestate.sync_board();
+        gamestate
+    }
+
+    fn is_players_turn(&self) -> bool {
+        Color::from(self.player_color) == self.engine_game.active_color()
+    }
+
+    /// Redraw board from the engine Gamestate. This also takes care of the pieces that
+    /// move without being dragged (castling Rooks and pawns captured en passant).
+    fn sync_board(&mut self) {
+        // NOTE: array64 is ordered A1, B1, ..., H8 so its rows are the same as board's
+        let array64 = self.engine_game.board().to_array64();
+        for (index, piece) in array64.into_iter().enumerate() {
+            self.board[index / 8][index % 8] = piece;
         }
     }
 
+    fn piece_at(&self, square: Square) -> Option<Piece> {
+        self.board[square.rank as usize][square.file as usize]
+    }
+
     fn take_square(&mut self, square: Square) -> Option<Piece> {
         self.board[square.rank as usize][square.file as usize].take()
     }
@@ -88,19 +187,208 @@ impl GameState {
         self.board[square.rank as usize][square.file as usize].replace(piece)
     }
 
-    fn move_piece(&mut self, move_: Move) {
-        let piece = self.take_square(move_.from).unwrap();
-        self.set_square(move_.to, piece);
+    /// Lift the player's own piece off square so it can be dragged. Clicking anywhere else
+    /// (empty squares, the opponent's pieces or outside the board) leaves nothing picked up.
+    fn pick_up(&mut self, square: Option<Square>) -> MouseState {
+        match square {
+            Some(clicked_square) => match self.piece_at(clicked_square) {
+                Some(piece) if piece.get_color() == Color::from(self.player_color) => {
+                    self.take_square(clicked_square);
+                    MouseState::Dragging {
+                        clicked_square,
+                        piece,
+                    }
+                }
+                _ => MouseState::Unclicked,
+            },
+            None => MouseState::Unclicked,
+        }
+    }
+
+    /// Legal moves of the piece on square. These are empty while it isn't the player's turn
+    /// since the engine only generates moves for the side to move.
+    fn legal_moves_from(&mut self, square: Square) -> Vec<Move> {
+        let engine_square = match to_engine_square(square) {
+            Some(engine_square) => engine_square,
+            None => return vec![],
+        };
+        match self.engine_game.legal_moves_from(engine_square) {
+            Ok(moves) => moves.into_iter().map(to_client_move).collect(),
+            Err(err) => {
+                warn!("couldn't generate moves from {:?}: {}", square, err);
+                vec![]
+            }
+        }
+    }
+
+    /// Play one of the player's own moves locally before sending it to the server. Returns
+    /// false, leaving everything untouched, if it isn't the player's turn or the move is illegal.
+    fn try_player_move(&mut self, move_: Move) -> bool {
+        if !self.is_players_turn() {
+            return false;
+        }
+        let is_legal = to_engine_move(&mut self.engine_game, move_)
+            .is_some_and(|engine_move| self.engine_game.make_move(engine_move).is_ok());
+        if is_legal {
+            self.pending_move = Some(move_);
+            self.sync_board();
+        }
+        is_legal
+    }
+
+    /// Apply a move the server says was made. The player's own moves were already played
+    /// when they were sent, so those only need confirming.
+    fn apply_server_move(&mut self, player: PlayerColor, move_: Move) {
+        if player == self.player_color && self.pending_move == Some(move_) {
+            self.pending_move = None;
+            return;
+        }
+        match to_engine_move(&mut self.engine_game, move_) {
+            Some(engine_move) if self.engine_game.make_move(engine_move).is_ok() => {
+                self.sync_board();
+            }
+            _ => warn!("server sent a move that is illegal here: {:?}", move_),
+        }
+    }
+
+    /// Start and end squares of the last move played, in either color
+    fn last_move_squares(&self) -> Option<(Square, Square)> {
+        let move_ = to_client_move(self.engine_game.last_move()?);
+        Some((move_.from, move_.to))
+    }
+
+    /// Square of the King whose turn it is, if it is in check
+    fn checked_king_square(&self) -> Option<Square> {
+        if !self.engine_game.is_in_check() {
+            return None;
+        }
+        let king_square = self
+            .engine_game
+            .king_square(self.engine_game.active_color())?;
+        Some(to_client_square(king_square))
+    }
+
+    /// Replace the local game with the server's after it rejected a move we thought was legal
+    fn resync(&mut self, fen: &str) {
+        match Gamestate::try_from(fen) {
+            Ok(engine_game) => {
+                info!("resyncing with server position {}", fen);
+                self.engine_game = engine_game;
+                self.pending_move = None;
+                self.sync_board();
+            }
+            Err(err) => warn!("server sent an invalid position {}: {}", fen, err),
+        }
+    }
+}
+
+/// Square to mouse_position, None if the mouse is off the board
+fn square_at(dimensions: &ScreenDimensions, x_pos: f32, y_pos: f32) -> Option<Square> {
+    if x_pos < dimensions.hor_margin
+        || dimensions.hor_margin + dimensions.game_size <= x_pos
+        || y_pos < dimensions.vert_margin
+        || dimensions.vert_margin + dimensions.game_size <= y_pos
+    {
+        return None;
+    }
+    Some(Square {
+        rank: ((y_pos - dimensions.vert_margin) / dimensions.square_size).floor() as u32,
+        file: ((x_pos - dimensions.hor_margin) / dimensions.square_size).floor() as u32,
+    })
+}
+
+/// Square the promotion choice at index is shown on when promoting on to
+fn promotion_choice_square(to: Square, index: usize) -> Square {
+    let rank = if to.rank == 0 {
+        index as u32
+    } else {
+        to.rank - index as u32
+    };
+    Square {
+        rank,
+        file: to.file,
+    }
+}
+
+fn promoted_piece(promotion: PromotionPiece, color: Color) -> Piece {
+    match (promotion, color) {
+        (PromotionPiece::Queen, Color::White) => Piece::WhiteQueen,
+        (PromotionPiece::Rook, Color::White) => Piece::WhiteRook,
+        (PromotionPiece::Bishop, Color::White) => Piece::WhiteBishop,
+        (PromotionPiece::Knight, Color::White) => Piece::WhiteKnight,
+        (PromotionPiece::Queen, Color::Black) => Piece::BlackQueen,
+        (PromotionPiece::Rook, Color::Black) => Piece::BlackRook,
+        (PromotionPiece::Bishop, Color::Black) => Piece::BlackBishop,
+        (PromotionPiece::Knight, Color::Black) => Piece::BlackKnight,
     }
 }
 
-pub async fn game_scene(color: PlayerColor, mut socket: QuadSocket) -> Scene {
-    info!("new game started as color {:#?}", color);
+/// Play the player's move from one square to another, unless the move is a promotion in
+/// which case the player is first asked what the pawn should become. Illegal moves are
+/// never sent, the piece just stays where it was.
+fn finish_move(
+    gamestate: &mut GameState,
+    socket: &mut QuadSocket,
+    from: Square,
+    to: Square,
+) -> MouseState {
+    let legal_moves = gamestate.legal_moves_from(from);
+    if legal_moves
+        .iter()
+        .any(|move_| move_.to == to && move_.promotion.is_some())
+    {
+        return MouseState::Promoting { from, to };
+    }
+    let move_ = Move {
+        from,
+        to,
+        promotion: None,
+    };
+    if gamestate.try_player_move(move_) {
+        socket.send_bin(&PlayerMessage::MovePiece(move_));
+    }
+    MouseState::Unclicked
+}
+
+/// Play a move typed into the console, sending it to the server if it is legal. The error
+/// is what the console shows when it isn't.
+fn play_typed_move(
+    gamestate: &mut GameState,
+    socket: &mut QuadSocket,
+    input: &str,
+) -> Result<(), String> {
+    if !gamestate.is_players_turn() {
+        return Err("it isn't your turn".to_string());
+    }
+    match parse_move_input(&mut gamestate.engine_game, input) {
+        Some(move_) if gamestate.try_player_move(move_) => {
+            socket.send_bin(&PlayerMessage::MovePiece(move_));
+            Ok(())
+        }
+        _ => Err(format!("{} is not a legal move", input.trim())),
+    }
+}
+
+/// Who the game is against and how long it is, e.g. "vs Computer (Hard), 3+2"
+fn game_caption(options: &GameOptions) -> String {
+    let opponent = match options.difficulty {
+        Some(difficulty) => format!("Computer ({:?})", difficulty),
+        None => "Human".to_string(),
+    };
+    match options.time_control {
+        Some(time_control) => format!("vs {}, {}", opponent, time_control),
+        None => format!("vs {}, untimed", opponent),
+    }
+}
+
+pub async fn game_scene(options: GameOptions, color: PlayerColor, mut socket: QuadSocket) -> Scene {
+    info!("new game started as color {:#?} with {:?}", color, options);
+    let caption = game_caption(&options);
     let path = "assets/boards/board.png";
     let board_texture: Texture2D = load_texture(path).await.unwrap();
 
-    let path = "assets/pieces/wiki_chess.png";
-    let piece_texture: Texture2D = load_texture(path).await.unwrap();
+    let theme = *storage::get::<PieceTheme>();
+    let piece_texture: Texture2D = load_texture(theme.texture_path()).await.unwrap();
 
     let mut gamestate = GameState::new(color);
     let mut dimensions = ScreenDimensions::default();
@@ -109,16 +397,48 @@ pub async fn game_scene(color: PlayerColor, mut socket: QuadSocket) -> Scene {
     let mut mouse_y_pos;
 
     let mut mouse_state = MouseState::Unclicked;
+    let mut console = MoveConsole::default();
     loop {
+        let mut board_synced = false;
         while let Some(resp) = socket.try_recv_bin::<ServerResponse>() {
             match resp {
                 ServerResponse::MoveMade { player, move_ } => {
-                    gamestate.move_piece(move_);
-                    gamestate.turn = !player;
+                    gamestate.apply_server_move(player, move_);
+                    board_synced = true;
+                }
+                ServerResponse::MoveRejected { fen, .. }
+                | ServerResponse::TakebackAccepted { fen } => {
+                    gamestate.resync(&fen);
+                    board_synced = true;
                 }
                 _ => {}
             }
         }
+        // NOTE: syncing puts back a piece that is being dragged, so lift it again. Selections
+        // are dropped if their piece isn't there anymore (e.g. it was captured).
+        if board_synced {
+            mouse_state = match mouse_state {
+                MouseState::Dragging {
+                    clicked_square,
+                    piece,
+                } => match gamestate.take_square(clicked_square) {
+                    Some(synced_piece) if synced_piece == piece => mouse_state,
+                    Some(synced_piece) => {
+                        gamestate.set_square(clicked_square, synced_piece);
+                        MouseState::Unclicked
+                    }
+                    None => MouseState::Unclicked,
+                },
+                MouseState::Selected {
+                    clicked_square,
+                    piece,
+                } if gamestate.piece_at(clicked_square) == Some(piece) => mouse_state,
+                MouseState::Promoting { from, .. } if gamestate.piece_at(from).is_some() => {
+                    mouse_state
+                }
+                _ => MouseState::Unclicked,
+            };
+        }
         clear_background(LIGHTGRAY);
         dimensions.update();
 
@@ -133,6 +453,27 @@ pub async fn game_scene(color: PlayerColor, mut socket: QuadSocket) -> Scene {
             },
         );
 
+        if let Some((from, to)) = gamestate.last_move_squares() {
+            draw_square_highlight(&dimensions, from, LAST_MOVE_HIGHLIGHT);
+            draw_square_highlight(&dimensions, to, LAST_MOVE_HIGHLIGHT);
+        }
+        if let Some(king_square) = gamestate.checked_king_square() {
+            draw_circle(
+                dimensions.hor_margin + dimensions.square_size * (king_square.file as f32 + 0.5),
+                dimensions.vert_margin + dimensions.square_size * (king_square.rank as f32 + 0.5),
+                dimensions.square_size / 2.0,
+                CHECK_HIGHLIGHT,
+            );
+        }
+        let selected_square = match mouse_state {
+            MouseState::Dragging { clicked_square, .. }
+            | MouseState::Selected { clicked_square, .. } => Some(clicked_square),
+            _ => None,
+        };
+        if let Some(selected_square) = selected_square {
+            draw_square_highlight(&dimensions, selected_square, SELECTED_HIGHLIGHT);
+        }
+
         for (row_idx, row) in gamestate.board.iter().enumerate() {
             for (col_idx, piece) in row.iter().enumerate() {
                 if let Some(piece) = piece {
@@ -142,6 +483,7 @@ pub async fn game_scene(color: PlayerColor, mut socket: QuadSocket) -> Scene {
                         dimensions.hor_margin + dimensions.square_size * col_idx as f32;
                     draw_piece(
                         piece_texture,
+                        theme,
                         *piece,
                         dimensions.square_size,
                         piece_y_pos,
@@ -151,76 +493,131 @@ pub async fn game_scene(color: PlayerColor, mut socket: QuadSocket) -> Scene {
             }
         }
 
+        // Dots go over the pieces so that captures are visible too
+        if let Some(selected_square) = selected_square {
+            for move_ in gamestate.legal_moves_from(selected_square) {
+                draw_circle(
+                    dimensions.hor_margin + dimensions.square_size * (move_.to.file as f32 + 0.5),
+                    dimensions.vert_margin + dimensions.square_size * (move_.to.rank as f32 + 0.5),
+                    dimensions.square_size / 6.0,
+                    LEGAL_TARGET_COLOR,
+                );
+            }
+        }
+
+        if let MouseState::Promoting { to, .. } = mouse_state {
+            let color = Color::from(gamestate.player_color);
+            for (index, promotion) in PROMOTION_CHOICES.into_iter().enumerate() {
+                let square = promotion_choice_square(to, index);
+                draw_square_highlight(&dimensions, square, PROMOTION_BACKGROUND);
+                draw_piece(
+                    piece_texture,
+                    theme,
+                    promoted_piece(promotion, color),
+                    dimensions.square_size,
+                    dimensions.vert_margin + dimensions.square_size * square.rank as f32,
+                    dimensions.hor_margin + dimensions.square_size * square.file as f32,
+                );
+            }
+        }
+
+        draw_text(&caption, 0.0, CAPTION_FONT_SIZE, CAPTION_FONT_SIZE, BLACK);
+
+        // Typed moves wait while a piece is held with the mouse or a promotion is being picked,
+        // and playing one drops whatever piece was selected
+        if let Some(input) = console.update() {
+            match mouse_state {
+                MouseState::Unclicked | MouseState::Selected { .. } => {
+                    match play_typed_move(&mut gamestate, &mut socket, &input) {
+                        Ok(()) => mouse_state = MouseState::Unclicked,
+                        Err(error) => console.error = Some(error),
+                    }
+                }
+                _ => console.input = input,
+            }
+        }
+        console.draw(&dimensions);
+
         (mouse_x_pos, mouse_y_pos) = mouse_position();
+        let hovered_square = square_at(&dimensions, mouse_x_pos, mouse_y_pos);
 
         mouse_state = match mouse_state {
             MouseState::Unclicked => {
-                if mouse_x_pos < dimensions.hor_margin
-                    || dimensions.hor_margin + dimensions.game_size <= mouse_x_pos
-                    || mouse_y_pos < dimensions.vert_margin
-                    || dimensions.vert_margin + dimensions.game_size <= mouse_y_pos
-                {
-                    MouseState::Unclicked
-                } else if is_mouse_button_pressed(MouseButton::Left) {
-                    let file = ((mouse_x_pos - dimensions.hor_margin) / dimensions.square_size)
-                        .floor() as u32;
-                    let rank = ((mouse_y_pos - dimensions.vert_margin) / dimensions.square_size)
-                        .floor() as u32;
-                    let clicked_square = Square { rank, file };
-                    let piece = gamestate.take_square(clicked_square);
-                    if let Some(piece) = piece {
-                        MouseState::Clicked {
-                            clicked_square,
-                            piece,
-                        }
-                    } else {
-                        MouseState::Unclicked
-                    }
+                if is_mouse_button_pressed(MouseButton::Left) {
+                    gamestate.pick_up(hovered_square)
                 } else {
                     MouseState::Unclicked
                 }
             }
-            MouseState::Clicked {
+            MouseState::Dragging {
                 clicked_square,
                 piece,
             } => {
                 if is_mouse_button_down(MouseButton::Left) {
                     mouse_state
-                } else if mouse_x_pos < dimensions.hor_margin
-                    || dimensions.hor_margin + dimensions.game_size <= mouse_x_pos
-                    || mouse_y_pos < dimensions.vert_margin
-                    || dimensions.vert_margin + dimensions.game_size <= mouse_y_pos
-                {
+                } else {
                     gamestate.set_square(clicked_square, piece);
-                    MouseState::Unclicked
+                    match hovered_square {
+                        // Letting go where the piece was picked up selects it instead
+                        Some(square) if square == clicked_square => MouseState::Selected {
+                            clicked_square,
+                            piece,
+                        },
+                        Some(square) => {
+                            finish_move(&mut gamestate, &mut socket, clicked_square, square)
+                        }
+                        None => MouseState::Unclicked,
+                    }
+                }
+            }
+            MouseState::Selected { clicked_square, .. } => {
+                if !is_mouse_button_pressed(MouseButton::Left) {
+                    mouse_state
                 } else {
-                    let new_file = ((mouse_x_pos - dimensions.hor_margin) / dimensions.square_size)
-                        .floor() as u32;
-                    let new_rank = ((mouse_y_pos - dimensions.vert_margin) / dimensions.square_size)
-                        .floor() as u32;
-                    let new_square = Square {
-                        rank: new_rank,
-                        file: new_file,
-                    };
-                    let move_ = PlayerMessage::MovePiece(Move {
-                        from: clicked_square,
-                        to: new_square,
-                    });
-                    if gamestate.player_color == gamestate.turn {
-                        socket.send_bin(&move_);
+                    match hovered_square {
+                        Some(square)
+                            if gamestate
+                                .legal_moves_from(clicked_square)
+                                .iter()
+                                .any(|move_| move_.to == square) =>
+                        {
+                            finish_move(&mut gamestate, &mut socket, clicked_square, square)
+                        }
+                        // Clicking another of the player's pieces picks that one up instead
+                        _ => gamestate.pick_up(hovered_square),
+                    }
+                }
+            }
+            MouseState::Promoting { from, to } => {
+                if !is_mouse_button_pressed(MouseButton::Left) {
+                    mouse_state
+                } else {
+                    // Clicking anywhere but one of the offered pieces cancels the move
+                    let promotion = PROMOTION_CHOICES
+                        .into_iter()
+                        .enumerate()
+                        .find(|(index, _)| {
+                            hovered_square == Some(promotion_choice_square(to, *index))
+                        })
+                        .map(|(_, promotion)| promotion);
+                    if let Some(promotion) = promotion {
+                        let move_ = Move {
+                            from,
+                            to,
+                            promotion: Some(promotion),
+                        };
+                        if gamestate.try_player_move(move_) {
+                            socket.send_bin(&PlayerMessage::MovePiece(move_));
+                        }
                     }
-                    gamestate.set_square(clicked_square, piece);
                     MouseState::Unclicked
                 }
             }
         };
-        if let MouseState::Clicked {
-            clicked_square: _,
-            piece,
-        } = mouse_state
-        {
+        if let MouseState::Dragging { piece, .. } = mouse_state {
             draw_piece(
                 piece_texture,
+                theme,
                 piece,
                 dimensions.square_size,
                 mouse_y_pos - dimensions.square_size / 2.0,
@@ -231,21 +628,24 @@ pub async fn game_scene(color: PlayerColor, mut socket: QuadSocket) -> Scene {
     }
 }
 
-fn draw_piece(texture: Texture2D, piece: Piece, size: f32, y_pos: f32, x_pos: f32) {
-    let rectangle = match piece {
-        Piece::WhiteKing => WK_RECTANGLE,
-        Piece::WhiteQueen => WQ_RECTANGLE,
-        Piece::WhiteBishop => WB_RECTANGLE,
-        Piece::WhiteKnight => WN_RECTANGLE,
-        Piece::WhiteRook => WR_RECTANGLE,
-        Piece::WhitePawn => WP_RECTANGLE,
-        Piece::BlackKing => BK_RECTANGLE,
-        Piece::BlackQueen => BQ_RECTANGLE,
-        Piece::BlackBishop => BB_RECTANGLE,
-        Piece::BlackKnight => BN_RECTANGLE,
-        Piece::BlackRook => BR_RECTANGLE,
-        Piece::BlackPawn => BP_RECTANGLE,
-    };
+fn draw_square_highlight(dimensions: &ScreenDimensions, square: Square, color: DrawColor) {
+    draw_rectangle(
+        dimensions.hor_margin + dimensions.square_size * square.file as f32,
+        dimensions.vert_margin + dimensions.square_size * square.rank as f32,
+        dimensions.square_size,
+        dimensions.square_size,
+        color,
+    );
+}
+
+fn draw_piece(
+    texture: Texture2D,
+    theme: PieceTheme,
+    piece: Piece,
+    size: f32,
+    y_pos: f32,
+    x_pos: f32,
+) {
     draw_texture_ex(
         texture,
         x_pos,
@@ -253,81 +653,8 @@ fn draw_piece(texture: Texture2D, piece: Piece, size: f32, y_pos: f32, x_pos: f3
         WHITE,
         DrawTextureParams {
             dest_size: Some(Vec2::splat(size)),
-            source: Some(rectangle),
+            source: Some(theme.source_rect(piece)),
             ..Default::default()
         },
     );
 }
-
-const WK_RECTANGLE: Rect = Rect {
-    x: 0.0,
-    y: 0.0,
-    w: 170.0,
-    h: 170.0,
-};
-const WQ_RECTANGLE: Rect = Rect {
-    x: 171.0,
-    y: 0.0,
-    w: 170.0,
-    h: 170.0,
-};
-const WB_RECTANGLE: Rect = Rect {
-    x: 342.0,
-    y: 0.0,
-    w: 170.0,
-    h: 170.0,
-};
-const WN_RECTANGLE: Rect = Rect {
-    x: 513.0,
-    y: 0.0,
-    w: 170.0,
-    h: 170.0,
-};
-const WR_RECTANGLE: Rect = Rect {
-    x: 684.0,
-    y: 0.0,
-    w: 170.0,
-    h: 170.0,
-};
-const WP_RECTANGLE: Rect = Rect {
-    x: 855.0,
-    y: 0.0,
-    w: 170.0,
-    h: 170.0,
-};
-const BK_RECTANGLE: Rect = Rect {
-    x: 0.0,
-    y: 171.0,
-    w: 170.0,
-    h: 170.0,
-};
-const BQ_RECTANGLE: Rect = Rect {
-    x: 171.0,
-    y: 171.0,
-    w: 170.0,
-    h: 170.0,
-};
-const BB_RECTANGLE: Rect = Rect {
-    x: 342.0,
-    y: 171.0,
-    w: 170.0,
-    h: 170.0,
-};
-const BN_RECTANGLE: Rect = Rect {
-    x: 513.0,
-    y: 171.0,
-    w: 170.0,
-    h: 170.0,
-};
-const BR_RECTANGLE: Rect = Rect {
-    x: 684.0,
-    y: 171.0,
-    w: 170.0,
-    h: 170.0,
-};
-const BP_RECTANGLE: Rect = Rect {
-    x: 855.0,
-    y: 171.0,
-    w: 170.0,
-    h: 170.0,
-};
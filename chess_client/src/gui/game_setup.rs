@@ -0,0 +1,99 @@
+use macroquad::{
+    color::BLACK,
+    experimental::collections::storage,
+    hash,
+    math::vec2,
+    ui::{root_ui, widgets},
+    window::{clear_background, next_frame, screen_height, screen_width},
+};
+
+use chess_client::types::{Difficulty, GameOptions, PlayerColor, TimeControl};
+
+use super::{GuiResources, Scene};
+
+const WINDOW_WIDTH: f32 = 520.0;
+const WINDOW_HEIGHT: f32 = 400.0;
+const TABBAR_HEIGHT: f32 = 40.0;
+const MARGIN: f32 = 20.0;
+
+/// Tabs for each option, lined up with what picking them means
+const OPPONENT_TABS: [&str; 4] = ["Human", "Easy", "Medium", "Hard"];
+const OPPONENTS: [Option<Difficulty>; 4] = [
+    None,
+    Some(Difficulty::Easy),
+    Some(Difficulty::Medium),
+    Some(Difficulty::Hard),
+];
+const COLOR_TABS: [&str; 3] = ["Random", "White", "Black"];
+const COLORS: [Option<PlayerColor>; 3] = [None, Some(PlayerColor::White), Some(PlayerColor::Black)];
+const TIME_CONTROL_TABS: [&str; 4] = ["Untimed", "3+2", "10+0", "15+10"];
+const TIME_CONTROLS: [Option<TimeControl>; 4] = [
+    None,
+    Some(TimeControl {
+        minutes: 3,
+        increment_secs: 2,
+    }),
+    Some(TimeControl {
+        minutes: 10,
+        increment_secs: 0,
+    }),
+    Some(TimeControl {
+        minutes: 15,
+        increment_secs: 10,
+    }),
+];
+
+/// Lets the player pick who to play, their color and a time control before connecting
+pub async fn game_setup() -> Scene {
+    let resources = storage::get::<GuiResources>();
+    loop {
+        clear_background(BLACK);
+        root_ui().push_skin(&resources.login_skin);
+
+        let mut next_scene = None;
+        let tabbar_size = vec2(WINDOW_WIDTH - 2. * MARGIN, TABBAR_HEIGHT);
+        widgets::Window::new(
+            hash!(),
+            vec2(
+                screen_width() / 2. - WINDOW_WIDTH / 2.,
+                screen_height() / 2. - WINDOW_HEIGHT / 2.,
+            ),
+            vec2(WINDOW_WIDTH, WINDOW_HEIGHT),
+        )
+        .titlebar(false)
+        .movable(false)
+        .ui(&mut root_ui(), |ui| {
+            ui.label(None, "Opponent");
+            let opponent = ui.tabbar(hash!(), tabbar_size, &OPPONENT_TABS);
+            let difficulty = OPPONENTS[opponent as usize];
+
+            // NOTE: human opponents are given colors at random by the server
+            let mut color = None;
+            if difficulty.is_some() {
+                ui.label(None, "Color");
+                color = COLORS[ui.tabbar(hash!(), tabbar_size, &COLOR_TABS) as usize];
+            }
+
+            ui.label(None, "Time control");
+            let time_control =
+                TIME_CONTROLS[ui.tabbar(hash!(), tabbar_size, &TIME_CONTROL_TABS) as usize];
+
+            if ui.button(None, "Play") {
+                next_scene = Some(Scene::Connect(GameOptions {
+                    difficulty,
+                    color,
+                    time_control,
+                }));
+            }
+            if ui.button(None, "Back") {
+                next_scene = Some(Scene::MainMenu);
+            }
+        });
+
+        root_ui().pop_skin();
+        if let Some(next_scene) = next_scene {
+            return next_scene;
+        }
+        next_frame().await;
+    }
+}
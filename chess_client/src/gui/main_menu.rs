@@ -6,13 +6,12 @@ use macroquad::{
     window::{clear_background, next_frame, screen_width},
 };
 
-use super::{GuiResources, Scene};
+use super::{GuiResources, PieceTheme, Scene};
 
 const BUTTON_WIDTH: f32 = 500.0;
 const BUTTON_HEIGHT: f32 = 300.0;
 const LABEL_HEIGHT: f32 = 300.0;
 const BUTTON_OFFSET: f32 = 50.0;
-const HALF_MARGIN: f32 = 10.0;
 
 pub async fn main_menu() -> Scene {
     let resources = storage::get::<GuiResources>();
@@ -25,28 +24,29 @@ pub async fn main_menu() -> Scene {
         let label_pos = vec2(screen_width() / 2. - label_size.x / 2., LABEL_HEIGHT);
         root_ui().label(Some(label_pos), title);
 
-        if widgets::Button::new("Computer")
+        if widgets::Button::new("Play")
             .size(vec2(BUTTON_WIDTH, BUTTON_HEIGHT))
             .position(vec2(
-                screen_width() / 2. - BUTTON_WIDTH - HALF_MARGIN,
+                screen_width() / 2. - BUTTON_WIDTH / 2.,
                 label_pos.y + label_size.y + BUTTON_OFFSET,
             ))
             .ui(&mut root_ui())
         {
             root_ui().pop_skin();
-            return Scene::Connect;
+            return Scene::GameSetup;
         }
 
-        if widgets::Button::new("Human")
+        let theme = *storage::get::<PieceTheme>();
+        let theme_label = format!("Pieces: {}", theme.name());
+        if widgets::Button::new(theme_label.as_str())
             .size(vec2(BUTTON_WIDTH, BUTTON_HEIGHT))
             .position(vec2(
-                screen_width() / 2. + HALF_MARGIN,
-                label_pos.y + label_size.y + BUTTON_OFFSET,
+                screen_width() / 2. - BUTTON_WIDTH / 2.,
+                label_pos.y + label_size.y + BUTTON_HEIGHT + BUTTON_OFFSET * 2.,
             ))
             .ui(&mut root_ui())
         {
-            root_ui().pop_skin();
-            return Scene::Connect;
+            *storage::get_mut::<PieceTheme>() = theme.next();
         }
 
         root_ui().pop_skin();
@@ -0,0 +1,105 @@
+use chess_engine::{
+    color::Color,
+    piece::{Piece, PieceType},
+};
+use macroquad::math::Rect;
+
+/// The piece sets the pieces can be drawn with, picked from the main menu. Each one is a
+/// single texture with a row of pieces per Color.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PieceTheme {
+    #[default]
+    Classic,
+    Modern,
+}
+
+/// Where the pieces are on a piece set's texture
+struct TileLayout {
+    tile_size: f32,
+    /// Distance from the start of one tile to the next, tiles can have a gap between them
+    stride: f32,
+    white_row: usize,
+    black_row: usize,
+    /// What each column holds, from left to right
+    columns: [PieceType; 6],
+}
+
+const CLASSIC_LAYOUT: TileLayout = TileLayout {
+    tile_size: 170.0,
+    stride: 171.0,
+    white_row: 0,
+    black_row: 1,
+    columns: [
+        PieceType::King,
+        PieceType::Queen,
+        PieceType::Bishop,
+        PieceType::Knight,
+        PieceType::Rook,
+        PieceType::Pawn,
+    ],
+};
+
+const MODERN_LAYOUT: TileLayout = TileLayout {
+    tile_size: 128.0,
+    stride: 128.0,
+    white_row: 1,
+    black_row: 0,
+    columns: [
+        PieceType::Rook,
+        PieceType::Knight,
+        PieceType::Bishop,
+        PieceType::Queen,
+        PieceType::King,
+        PieceType::Pawn,
+    ],
+};
+
+impl PieceTheme {
+    pub const ALL: [PieceTheme; 2] = [PieceTheme::Classic, PieceTheme::Modern];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PieceTheme::Classic => "Classic",
+            PieceTheme::Modern => "Modern",
+        }
+    }
+
+    /// The theme after this one in ALL, wrapping around, for cycling through them
+    pub fn next(&self) -> PieceTheme {
+        let index = PieceTheme::ALL
+            .iter()
+            .position(|theme| theme == self)
+            .expect("every theme should be in ALL");
+        PieceTheme::ALL[(index + 1) % PieceTheme::ALL.len()]
+    }
+
+    pub fn texture_path(&self) -> &'static str {
+        match self {
+            PieceTheme::Classic => "assets/pieces/wiki_chess.png",
+            PieceTheme::Modern => "assets/pieces/madware_tileset_128.png",
+        }
+    }
+
+    /// Part of the theme's texture to draw for the piece
+    pub fn source_rect(&self, piece: Piece) -> Rect {
+        let layout = match self {
+            PieceTheme::Classic => &CLASSIC_LAYOUT,
+            PieceTheme::Modern => &MODERN_LAYOUT,
+        };
+        let column = layout
+            .columns
+            .iter()
+            .position(|piece_type| *piece_type == piece.get_piece_type())
+            .expect("every PieceType should have a column");
+        let row = match piece.get_color() {
+            Color::White => layout.white_row,
+            Color::Black => layout.black_row,
+        };
+        Rect::new(
+            layout.stride * column as f32,
+            layout.stride * row as f32,
+            layout.tile_size,
+            layout.tile_size,
+        )
+    }
+}
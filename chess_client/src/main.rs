@@ -7,6 +7,7 @@ use gui::Scene;
 async fn main() {
     let gui_resources = gui::GuiResources::new();
     storage::store(gui_resources);
+    storage::store(gui::PieceTheme::default());
 
     let mut next_scene = Scene::MainMenu;
     loop {
@@ -14,11 +15,14 @@ async fn main() {
             Scene::MainMenu => {
                 next_scene = gui::main_menu().await;
             }
-            Scene::Connect => {
-                next_scene = gui::connect().await;
+            Scene::GameSetup => {
+                next_scene = gui::game_setup().await;
             }
-            Scene::QuickGame(color, socket) => {
-                next_scene = gui::game_scene(color, socket).await;
+            Scene::Connect(options) => {
+                next_scene = gui::connect(options).await;
+            }
+            Scene::QuickGame(options, color, socket) => {
+                next_scene = gui::game_scene(options, color, socket).await;
             }
         }
     }
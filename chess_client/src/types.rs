@@ -1,20 +1,107 @@
+use chess_engine::{
+    color::Color, file::File, gamestate::Gamestate, moves::Move as EngineMove, pgn::san_to_move,
+    piece::PieceType, rank::Rank, square::Square as EngineSquare,
+};
 use nanoserde::{DeBin, SerBin};
-use std::ops::Not;
+use std::{fmt, ops::Not};
 
 #[derive(Clone, Debug, DeBin, SerBin)]
 pub enum PlayerMessage {
-    GameVsComputer,
-    GameVsHuman,
+    /// color is the color the player wants to play, or None to be given one at random
+    GameVsComputer {
+        difficulty: Difficulty,
+        color: Option<PlayerColor>,
+        time_control: Option<TimeControl>,
+    },
+    GameVsHuman {
+        time_control: Option<TimeControl>,
+    },
     MovePiece(Move),
     Resign,
+    /// Ask the opponent to take back the requester's last move (and the opponent's reply to it)
+    RequestTakeback,
+    /// Agree to the takeback the opponent asked for
+    AcceptTakeback,
+    /// Ask for the server's ServerResponse::Stats. Only answered before a game is requested,
+    /// and the connection can still request a game afterwards.
+    Stats,
 }
 
 #[derive(Clone, Debug, DeBin, SerBin)]
 pub enum ServerResponse {
     GameStarted(PlayerColor),
-    GameWon(PlayerColor),
-    GameDraw,
-    MoveMade { player: PlayerColor, move_: Move },
+    /// disconnected is set when the loser's connection died instead of the game ending on the board
+    GameWon {
+        winner: PlayerColor,
+        disconnected: bool,
+    },
+    GameDrawn {
+        reason: String,
+    },
+    MoveMade {
+        player: PlayerColor,
+        move_: Move,
+    },
+    /// Sent only to the player whose move was ignored (illegal or out of turn), naming the
+    /// move, with the server's position so that a client which already showed the move can
+    /// resync
+    MoveRejected {
+        move_: Move,
+        fen: String,
+    },
+    /// The player of this color asked for a takeback, which the opponent can accept
+    TakebackOffered(PlayerColor),
+    /// The takeback was made, fen is the position both clients should resync to
+    TakebackAccepted {
+        fen: String,
+    },
+    /// No human opponent with the same time control showed up in time. Answer with
+    /// GameVsComputer to play the computer instead, or GameVsHuman to keep waiting.
+    BotGameOffered,
+    /// What the server is doing right now. games_played counts the games that ended since
+    /// the server started.
+    Stats {
+        active_games: u32,
+        players_waiting: u32,
+        games_played: u32,
+    },
+    /// The position the game is in, sent to both players after GameStarted, MoveMade and
+    /// TakebackAccepted. legal_moves are the moves of whoever is to move, so a client that
+    /// doesn't run the rules itself can draw fen and only offer those.
+    Position {
+        fen: String,
+        legal_moves: Vec<Move>,
+    },
+}
+
+/// Strength of the engine in a computer game. The server maps each level to the time
+/// budget its iterative deepening search gets per move (by default Easy=50ms,
+/// Medium=250ms and Hard=1500ms, see the `difficulty` section of the server config).
+/// Lower levels also play a random move from those scoring close to the best one.
+/// Both the client and the server serialize this enum, so variants must only be
+/// appended to keep the binary encoding in sync.
+#[derive(Clone, Copy, Debug, DeBin, SerBin, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+/// Minutes each player starts with, plus the seconds they get back after each of their moves
+#[derive(Clone, Copy, Debug, DeBin, SerBin, PartialEq, Eq, Hash)]
+pub struct TimeControl {
+    pub minutes: u32,
+    pub increment_secs: u32,
+}
+
+/// Everything picked before connecting to a game
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GameOptions {
+    /// None to play against another human
+    pub difficulty: Option<Difficulty>,
+    /// Only used against the computer, human opponents are given colors at random
+    pub color: Option<PlayerColor>,
+    pub time_control: Option<TimeControl>,
 }
 
 #[derive(Clone, Copy, Debug, DeBin, SerBin, PartialEq, Eq)]
@@ -23,13 +110,25 @@ pub enum PlayerColor {
     Black,
 }
 
-#[derive(Clone, Copy, Debug, DeBin, SerBin)]
+#[derive(Clone, Copy, Debug, DeBin, SerBin, PartialEq, Eq)]
 pub struct Move {
     pub from: Square,
     pub to: Square,
+    /// Must be set when a pawn moves onto the last rank, and left as None otherwise.
+    /// There is no separate step where the server asks which piece to promote to, so
+    /// the server ignores a promoting move that doesn't include it.
+    pub promotion: Option<PromotionPiece>,
+}
+
+#[derive(Clone, Copy, Debug, DeBin, SerBin, PartialEq, Eq)]
+pub enum PromotionPiece {
+    Knight,
+    Bishop,
+    Rook,
+    Queen,
 }
 
-#[derive(Clone, Copy, Debug, DeBin, SerBin)]
+#[derive(Clone, Copy, Debug, DeBin, SerBin, PartialEq, Eq)]
 pub struct Square {
     pub rank: u32,
     pub file: u32,
@@ -45,3 +144,302 @@ impl Not for PlayerColor {
         }
     }
 }
+
+/// Written the usual way, minutes then increment (e.g. "3+2")
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}+{}", self.minutes, self.increment_secs)
+    }
+}
+
+impl GameOptions {
+    /// The first message to send the server to start a game with these options
+    pub fn to_message(&self) -> PlayerMessage {
+        match self.difficulty {
+            Some(difficulty) => PlayerMessage::GameVsComputer {
+                difficulty,
+                color: self.color,
+                time_control: self.time_control,
+            },
+            None => PlayerMessage::GameVsHuman {
+                time_control: self.time_control,
+            },
+        }
+    }
+}
+
+/// PlayerColor is the wire format version of the engine's Color. Converting through these
+/// keeps the two in sync instead of matching on both by hand wherever they meet.
+impl From<Color> for PlayerColor {
+    fn from(color: Color) -> Self {
+        match color {
+            Color::White => PlayerColor::White,
+            Color::Black => PlayerColor::Black,
+        }
+    }
+}
+
+impl From<PlayerColor> for Color {
+    fn from(player_color: PlayerColor) -> Self {
+        match player_color {
+            PlayerColor::White => Color::White,
+            PlayerColor::Black => Color::Black,
+        }
+    }
+}
+
+/// Find the legal engine move matching the move sent by a client, if there is one.
+/// A pawn move onto the last rank only matches if the client picked a promotion piece.
+pub fn to_engine_move(engine_game: &mut Gamestate, move_: Move) -> Option<EngineMove> {
+    let start = to_engine_square(move_.from)?;
+    let end = to_engine_square(move_.to)?;
+    let promotion = move_.promotion.map(to_piece_type);
+
+    engine_game
+        .gen_legal_moves()
+        .ok()?
+        .moves
+        .into_iter()
+        .flatten()
+        .find(|engine_move| {
+            engine_move.get_start() == Ok(start)
+                && engine_move.get_end() == Ok(end)
+                && engine_move
+                    .get_piece_promoted()
+                    .map(|piece| piece.map(|piece| piece.get_piece_type()))
+                    == Ok(promotion)
+        })
+}
+
+/// Find the legal move for some typed text, either in UCI long algebraic notation ("g1f3",
+/// "e7e8q") or in SAN ("Nf3", "exd5", "O-O"). UCI is tried first since a few UCI strings
+/// would also read as pawn moves in SAN.
+pub fn parse_move_input(engine_game: &mut Gamestate, input: &str) -> Option<Move> {
+    let input = input.trim();
+    let uci_move = engine_game
+        .gen_legal_moves()
+        .ok()?
+        .moves
+        .into_iter()
+        .flatten()
+        .find(|engine_move| engine_move.to_uci() == input.to_lowercase());
+    uci_move
+        .or_else(|| san_to_move(engine_game, input).ok())
+        .map(to_client_move)
+}
+
+fn to_piece_type(promotion: PromotionPiece) -> PieceType {
+    match promotion {
+        PromotionPiece::Knight => PieceType::Knight,
+        PromotionPiece::Bishop => PieceType::Bishop,
+        PromotionPiece::Rook => PieceType::Rook,
+        PromotionPiece::Queen => PieceType::Queen,
+    }
+}
+
+pub fn to_engine_square(square: Square) -> Option<EngineSquare> {
+    let file = File::try_from(square.file as usize).ok()?;
+    let rank = Rank::try_from(square.rank as usize).ok()?;
+    Some(EngineSquare::from_file_and_rank(file, rank))
+}
+
+pub fn to_client_square(square: EngineSquare) -> Square {
+    Square {
+        rank: square.get_rank() as u32,
+        file: square.get_file() as u32,
+    }
+}
+
+pub fn to_client_move(engine_move: EngineMove) -> Move {
+    let promotion =
+        engine_move
+            .get_piece_promoted()
+            .unwrap()
+            .and_then(|piece| match piece.get_piece_type() {
+                PieceType::Knight => Some(PromotionPiece::Knight),
+                PieceType::Bishop => Some(PromotionPiece::Bishop),
+                PieceType::Rook => Some(PromotionPiece::Rook),
+                PieceType::Queen => Some(PromotionPiece::Queen),
+                _ => None,
+            });
+
+    Move {
+        from: to_client_square(engine_move.get_start().unwrap()),
+        to: to_client_square(engine_move.get_end().unwrap()),
+        promotion,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chess_engine::piece::Piece;
+
+    #[test]
+    fn test_player_color_round_trip() {
+        for player_color in [PlayerColor::White, PlayerColor::Black] {
+            let output = PlayerColor::from(Color::from(player_color));
+            let expected = player_color;
+            assert_eq!(output, expected);
+        }
+    }
+
+    #[test]
+    fn test_color_round_trip() {
+        for color in [Color::White, Color::Black] {
+            let output = Color::from(PlayerColor::from(color));
+            let expected = color;
+            assert_eq!(output, expected);
+        }
+    }
+
+    #[test]
+    fn test_game_options_to_message_computer() {
+        let time_control = TimeControl {
+            minutes: 3,
+            increment_secs: 2,
+        };
+        let options = GameOptions {
+            difficulty: Some(Difficulty::Hard),
+            color: Some(PlayerColor::Black),
+            time_control: Some(time_control),
+        };
+        let output = matches!(
+            options.to_message(),
+            PlayerMessage::GameVsComputer {
+                difficulty: Difficulty::Hard,
+                color: Some(PlayerColor::Black),
+                time_control: Some(sent),
+            } if sent == time_control
+        );
+        assert!(output);
+    }
+
+    #[test]
+    fn test_game_options_to_message_human_ignores_color() {
+        let options = GameOptions {
+            difficulty: None,
+            color: Some(PlayerColor::White),
+            time_control: None,
+        };
+        let output = matches!(
+            options.to_message(),
+            PlayerMessage::GameVsHuman { time_control: None }
+        );
+        assert!(output);
+    }
+
+    #[test]
+    fn test_time_control_display() {
+        let time_control = TimeControl {
+            minutes: 15,
+            increment_secs: 10,
+        };
+        let output = time_control.to_string();
+        let expected = "15+10";
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_player_color_not_matches_opponent() {
+        for color in [Color::White, Color::Black] {
+            let output = !PlayerColor::from(color);
+            let expected = PlayerColor::from(color.opponent());
+            assert_eq!(output, expected);
+        }
+    }
+
+    fn promotion_move(promotion: Option<PromotionPiece>) -> Move {
+        // b7 to b8
+        Move {
+            from: Square { rank: 6, file: 1 },
+            to: Square { rank: 7, file: 1 },
+            promotion,
+        }
+    }
+
+    #[test]
+    fn test_to_engine_move_each_promotion_choice() {
+        let promotions = [
+            PromotionPiece::Knight,
+            PromotionPiece::Bishop,
+            PromotionPiece::Rook,
+            PromotionPiece::Queen,
+        ];
+
+        for promotion in promotions {
+            let mut engine_game = Gamestate::try_from("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+            let engine_move = to_engine_move(&mut engine_game, promotion_move(Some(promotion)));
+
+            let output = engine_move.map(|engine_move| engine_move.get_piece_promoted());
+            let expected = Some(Ok(Some(match promotion {
+                PromotionPiece::Knight => Piece::WhiteKnight,
+                PromotionPiece::Bishop => Piece::WhiteBishop,
+                PromotionPiece::Rook => Piece::WhiteRook,
+                PromotionPiece::Queen => Piece::WhiteQueen,
+            })));
+            assert_eq!(output, expected);
+
+            let output = engine_move
+                .map(to_client_move)
+                .and_then(|move_| move_.promotion);
+            let expected = Some(promotion);
+            assert_eq!(output, expected);
+        }
+    }
+
+    #[test]
+    fn test_to_engine_move_promotion_required() {
+        let mut engine_game = Gamestate::try_from("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let output = to_engine_move(&mut engine_game, promotion_move(None));
+        let expected = None;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_parse_move_input_uci_and_san() {
+        let expected = Some(Move {
+            from: Square { rank: 0, file: 6 },
+            to: Square { rank: 2, file: 5 },
+            promotion: None,
+        });
+        for input in ["g1f3", "G1F3", "Nf3", " Nf3 "] {
+            let mut engine_game = Gamestate::default();
+            let output = parse_move_input(&mut engine_game, input);
+            assert_eq!(output, expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_move_input_promotion() {
+        let mut engine_game = Gamestate::try_from("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let expected = Some(promotion_move(Some(PromotionPiece::Knight)));
+        for input in ["b7b8n", "b8=N"] {
+            let output = parse_move_input(&mut engine_game, input);
+            assert_eq!(output, expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_move_input_rejects_illegal_and_garbage() {
+        let mut engine_game = Gamestate::default();
+        for input in ["e2e5", "Nf6", "e7e5", "", "hello"] {
+            let output = parse_move_input(&mut engine_game, input);
+            let expected = None;
+            assert_eq!(output, expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_to_engine_move_promotion_on_non_promoting_move() {
+        let mut engine_game = Gamestate::default();
+        let move_ = Move {
+            from: Square { rank: 1, file: 4 },
+            to: Square { rank: 3, file: 4 },
+            promotion: Some(PromotionPiece::Queen),
+        };
+        let output = to_engine_move(&mut engine_game, move_);
+        let expected = None;
+        assert_eq!(output, expected);
+    }
+}
@@ -0,0 +1,69 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use chess_engine::{error::MakeMoveError, evaluation::evaluate, gamestate::Gamestate, moves::Move};
+
+// CONSTANTS:
+const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+const KIWIPETE_FEN: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+/// Either Gamestate::make_move or Gamestate::make_move_unchecked
+type MakeMove = fn(&mut Gamestate, Move) -> Result<(), MakeMoveError>;
+
+/// Count the leaf nodes of the legal move tree to the given depth
+fn perft(gamestate: &mut Gamestate, depth: usize, make_move: MakeMove) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let mut nodes = 0;
+    for move_ in gamestate
+        .gen_legal_moves()
+        .unwrap()
+        .moves
+        .into_iter()
+        .flatten()
+    {
+        make_move(gamestate, move_).unwrap();
+        nodes += perft(gamestate, depth - 1, make_move);
+        gamestate.undo_move().unwrap();
+    }
+    nodes
+}
+
+fn bench_gen_move_list(c: &mut Criterion) {
+    for (name, fen) in [("starting", STARTING_FEN), ("kiwipete", KIWIPETE_FEN)] {
+        let gamestate = Gamestate::try_from(fen).unwrap();
+        c.bench_function(&format!("gen_move_list {name}"), |b| {
+            b.iter(|| black_box(&gamestate).gen_move_list().unwrap())
+        });
+    }
+}
+
+fn bench_perft(c: &mut Criterion) {
+    let mut group = c.benchmark_group("perft");
+    // NOTE: a single perft(4) takes long enough that the default 100 samples is too slow
+    group.sample_size(10);
+    for (name, fen) in [("starting", STARTING_FEN), ("kiwipete", KIWIPETE_FEN)] {
+        let mut gamestate = Gamestate::try_from(fen).unwrap();
+        group.bench_function(format!("perft(4) {name}"), |b| {
+            b.iter(|| perft(black_box(&mut gamestate), 4, Gamestate::make_move))
+        });
+        // Skips validating each move, like search does
+        group.bench_function(format!("unchecked perft(4) {name}"), |b| {
+            b.iter(|| perft(black_box(&mut gamestate), 4, Gamestate::make_move_unchecked))
+        });
+    }
+    group.finish();
+}
+
+fn bench_evaluate(c: &mut Criterion) {
+    for (name, fen) in [("starting", STARTING_FEN), ("kiwipete", KIWIPETE_FEN)] {
+        let gamestate = Gamestate::try_from(fen).unwrap();
+        c.bench_function(&format!("evaluate {name}"), |b| {
+            b.iter(|| evaluate(black_box(&gamestate)))
+        });
+    }
+}
+
+criterion_group!(benches, bench_gen_move_list, bench_perft, bench_evaluate);
+criterion_main!(benches);
@@ -0,0 +1,210 @@
+//! Minimal Universal Chess Interface front-end so the engine can be used from
+//! standard chess GUIs. Only the commands needed to play games are supported.
+use std::io::{self, BufRead, Write};
+use std::time::Duration;
+
+use chess_engine::{
+    color::Color,
+    gamestate::Gamestate,
+    search::{is_mate_score, search, search_timed, SearchResult, MATE_SCORE},
+};
+
+// CONSTANTS:
+const ENGINE_NAME: &str = "rust_chess";
+const ENGINE_AUTHOR: &str = "KomodoTech";
+/// Used when go is sent without any limits
+const DEFAULT_MOVETIME_MS: u64 = 1000;
+/// When playing on a clock, spend this fraction of the remaining time on each move
+const CLOCK_FRACTION: u64 = 30;
+
+enum SearchLimit {
+    Depth(usize),
+    MoveTime(Duration),
+}
+
+fn main() {
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+    let mut gamestate = Gamestate::default();
+
+    for line in stdin.lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        let keep_running = handle_command(&line, &mut gamestate, &mut stdout);
+        stdout.flush().unwrap();
+        if !keep_running {
+            break;
+        }
+    }
+}
+
+/// Respond to one line sent by the GUI, writing everything (info strings included) to out.
+/// Returns false once the GUI asks to quit.
+fn handle_command(line: &str, gamestate: &mut Gamestate, out: &mut impl Write) -> bool {
+    let tokens = line.split_whitespace().collect::<Vec<_>>();
+
+    match tokens.first() {
+        Some(&"uci") => {
+            writeln!(out, "id name {ENGINE_NAME}").unwrap();
+            writeln!(out, "id author {ENGINE_AUTHOR}").unwrap();
+            writeln!(out, "uciok").unwrap();
+        }
+        Some(&"isready") => writeln!(out, "readyok").unwrap(),
+        Some(&"ucinewgame") => *gamestate = Gamestate::default(),
+        Some(&"position") => match parse_position(&tokens[1..]) {
+            Ok(position) => *gamestate = position,
+            Err(err) => writeln!(out, "info string invalid position: {err}").unwrap(),
+        },
+        Some(&"go") => {
+            let limit = parse_go(&tokens[1..], gamestate);
+            let result = match limit {
+                SearchLimit::Depth(depth) => search(gamestate, depth),
+                SearchLimit::MoveTime(budget) => search_timed(gamestate, budget),
+            };
+            match result {
+                Ok(result) => write_search_result(out, &result),
+                Err(err) => {
+                    writeln!(out, "info string search failed: {err}").unwrap();
+                    writeln!(out, "bestmove 0000").unwrap();
+                }
+            }
+        }
+        Some(&"quit") => return false,
+        // NOTE: the protocol says unknown commands should be ignored
+        _ => {}
+    }
+    true
+}
+
+/// Parse the arguments of "position startpos moves ..." or "position fen <fen> moves ..."
+fn parse_position(tokens: &[&str]) -> Result<Gamestate, String> {
+    let moves_index = tokens.iter().position(|&token| token == "moves");
+    let (setup, moves) = match moves_index {
+        Some(index) => (&tokens[..index], &tokens[index + 1..]),
+        None => (tokens, &[][..]),
+    };
+
+    let mut gamestate = match setup.split_first() {
+        Some((&"startpos", _)) => Gamestate::default(),
+        Some((&"fen", fen)) => {
+            Gamestate::try_from(fen.join(" ").as_str()).map_err(|err| err.to_string())?
+        }
+        _ => {
+            return Err(format!(
+                "expected startpos or fen, got: {}",
+                setup.join(" ")
+            ))
+        }
+    };
+
+    gamestate
+        .apply_uci_moves(moves)
+        .map_err(|err| err.to_string())?;
+    Ok(gamestate)
+}
+
+/// Work out how long to search for from the arguments of "go"
+fn parse_go(tokens: &[&str], gamestate: &Gamestate) -> SearchLimit {
+    let get_value = |name: &str| {
+        tokens
+            .iter()
+            .position(|&token| token == name)
+            .and_then(|index| tokens.get(index + 1))
+            .and_then(|value| value.parse::<u64>().ok())
+    };
+
+    if let Some(depth) = get_value("depth") {
+        return SearchLimit::Depth(depth as usize);
+    }
+    if let Some(movetime) = get_value("movetime") {
+        return SearchLimit::MoveTime(Duration::from_millis(movetime));
+    }
+
+    let remaining = match gamestate.active_color() {
+        Color::White => get_value("wtime"),
+        Color::Black => get_value("btime"),
+    };
+    match remaining {
+        Some(remaining) => SearchLimit::MoveTime(Duration::from_millis(remaining / CLOCK_FRACTION)),
+        None => SearchLimit::MoveTime(Duration::from_millis(DEFAULT_MOVETIME_MS)),
+    }
+}
+
+/// Mates are reported in moves rather than centipawns, negative when the engine is getting mated
+fn uci_score(score: i32) -> String {
+    if is_mate_score(score) {
+        let plies = MATE_SCORE - score.abs();
+        format!("mate {}", score.signum() * ((plies + 1) / 2))
+    } else {
+        format!("cp {score}")
+    }
+}
+
+fn write_search_result(out: &mut impl Write, result: &SearchResult) {
+    writeln!(
+        out,
+        "info depth {} score {} nodes {}",
+        result.depth,
+        uci_score(result.score),
+        result.nodes
+    )
+    .unwrap();
+
+    match result.best_move {
+        Some(best_move) => writeln!(out, "bestmove {}", best_move.to_uci()).unwrap(),
+        // NOTE: the null move is how UCI says there is nothing to play
+        None => writeln!(out, "bestmove 0000").unwrap(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Send the lines in order and return everything written back
+    fn run(lines: &[&str]) -> String {
+        let mut gamestate = Gamestate::default();
+        let mut out = Vec::new();
+        for line in lines {
+            handle_command(line, &mut gamestate, &mut out);
+        }
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_uci_invalid_position_info_string() {
+        let output = run(&["position startpos moves e2e5", "isready"]);
+        assert!(
+            output.starts_with("info string invalid position: ") && output.ends_with("readyok\n"),
+            "{output}"
+        );
+    }
+
+    #[test]
+    fn test_uci_go_depth_bestmove() {
+        // Back rank mate: Ra1-a8#
+        let output = run(&[
+            "position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1",
+            "go depth 2",
+        ]);
+        assert!(output.ends_with("bestmove a1a8\n"), "{output}");
+        assert!(output.contains("info depth 2 score mate 1 "), "{output}");
+    }
+
+    #[test]
+    fn test_uci_go_depth_getting_mated() {
+        // Black's only move is Kb8, then Rh8#
+        let output = run(&["position fen k7/8/1K6/8/8/8/8/7R b - - 0 1", "go depth 3"]);
+        assert!(output.contains("info depth 3 score mate -1 "), "{output}");
+    }
+
+    #[test]
+    fn test_uci_quit() {
+        let mut gamestate = Gamestate::default();
+        let output = handle_command("quit", &mut gamestate, &mut Vec::new());
+        let expected = false;
+        assert_eq!(output, expected);
+    }
+}
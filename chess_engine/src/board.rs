@@ -27,6 +27,15 @@ pub const NUM_EXTERNAL_BOARD_SQUARES: usize = 64;
 pub const NUM_BOARD_COLUMNS: usize = 10;
 /// Number of rows for the internal board (10x12)
 pub const NUM_BOARD_ROWS: usize = 12;
+/// Longest rank FEN that gets parsed. A valid one is at most 8 characters, the slack lets near
+/// misses still report what is actually wrong with them.
+pub const MAX_RANK_FEN_LEN: usize = 16;
+/// Non-pawn material (Knights, Bishops, Rooks and Queens of both colors) in the starting position
+const OPENING_PHASE_MATERIAL: u32 = 6_800;
+/// At or below this much non-pawn material (e.g. a Rook and a minor piece each) it's an endgame
+const ENDGAME_PHASE_MATERIAL: u32 = 2_600;
+/// phase_factor above which the game is still considered to be in the opening
+const OPENING_PHASE_FACTOR: f32 = 0.9;
 
 #[rustfmt::skip]
 const STARTING_POSITION_PIECES: [Option<Piece>; NUM_INTERNAL_BOARD_SQUARES] = [
@@ -44,6 +53,14 @@ const STARTING_POSITION_PIECES: [Option<Piece>; NUM_INTERNAL_BOARD_SQUARES] = [
     None, None,                   None,                     None,                     None,                    None,                   None,                     None,                     None,                   None,
 ];
 
+/// Rough stage of the game, based on how much non-pawn material is left
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Opening,
+    Middlegame,
+    Endgame,
+}
+
 #[derive(Debug)]
 pub struct BoardBuilder {
     validity_check: ValidityCheck,
@@ -90,73 +107,80 @@ impl BoardBuilder {
     /// Finalizer function. Has access to pieces and generates everything else.
     /// Given that the validity check can fail, building has to return a Result
     pub fn build(&self) -> Result<Board, BoardBuildError> {
-        let mut pawns: [BitBoard; Color::COUNT] = [BitBoard(0), BitBoard(0)];
-        let mut kings_square: [Option<Square>; Color::COUNT] = [None; Color::COUNT];
+        let mut board = Board {
+            pieces: [None; NUM_INTERNAL_BOARD_SQUARES],
+            pawns: [BitBoard(0); Color::COUNT],
+            kings_square: [None; Color::COUNT],
+            piece_count: [0; Piece::COUNT],
+            big_piece_count: [0; Color::COUNT],
+            major_piece_count: [0; Color::COUNT],
+            minor_piece_count: [0; Color::COUNT],
+            material_score: [0; Color::COUNT],
+            piece_list: Default::default(),
+        };
+        self.build_into(&mut board)?;
+        Ok(board)
+    }
 
-        let mut piece_count: [u8; Piece::COUNT] = [0; Piece::COUNT];
-        let mut big_piece_count: [u8; Color::COUNT] = [0; Color::COUNT];
-        let mut major_piece_count: [u8; Color::COUNT] = [0; Color::COUNT];
-        let mut minor_piece_count: [u8; Color::COUNT] = [0; Color::COUNT];
-        let mut material_score: [u32; Color::COUNT] = [0; Color::COUNT];
-        let mut piece_list: [Vec<Square>; Piece::COUNT] = Default::default();
+    /// Same as build but overwrites an existing Board instead of creating a new one. The
+    /// piece_list Vecs are cleared rather than replaced so their allocations get reused.
+    /// If the validity check fails the Board is still overwritten.
+    pub fn build_into(&self, board: &mut Board) -> Result<(), BoardBuildError> {
+        board.pawns = [BitBoard(0); Color::COUNT];
+        board.kings_square = [None; Color::COUNT];
+        board.piece_count = [0; Piece::COUNT];
+        board.big_piece_count = [0; Color::COUNT];
+        board.major_piece_count = [0; Color::COUNT];
+        board.minor_piece_count = [0; Color::COUNT];
+        board.material_score = [0; Color::COUNT];
+        for squares in board.piece_list.iter_mut() {
+            squares.clear();
+        }
 
-        // Note: pieces are being cloned here so that we can create multiple boards.
-        // from the same builder. Optimizer might elide clones/copies if you
-        // don't reuse the BoardBuilder, but not sure.
-        let pieces = self.pieces;
+        // Note: pieces are being copied here so that we can create multiple boards.
+        // from the same builder.
+        board.pieces = self.pieces;
 
-        for (index, piece) in pieces.into_iter().enumerate() {
+        for (index, piece) in self.pieces.into_iter().enumerate() {
             if let Some(piece) = piece {
                 // check that no Piece is found on an invalid 10x12 square
                 let square = Square::try_from(index)?;
                 let color = piece.get_color();
 
-                piece_count[piece as usize] += 1;
+                board.piece_count[piece as usize] += 1;
 
                 // update material_score
-                material_score[piece.get_color() as usize] += piece.get_value();
+                board.material_score[piece.get_color() as usize] += piece.get_value();
 
                 match piece {
                     pawn if piece.get_piece_type() == PieceType::Pawn => {
-                        pawns[color as usize].set_bit(Square64::from(square))
+                        board.pawns[color as usize].set_bit(Square64::from(square))
                     }
                     king if piece.get_piece_type() == PieceType::King => {
-                        big_piece_count[color as usize] += 1;
-                        major_piece_count[color as usize] += 1;
+                        board.big_piece_count[color as usize] += 1;
+                        board.major_piece_count[color as usize] += 1;
 
-                        kings_square[color as usize] = Some(square)
+                        board.kings_square[color as usize] = Some(square)
                     }
                     other_piece => {
-                        big_piece_count[color as usize] += 1;
+                        board.big_piece_count[color as usize] += 1;
                         match other_piece {
                             major if other_piece.is_major() => {
-                                major_piece_count[color as usize] += 1
+                                board.major_piece_count[color as usize] += 1
                             }
-                            minor => minor_piece_count[color as usize] += 1,
+                            minor => board.minor_piece_count[color as usize] += 1,
                         }
                     }
                 }
-                piece_list[piece as usize].push(square);
+                board.piece_list[piece as usize].push(square);
             }
         }
 
-        let board = Board {
-            pieces,
-            pawns,
-            kings_square,
-            piece_count,
-            big_piece_count,
-            major_piece_count,
-            minor_piece_count,
-            material_score,
-            piece_list,
-        };
-
         // NOTE: Basic mode doesn't do any extra board checking and that's probably not going to change
-        if let ValidityCheck::Strict = self.validity_check {
+        if let ValidityCheck::Strict | ValidityCheck::Chess960 = self.validity_check {
             board.check_board(self.validity_check)?;
         }
-        Ok(board)
+        Ok(())
     }
 
     /// Generates the pieces array of a Board struct given a board fen
@@ -195,6 +219,11 @@ impl BoardBuilder {
         rank_fen: &str,
     ) -> Result<[Option<Piece>; File::COUNT], RankFenDeserializeError> {
         match rank_fen {
+            rank_fen if rank_fen.len() > MAX_RANK_FEN_LEN => {
+                Err(RankFenDeserializeError::TooLong {
+                    length: rank_fen.len(),
+                })
+            }
             rank_fen if !rank_fen.is_empty() => {
                 let mut rank = [None; File::COUNT];
                 let mut is_last_char_digit = false;
@@ -286,7 +315,7 @@ impl Default for BoardBuilder {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, Clone)]
 pub struct Board {
     // TODO: Consider making board field private
     pub pieces: [Option<Piece>; NUM_INTERNAL_BOARD_SQUARES],
@@ -301,6 +330,30 @@ pub struct Board {
     pub piece_list: [Vec<Square>; Piece::COUNT],
 }
 
+// NOTE: implemented by hand because the order of each piece_list depends on the order pieces
+// were added and removed in, so the same position reached two ways can have them in different
+// orders. Each square only appears once per list so comparing them as sets is enough.
+impl PartialEq for Board {
+    fn eq(&self, other: &Self) -> bool {
+        self.pieces == other.pieces
+            && self.pawns == other.pawns
+            && self.kings_square == other.kings_square
+            && self.piece_count == other.piece_count
+            && self.big_piece_count == other.big_piece_count
+            && self.major_piece_count == other.major_piece_count
+            && self.minor_piece_count == other.minor_piece_count
+            && self.material_score == other.material_score
+            && self.piece_list.iter().zip(other.piece_list.iter()).all(
+                |(squares, other_squares)| {
+                    squares.len() == other_squares.len()
+                        && squares.iter().all(|square| other_squares.contains(square))
+                },
+            )
+    }
+}
+
+impl Eq for Board {}
+
 /// Returns an a Board with the default starting position in regular chess.
 impl Default for Board {
     fn default() -> Self {
@@ -330,13 +383,111 @@ impl Board {
     }
     //=========================================================================
 
+    /// Total value of the Pieces of the given Color (including its King)
+    pub fn material(&self, color: Color) -> u32 {
+        self.material_score[color as usize]
+    }
+
+    /// White's material minus Black's material. Positive means White is ahead
+    pub fn material_balance(&self) -> i32 {
+        self.material(Color::White) as i32 - self.material(Color::Black) as i32
+    }
+
+    /// Number of Pawns of the given Color on the file
+    pub fn pawns_on_file(&self, color: Color, file: File) -> u8 {
+        BitBoard(self.pawns[color as usize].0 & BitBoard::file_mask(file).0).count_bits()
+    }
+
+    /// Number of Pawns of the given Color on each file, indexed by File
+    pub fn pawn_files(&self, color: Color) -> [u8; File::COUNT] {
+        let mut pawn_files = [0; File::COUNT];
+        for file in File::iter() {
+            pawn_files[file as usize] = self.pawns_on_file(color, file);
+        }
+        pawn_files
+    }
+
+    /// Total value of the Knights, Bishops, Rooks and Queens of both colors
+    fn non_pawn_material(&self) -> u32 {
+        Piece::all()
+            .zip(self.piece_count)
+            .map(|(piece, count)| {
+                if piece.is_big() && !piece.is_king() {
+                    count as u32 * piece.get_value()
+                } else {
+                    0
+                }
+            })
+            .sum()
+    }
+
+    /// How far from the endgame the position is, from 1.0 with all the starting pieces to
+    /// 0.0 once it's an endgame. Used to taper between opening and endgame evaluation
+    pub fn phase_factor(&self) -> f32 {
+        let material = self.non_pawn_material().min(OPENING_PHASE_MATERIAL);
+        let above_endgame = material.saturating_sub(ENDGAME_PHASE_MATERIAL);
+        above_endgame as f32 / (OPENING_PHASE_MATERIAL - ENDGAME_PHASE_MATERIAL) as f32
+    }
+
+    pub fn game_phase(&self) -> Phase {
+        match self.phase_factor() {
+            factor if factor >= OPENING_PHASE_FACTOR => Phase::Opening,
+            factor if factor > 0.0 => Phase::Middlegame,
+            _ => Phase::Endgame,
+        }
+    }
+
+    /// Build a Board from an 8x8 array indexed by Square64 (A1, B1, ..., H8)
+    pub fn from_array64(
+        pieces: [Option<Piece>; NUM_EXTERNAL_BOARD_SQUARES],
+        validity_check: ValidityCheck,
+    ) -> Result<Board, BoardBuildError> {
+        let mut board_builder = BoardBuilder::new();
+        board_builder.validity_check(validity_check);
+
+        for (index, piece) in pieces.into_iter().enumerate() {
+            if let Some(piece) = piece {
+                let square = Square64::try_from(index)
+                    .expect("index into array of NUM_EXTERNAL_BOARD_SQUARES is a valid Square64");
+                board_builder.piece(piece, square);
+            }
+        }
+
+        board_builder.build()
+    }
+
+    /// View of the Board as an 8x8 array indexed by Square64 (A1, B1, ..., H8)
+    pub fn to_array64(&self) -> [Option<Piece>; NUM_EXTERNAL_BOARD_SQUARES] {
+        let mut pieces = [None; NUM_EXTERNAL_BOARD_SQUARES];
+        for (square, piece) in self.iter_pieces() {
+            pieces[Square64::from(square) as usize] = Some(piece);
+        }
+        pieces
+    }
+
+    /// Iterate over the occupied squares from A1 to H8, skipping empty squares and
+    /// the sentinel squares around the 10x12 board
+    pub fn iter_pieces(&self) -> impl Iterator<Item = (Square, Piece)> + '_ {
+        self.pieces
+            .iter()
+            .enumerate()
+            .filter_map(|(index, piece)| {
+                piece.map(|piece| {
+                    let square = Square::try_from(index).expect(
+                        "building the board should guarantee that there are no pieces on invalid squares",
+                    );
+                    (square, piece)
+                })
+            })
+    }
+
     /// Checks the board to make sure that it is consistent with the ValidityCheck/mode
     pub fn check_board(
         &self,
         validity_check: ValidityCheck,
     ) -> Result<(), BoardValidityCheckError> {
         match validity_check {
-            ValidityCheck::Strict => {
+            ValidityCheck::Strict | ValidityCheck::Chess960 => {
                 // check that there is exactly one BlackKing and one WhiteKing
                 if !(self.piece_count[Piece::WhiteKing as usize] == 1
                     && self.piece_count[Piece::BlackKing as usize] == 1)
@@ -368,9 +519,8 @@ impl Board {
 
                 let mut num_excess_big_pieces = [0, 0];
 
-                for (index, piece_count) in self.piece_count.into_iter().enumerate() {
+                for (piece, piece_count) in Piece::all().zip(self.piece_count) {
                     // all piece_counts are less than or equal to max allowed per piece type
-                    let piece = Piece::try_from(index).unwrap(); // index should always be in range 0..12
                     let max_allowed = piece.get_max_num_allowed();
                     if piece_count > max_allowed {
                         return Err(BoardValidityCheckError::StrictExceedsMaxNumForPieceType {
@@ -413,27 +563,37 @@ impl Board {
                     );
                 }
 
-                for (index, piece) in self.pieces.into_iter().enumerate() {
-                    if let Some(piece) = piece {
-                        let square = Square::try_from(index)
-                        .expect("building the board should guarantee that there are no pieces on invalid squares");
-                        match piece {
-                            Piece::WhitePawn => {
-                                // check that there aren't any WhitePawns in first rank
-                                if let Rank::Rank1 = square.get_rank() {
-                                    return Err(
-                                        BoardValidityCheckError::StrictWhitePawnInFirstRank,
-                                    );
-                                }
+                for (square, piece) in self.iter_pieces() {
+                    match piece {
+                        Piece::WhitePawn => match square.get_rank() {
+                            // check that there aren't any WhitePawns in first rank
+                            Rank::Rank1 => {
+                                return Err(BoardValidityCheckError::StrictWhitePawnInFirstRank)
                             }
-                            Piece::BlackPawn => {
-                                // check that there aren't any BlackPawns in last rank
-                                if let Rank::Rank8 = square.get_rank() {
-                                    return Err(BoardValidityCheckError::StrictBlackPawnInLastRank);
-                                }
+                            // or in last rank, since they would have been promoted
+                            Rank::Rank8 => {
+                                return Err(BoardValidityCheckError::StrictPawnInPromotionRank {
+                                    pawn: piece,
+                                    square,
+                                })
                             }
                             _ => (),
-                        }
+                        },
+                        Piece::BlackPawn => match square.get_rank() {
+                            // check that there aren't any BlackPawns in last rank
+                            Rank::Rank8 => {
+                                return Err(BoardValidityCheckError::StrictBlackPawnInLastRank)
+                            }
+                            // or in first rank, since they would have been promoted
+                            Rank::Rank1 => {
+                                return Err(BoardValidityCheckError::StrictPawnInPromotionRank {
+                                    pawn: piece,
+                                    square,
+                                })
+                            }
+                            _ => (),
+                        },
+                        _ => (),
                     }
                 }
             }
@@ -471,9 +631,8 @@ impl Board {
 
                 let mut num_excess_big_pieces = [0, 0];
 
-                for (index, piece_count) in self.piece_count.into_iter().enumerate() {
+                for (piece, piece_count) in Piece::all().zip(self.piece_count) {
                     // all piece_counts are less than or equal to max allowed per piece type
-                    let piece = Piece::try_from(index).unwrap(); // index should always be in range 0..12
                     let max_allowed = piece.get_max_num_allowed();
                     if piece_count > max_allowed {
                         return Err(BoardValidityCheckError::StrictExceedsMaxNumForPieceType {
@@ -516,27 +675,37 @@ impl Board {
                     );
                 }
 
-                for (index, piece) in self.pieces.into_iter().enumerate() {
-                    if let Some(piece) = piece {
-                        let square = Square::try_from(index)
-                        .expect("building the board should guarantee that there are no pieces on invalid squares");
-                        match piece {
-                            Piece::WhitePawn => {
-                                // check that there aren't any WhitePawns in first rank
-                                if let Rank::Rank1 = square.get_rank() {
-                                    return Err(
-                                        BoardValidityCheckError::StrictWhitePawnInFirstRank,
-                                    );
-                                }
+                for (square, piece) in self.iter_pieces() {
+                    match piece {
+                        Piece::WhitePawn => match square.get_rank() {
+                            // check that there aren't any WhitePawns in first rank
+                            Rank::Rank1 => {
+                                return Err(BoardValidityCheckError::StrictWhitePawnInFirstRank)
                             }
-                            Piece::BlackPawn => {
-                                // check that there aren't any BlackPawns in last rank
-                                if let Rank::Rank8 = square.get_rank() {
-                                    return Err(BoardValidityCheckError::StrictBlackPawnInLastRank);
-                                }
+                            // or in last rank, since they would have been promoted
+                            Rank::Rank8 => {
+                                return Err(BoardValidityCheckError::StrictPawnInPromotionRank {
+                                    pawn: piece,
+                                    square,
+                                })
                             }
                             _ => (),
-                        }
+                        },
+                        Piece::BlackPawn => match square.get_rank() {
+                            // check that there aren't any BlackPawns in last rank
+                            Rank::Rank8 => {
+                                return Err(BoardValidityCheckError::StrictBlackPawnInLastRank)
+                            }
+                            // or in first rank, since they would have been promoted
+                            Rank::Rank1 => {
+                                return Err(BoardValidityCheckError::StrictPawnInPromotionRank {
+                                    pawn: piece,
+                                    square,
+                                })
+                            }
+                            _ => (),
+                        },
+                        _ => (),
                     }
                 }
             }
@@ -608,6 +777,60 @@ impl Board {
     //     todo!()
     // }
 
+    /// Render the Board as seen by the player of the given Color, with their pieces at the
+    /// bottom. From Black's side the ranks go 1 to 8 from the top and the files H to A.
+    pub fn to_string_oriented(&self, perspective: Color) -> String {
+        let (ranks, files): (Vec<Rank>, Vec<File>) = match perspective {
+            Color::White => (Rank::iter().rev().collect(), File::iter().collect()),
+            Color::Black => (Rank::iter().collect(), File::iter().rev().collect()),
+        };
+
+        let mut board = String::new();
+        for &rank in &ranks {
+            let row = files
+                .iter()
+                .map(
+                    |&file| match self.pieces[Square::from_file_and_rank(file, rank) as usize] {
+                        Some(piece) => piece.to_string(),
+                        None => ".".to_string(),
+                    },
+                )
+                .collect::<Vec<_>>();
+            // Add rank number at the start of each rank
+            board.push_str(&format!("{}\t{}\n", rank as u8 + 1, row.join("\t")));
+        }
+
+        // Add File legend at the bottom
+        let legend = files
+            .iter()
+            .map(|&file| char::from(file).to_string())
+            .collect::<Vec<_>>();
+        board.push_str(&format!("\n\t{}\n", legend.join("\t")));
+
+        board
+    }
+
+    /// Render the Board from White's side using FEN piece letters (uppercase for White,
+    /// lowercase for Black) and . for empty squares, for places that can't show the Unicode
+    /// pieces used by Display
+    pub fn to_ascii(&self) -> String {
+        let mut board = String::new();
+        for rank in Rank::iter().rev() {
+            let row = File::iter()
+                .map(
+                    |file| match self.pieces[Square::from_file_and_rank(file, rank) as usize] {
+                        Some(piece) => char::from(piece),
+                        None => '.',
+                    },
+                )
+                .map(String::from)
+                .collect::<Vec<_>>();
+            board.push_str(&row.join(" "));
+            board.push('\n');
+        }
+        board
+    }
+
     // /// Places new piece on given square.
     // /// Returns the piece previously occupying square or None if square was empty
     // pub fn add_piece(&mut self, square: Square, piece: Piece) -> Option<Piece> {
@@ -627,66 +850,11 @@ impl Board {
     // }
 }
 
-// TODO: flip board display so black is at the top and white at the bottom
 // TODO: use shorter version of rank and file names
+/// Display from White's point of view, see Board::to_string_oriented for Black's
 impl fmt::Display for Board {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // Reverse ranks so the board is presented like a chess board typically is
-        // with white at the bottom
-        for rank in Rank::iter().rev() {
-            for file in File::iter() {
-                let square = Square::from_file_and_rank(file, rank);
-                let piece = self.pieces[square as usize];
-                match file {
-                    // Add rank number at the start of each rank
-                    File::FileA => {
-                        write!(f, "{}\t", rank as u8 + 1);
-                        match piece {
-                            Some(p) => {
-                                write!(f, "{}\t", p);
-                            }
-                            None => {
-                                write!(f, ".\t");
-                            }
-                        }
-                    }
-                    // Don't add tab at the end of rank
-                    File::FileH => match piece {
-                        Some(p) => {
-                            write!(f, "{}", p);
-                        }
-                        _ => {
-                            write!(f, ".");
-                        }
-                    },
-                    _ => match piece {
-                        Some(p) => {
-                            write!(f, "{}\t", p);
-                        }
-                        _ => {
-                            write!(f, ".\t");
-                        }
-                    },
-                }
-            }
-            if rank != Rank::Rank1 {
-                writeln!(f);
-            }
-        }
-        // Add File legend at the bottom
-        write!(f, "\n\n\t");
-        for file in File::iter() {
-            match file {
-                // Don't add trailing tab
-                File::FileH => {
-                    write!(f, "{}", char::from(file));
-                }
-                _ => {
-                    write!(f, "{}\t", char::from(file));
-                }
-            }
-        }
-        writeln!(f)
+        write!(f, "{}", self.to_string_oriented(Color::White))
     }
 }
 
@@ -727,6 +895,27 @@ mod tests {
 
     //-----------------------------------------------------------------------------
     //============================== Miscellaneous Tests ==========================
+    #[test]
+    fn test_board_pawns_on_file_doubled() {
+        let board = Board::try_from("4k3/pp3ppp/8/8/2P5/2P5/P2P1PPP/4K3").unwrap();
+        let output = (
+            board.pawns_on_file(Color::White, File::FileC),
+            board.pawns_on_file(Color::Black, File::FileC),
+        );
+        let expected = (2, 0);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_board_pawn_files() {
+        let board = Board::try_from("4k3/pp3ppp/8/8/2P5/2P5/P2P1PPP/4K3").unwrap();
+        let output = (
+            board.pawn_files(Color::White),
+            board.pawn_files(Color::Black),
+        );
+        let expected = ([1, 0, 2, 1, 0, 1, 1, 1], [1, 1, 0, 0, 0, 1, 1, 1]);
+        assert_eq!(output, expected);
+    }
 
     //============================== Display ======================================
     #[rustfmt::skip]
@@ -749,6 +938,42 @@ mod tests {
         assert_eq!(output, expected);
     }
 
+    #[rustfmt::skip]
+    #[test]
+    fn test_board_to_string_oriented_black() {
+        let input = Board::try_from("4k3/8/8/8/8/8/P7/4K2R").unwrap();
+        let output = input.to_string_oriented(Color::Black);
+        let expected = format!("{}{}{}{}{}{}{}{}{}",
+                            "1\t♖\t.\t.\t♔\t.\t.\t.\t.\n",
+                            "2\t.\t.\t.\t.\t.\t.\t.\t♙\n",
+                            "3\t.\t.\t.\t.\t.\t.\t.\t.\n",
+                            "4\t.\t.\t.\t.\t.\t.\t.\t.\n",
+                            "5\t.\t.\t.\t.\t.\t.\t.\t.\n",
+                            "6\t.\t.\t.\t.\t.\t.\t.\t.\n",
+                            "7\t.\t.\t.\t.\t.\t.\t.\t.\n",
+                            "8\t.\t.\t.\t♚\t.\t.\t.\t.\n\n",
+                            "\tH\tG\tF\tE\tD\tC\tB\tA\n"
+                        );
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_board_to_ascii() {
+        let input = Board::default();
+        let output = input.to_ascii();
+        let expected = "\
+r n b q k b n r
+p p p p p p p p
+. . . . . . . .
+. . . . . . . .
+. . . . . . . .
+. . . . . . . .
+P P P P P P P P
+R N B Q K B N R
+";
+        assert_eq!(output, expected);
+    }
+
     //-----------------------------------------------------------------------------
     //============================== Basic Board Building =========================
 
@@ -819,6 +1044,94 @@ mod tests {
         assert_eq!(output, expected);
     }
 
+    #[test]
+    fn test_board_iter_pieces_default() {
+        let board = Board::default();
+        let output = board.iter_pieces().count();
+        let expected = 32;
+        assert_eq!(output, expected);
+
+        let output = board.iter_pieces().next();
+        let expected = Some((Square::A1, Piece::WhiteRook));
+        assert_eq!(output, expected);
+
+        let output = board.iter_pieces().last();
+        let expected = Some((Square::H8, Piece::BlackRook));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_board_game_phase_default() {
+        let board = Board::default();
+        let output = (board.game_phase(), board.phase_factor());
+        let expected = (Phase::Opening, 1.0);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_board_game_phase_kings_and_pawns() {
+        let board = Board::try_from("4k3/pppp4/8/8/8/8/4PPPP/4K3").unwrap();
+        let output = (board.game_phase(), board.phase_factor());
+        let expected = (Phase::Endgame, 0.0);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_board_game_phase_queens_traded() {
+        let board = Board::try_from("rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNB1KBNR").unwrap();
+        let output = board.game_phase();
+        let expected = Phase::Middlegame;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_board_material_balance_default() {
+        let board = Board::default();
+        let output = board.material_balance();
+        let expected = 0;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_board_material_balance_missing_black_knight() {
+        let board = Board::try_from("r1bqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR").unwrap();
+        let output = board.material_balance();
+        let expected = 325;
+        assert_eq!(output, expected);
+
+        let output = board.material(Color::Black);
+        let expected = board.material(Color::White) - 325;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_board_array64_round_trip() {
+        let input =
+            Board::try_from("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R").unwrap();
+        let output = Board::from_array64(input.to_array64(), ValidityCheck::Strict).unwrap();
+        let expected = input;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_board_eq_ignores_piece_list_order() {
+        let mut output = Board::default();
+        output.piece_list[Piece::WhitePawn as usize].reverse();
+        output.piece_list[Piece::BlackKnight as usize].swap(0, 1);
+        let expected = Board::default();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_board_eq_different_piece_lists() {
+        let board = Board::default();
+        let mut other = Board::default();
+        other.piece_list[Piece::WhiteRook as usize][0] = Square::A2;
+        let output = board == other;
+        let expected = false;
+        assert_eq!(output, expected);
+    }
+
     #[test]
     fn test_board_build_piece_on_invalid_square() {
         #[rustfmt::skip]
@@ -928,8 +1241,8 @@ mod tests {
             .piece(Piece::BlackRook, Square64::G8)
             .piece(Piece::BlackKing, Square64::H8);
 
-        for i in 8_u8..=15 {
-            output.piece(Piece::WhiteQueen, Square64::try_from(i).unwrap());
+        for square in Square64::all().filter(|square| square.get_rank() == Rank::Rank2) {
+            output.piece(Piece::WhiteQueen, square);
         }
         let output = output.build();
 
@@ -980,8 +1293,8 @@ mod tests {
             .piece(Piece::WhiteKnight, Square64::G1)
             .piece(Piece::BlackKing, Square64::D8);
 
-        for i in 8_u8..=15 {
-            output.piece(Piece::WhitePawn, Square64::try_from(i).unwrap());
+        for square in Square64::all().filter(|square| square.get_rank() == Rank::Rank2) {
+            output.piece(Piece::WhitePawn, square);
         }
 
         let output = output.build();
@@ -1010,6 +1323,44 @@ mod tests {
         assert_eq!(output, expected);
     }
 
+    #[test]
+    fn test_board_build_strict_validity_check_invalid_white_pawn_in_rank_8() {
+        let output = BoardBuilder::new_with_fen("P3k3/8/8/8/8/8/8/4K3")
+            .unwrap()
+            .build();
+        let expected = Err(BoardBuildError::BoardValidityCheck(
+            BoardValidityCheckError::StrictPawnInPromotionRank {
+                pawn: Piece::WhitePawn,
+                square: Square::A8,
+            },
+        ));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_board_build_strict_validity_check_invalid_black_pawn_in_rank_1() {
+        let output = BoardBuilder::new_with_fen("4k3/8/8/8/8/8/8/4K2p")
+            .unwrap()
+            .build();
+        let expected = Err(BoardBuildError::BoardValidityCheck(
+            BoardValidityCheckError::StrictPawnInPromotionRank {
+                pawn: Piece::BlackPawn,
+                square: Square::H1,
+            },
+        ));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_board_build_basic_validity_check_allows_pawn_in_promotion_rank() {
+        let output = BoardBuilder::new_with_fen("P3k3/8/8/8/8/8/8/4K2p")
+            .unwrap()
+            .validity_check(ValidityCheck::Basic)
+            .build()
+            .is_ok();
+        assert!(output);
+    }
+
     #[test]
     fn test_board_build_strict_validity_check_invalid_black_pawn_in_rank_8() {
         let output = BoardBuilder::new()
@@ -1170,6 +1521,14 @@ mod tests {
         assert_eq!(output, expected);
     }
 
+    #[test]
+    fn test_get_rank_from_fen_invalid_too_long() {
+        let input = "p".repeat(1_000);
+        let output = BoardBuilder::rank_from_fen(&input);
+        let expected = Err(RankFenDeserializeError::TooLong { length: 1_000 });
+        assert_eq!(output, expected);
+    }
+
     #[test]
     fn test_get_rank_from_fen_invalid_too_few_squares() {
         let input = "rn2kb";
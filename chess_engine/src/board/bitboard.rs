@@ -1,4 +1,5 @@
 use crate::{
+    color::Color,
     file::File,
     rank::Rank,
     square::{Square, Square64},
@@ -23,6 +24,27 @@ use strum_macros::EnumIter;
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct BitBoard(pub u64);
 
+/// Every square on the A file. Shifting it left by a file's index gives that file instead.
+const FILE_A: u64 = 0x01_01_01_01_01_01_01_01;
+/// Every square on the H file
+const FILE_H: u64 = FILE_A << 7;
+/// Every square on the first rank. Shifting it left by 8 times a rank's index gives that rank.
+const RANK_1: u64 = 0xFF;
+
+/// Directions a BitBoard can be shifted in, seen from White's side of the board (North is
+/// towards the 8th rank)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, EnumIter)]
+pub enum Direction {
+    North,
+    South,
+    East,
+    West,
+    NorthEast,
+    NorthWest,
+    SouthEast,
+    SouthWest,
+}
+
 // https://stackoverflow.com/questions/30680559/how-to-find-magic-bitboards
 // TODO: generate own Magic Bitboard and implement
 // const BIT_TABLE: [Square; NUM_EXTERNAL_BOARD_SQUARES = [
@@ -54,26 +76,30 @@ impl BitBoard {
         // count
     }
 
-    /// Sets the first set LSB to 0 and returns the index corresponding to it
-    // NOTE: this is slow in comparison to magic bitboard implementation which
-    // has a very real effect on performance of move generation and thus on bot ability
-    fn pop_bit(&mut self) -> Option<Square64> {
+    /// Returns the square of the least significant set bit without clearing it
+    pub fn lsb(&self) -> Option<Square64> {
         let lsb_index = self.0.trailing_zeros();
         match lsb_index {
             // all zeros
             64 => None,
-            _ => {
-                let mask: u64 = 1 << lsb_index;
-                self.0 ^= mask;
-                Some(
-                    lsb_index
-                        .try_into()
-                        .expect("lsb_index should be in range 0..=63"),
-                )
-            }
+            _ => Some(
+                lsb_index
+                    .try_into()
+                    .expect("lsb_index should be in range 0..=63"),
+            ),
         }
     }
 
+    /// Sets the first set LSB to 0 and returns the index corresponding to it
+    // NOTE: this is slow in comparison to magic bitboard implementation which
+    // has a very real effect on performance of move generation and thus on bot ability
+    pub fn pop_lsb(&mut self) -> Option<Square64> {
+        let lsb = self.lsb()?;
+        // NOTE: clears the lowest set bit
+        self.0 &= self.0 - 1;
+        Some(lsb)
+    }
+
     // TODO: implement magic bitboard version
     // // Relies on Magic BitBoard (see BIT_TABLE for more information)
     // fn pop_bit(&mut self) -> Square {
@@ -85,9 +111,32 @@ impl BitBoard {
 
     /// Check if bit at index is set
     pub fn check_bit(&self, square: Square64) -> bool {
+        self.is_set(square)
+    }
+
+    /// Check if the bit for the provided square is set
+    pub fn is_set(&self, square: Square64) -> bool {
         self.0 & (1 << (square as u8)) != 0
     }
 
+    /// Moves every set bit one square in the provided direction. Bits that would wrap around
+    /// to the other side of the board or fall off it are dropped.
+    pub fn shift(&self, direction: Direction) -> BitBoard {
+        let not_file_a = !FILE_A;
+        let not_file_h = !FILE_H;
+        let b = self.0;
+        BitBoard(match direction {
+            Direction::North => b << 8,
+            Direction::South => b >> 8,
+            Direction::East => (b & not_file_h) << 1,
+            Direction::West => (b & not_file_a) >> 1,
+            Direction::NorthEast => (b & not_file_h) << 9,
+            Direction::NorthWest => (b & not_file_a) << 7,
+            Direction::SouthEast => (b & not_file_h) >> 7,
+            Direction::SouthWest => (b & not_file_a) >> 9,
+        })
+    }
+
     /// Sets bit at index
     pub fn set_bit(&mut self, square: Square64) {
         self.0 |= 1 << (square as u8);
@@ -101,6 +150,63 @@ impl BitBoard {
             self.0 ^= 1 << (square as u8);
         }
     }
+
+    //=============================== MASKS ===================================
+
+    /// Every square on the provided file
+    pub fn file_mask(file: File) -> BitBoard {
+        BitBoard(FILE_A << file as u8)
+    }
+
+    /// Every square on the provided rank
+    pub fn rank_mask(rank: Rank) -> BitBoard {
+        BitBoard(RANK_1 << (8 * rank as u8))
+    }
+
+    /// Every square on the files right next to the provided file
+    pub fn adjacent_files_mask(file: File) -> BitBoard {
+        BitBoard(
+            file.adjacent()
+                .fold(0, |mask, adjacent| mask | Self::file_mask(adjacent).0),
+        )
+    }
+
+    /// Every square on the ranks in front of the provided rank, from the provided color's
+    /// side of the board (i.e. the ranks its pawns still have to cross)
+    pub fn ranks_ahead_mask(rank: Rank, color: Color) -> BitBoard {
+        match color {
+            Color::White => BitBoard(u64::MAX.checked_shl(8 * (rank as u32 + 1)).unwrap_or(0)),
+            Color::Black => BitBoard((1 << (8 * rank as u32)) - 1),
+        }
+    }
+}
+
+/// Iterator over the set squares of a BitBoard, from A1 to H8
+#[derive(Debug, Clone)]
+pub struct Squares(BitBoard);
+
+impl Iterator for Squares {
+    type Item = Square64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop_lsb()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let count = self.0.count_bits() as usize;
+        (count, Some(count))
+    }
+}
+
+impl ExactSizeIterator for Squares {}
+
+impl IntoIterator for BitBoard {
+    type Item = Square64;
+    type IntoIter = Squares;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Squares(self)
+    }
 }
 
 impl From<u64> for BitBoard {
@@ -224,9 +330,9 @@ mod tests {
     }
 
     #[test]
-    fn test_pop_bit_single_set_bit() {
+    fn test_pop_lsb_single_set_bit() {
         let mut input = BitBoard(0x80_00_00_00_00_00_00_00);
-        let output = input.pop_bit();
+        let output = input.pop_lsb();
         let expected_index = Some(Square64::H8);
         let expected_board = BitBoard(0);
         assert_eq!(output, expected_index);
@@ -234,9 +340,9 @@ mod tests {
     }
 
     #[test]
-    fn test_pop_bit_multiple_set_bit() {
+    fn test_pop_lsb_multiple_set_bit() {
         let mut input = BitBoard(0x0C_0F_00_D0_00_00_01_00);
-        let output = input.pop_bit();
+        let output = input.pop_lsb();
         let expected_index = Some(Square64::A2);
         let expected_board = BitBoard(0x0C_0F_00_D0_00_00_00_00);
         assert_eq!(output, expected_index);
@@ -244,9 +350,214 @@ mod tests {
     }
 
     #[test]
-    fn test_pop_bit_empty_board() {
+    fn test_is_set_all_set() {
+        let input = BitBoard(u64::MAX);
+        let output = Square64::iter().all(|square| input.is_set(square));
+        let expected = true;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_is_set_empty() {
+        let input = BitBoard(0);
+        let output = Square64::iter().any(|square| input.is_set(square));
+        let expected = false;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_lsb_does_not_clear_bit() {
+        let input = BitBoard(0x0C_0F_00_D0_00_00_01_00);
+        let output = input.lsb();
+        let expected = Some(Square64::A2);
+        assert_eq!(output, expected);
+        assert_eq!(input, BitBoard(0x0C_0F_00_D0_00_00_01_00));
+    }
+
+    #[test]
+    fn test_lsb_all_set() {
+        let input = BitBoard(u64::MAX);
+        let output = input.lsb();
+        let expected = Some(Square64::A1);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_lsb_empty() {
+        let input = BitBoard(0);
+        let output = input.lsb();
+        let expected = None;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_pop_lsb_all_set() {
+        let mut input = BitBoard(u64::MAX);
+        let output = input.pop_lsb();
+        let expected_index = Some(Square64::A1);
+        let expected_board = BitBoard(u64::MAX - 1);
+        assert_eq!(output, expected_index);
+        assert_eq!(input, expected_board);
+    }
+
+    #[test]
+    fn test_shift_single_square() {
+        // from d4
+        let input = BitBoard(1 << Square64::D4 as u8);
+        let output: Vec<Square64> = Direction::iter()
+            .map(|direction| {
+                input
+                    .shift(direction)
+                    .lsb()
+                    .expect("d4 should stay on the board")
+            })
+            .collect();
+        let expected = vec![
+            Square64::D5,
+            Square64::D3,
+            Square64::E4,
+            Square64::C4,
+            Square64::E5,
+            Square64::C5,
+            Square64::E3,
+            Square64::C3,
+        ];
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_shift_does_not_wrap_around() {
+        let a_file = BitBoard::file_mask(File::FileA);
+        let h_file = BitBoard::file_mask(File::FileH);
+        let output = (
+            a_file.shift(Direction::West),
+            a_file.shift(Direction::NorthWest),
+            a_file.shift(Direction::SouthWest),
+            h_file.shift(Direction::East),
+            h_file.shift(Direction::NorthEast),
+            h_file.shift(Direction::SouthEast),
+        );
+        let expected = (
+            BitBoard(0),
+            BitBoard(0),
+            BitBoard(0),
+            BitBoard(0),
+            BitBoard(0),
+            BitBoard(0),
+        );
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_shift_all_set() {
+        let input = BitBoard(u64::MAX);
+        let output: Vec<u8> = Direction::iter()
+            .map(|direction| input.shift(direction).count_bits())
+            .collect();
+        // straight shifts lose a rank or file, diagonal shifts lose both
+        let expected = vec![56, 56, 56, 56, 49, 49, 49, 49];
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_shift_empty() {
+        let input = BitBoard(0);
+        let output = Direction::iter().all(|direction| input.shift(direction) == BitBoard(0));
+        let expected = true;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_into_iter() {
+        let input = BitBoard(0x80_00_00_00_10_00_01_01);
+        let output: Vec<Square64> = input.into_iter().collect();
+        let expected = vec![Square64::A1, Square64::A2, Square64::E4, Square64::H8];
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_into_iter_all_set() {
+        let input = BitBoard(u64::MAX);
+        let output: Vec<Square64> = input.into_iter().collect();
+        let expected: Vec<Square64> = Square64::iter().collect();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_into_iter_empty() {
+        let input = BitBoard(0);
+        let output = input.into_iter().len();
+        let expected = 0;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_file_mask() {
+        let output = BitBoard::file_mask(File::FileC);
+        let expected = BitBoard(0x04_04_04_04_04_04_04_04);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_rank_mask() {
+        let output = (
+            BitBoard::rank_mask(Rank::Rank1),
+            BitBoard::rank_mask(Rank::Rank6),
+        );
+        let expected = (
+            BitBoard(0x00_00_00_00_00_00_00_FF),
+            BitBoard(0x00_00_FF_00_00_00_00_00),
+        );
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_adjacent_files_mask() {
+        let output = BitBoard::adjacent_files_mask(File::FileC);
+        let expected = BitBoard(0x0A_0A_0A_0A_0A_0A_0A_0A);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_adjacent_files_mask_edge_files() {
+        let output = (
+            BitBoard::adjacent_files_mask(File::FileA),
+            BitBoard::adjacent_files_mask(File::FileH),
+        );
+        let expected = (
+            BitBoard(0x02_02_02_02_02_02_02_02),
+            BitBoard(0x40_40_40_40_40_40_40_40),
+        );
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_ranks_ahead_mask() {
+        let output = (
+            BitBoard::ranks_ahead_mask(Rank::Rank6, Color::White),
+            BitBoard::ranks_ahead_mask(Rank::Rank3, Color::Black),
+        );
+        let expected = (
+            BitBoard(0xFF_FF_00_00_00_00_00_00),
+            BitBoard(0x00_00_00_00_00_00_FF_FF),
+        );
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_ranks_ahead_mask_last_rank_is_empty() {
+        let output = (
+            BitBoard::ranks_ahead_mask(Rank::Rank8, Color::White),
+            BitBoard::ranks_ahead_mask(Rank::Rank1, Color::Black),
+        );
+        let expected = (BitBoard(0), BitBoard(0));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_pop_lsb_empty_board() {
         let mut input = BitBoard(0);
-        let output = input.pop_bit();
+        let output = input.pop_lsb();
         let expected = None;
         assert_eq!(output, expected);
     }
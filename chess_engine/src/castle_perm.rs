@@ -1,5 +1,5 @@
 use crate::{board::NUM_INTERNAL_BOARD_SQUARES, error::CastlePermConversionError, square::Square};
-use std::fmt;
+use std::{fmt, str::FromStr};
 use strum::{EnumCount, IntoEnumIterator};
 use strum_macros::{Display as EnumDisplay, EnumCount as EnumCountMacro, EnumIter, EnumString};
 
@@ -64,6 +64,13 @@ impl CastlePerm {
     }
 
     fn from_fen(value: &str) -> Result<Self, CastlePermConversionError> {
+        // checked first so the error doesn't copy arbitrarily long input
+        if value.len() > MAX_CASTLE_PERM_FEN_LEN {
+            return Err(CastlePermConversionError::FromStrTooLong {
+                length: value.len(),
+            });
+        }
+
         let mut castle_perm = CastlePerm::new();
         for char in value.chars() {
             match char {
@@ -156,6 +163,13 @@ impl TryFrom<&str> for CastlePerm {
     }
 }
 
+impl FromStr for CastlePerm {
+    type Err = CastlePermConversionError;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::try_from(value)
+    }
+}
+
 /// Display in FEN style
 impl fmt::Display for CastlePerm {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -289,12 +303,28 @@ mod tests {
     fn test_castle_perm_try_from_str_too_many_chars() {
         let input = "KQkqK";
         let output = CastlePerm::try_from(input);
+        let expected = Err(CastlePermConversionError::FromStrTooLong { length: 5 });
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_castle_perm_try_from_str_duplicates() {
+        let input = "qqqq";
+        let output = CastlePerm::try_from(input);
         let expected = Err(CastlePermConversionError::FromStrDuplicates {
             invalid_string: input.to_owned(),
         });
         assert_eq!(output, expected);
     }
 
+    #[test]
+    fn test_castle_perm_try_from_str_very_long() {
+        let input = "K".repeat(1_000);
+        let output = CastlePerm::try_from(input.as_str());
+        let expected = Err(CastlePermConversionError::FromStrTooLong { length: 1_000 });
+        assert_eq!(output, expected);
+    }
+
     #[test]
     fn test_castle_perm_try_from_str_dupe_dash() {
         let input = "--";
@@ -309,9 +339,7 @@ mod tests {
     fn test_castle_perm_try_from_str_dupe_dash_and_too_long() {
         let input = "KQkq--";
         let output = CastlePerm::try_from(input);
-        let expected = Err(CastlePermConversionError::FromStr {
-            invalid_string: input.to_owned(),
-        });
+        let expected = Err(CastlePermConversionError::FromStrTooLong { length: 6 });
         assert_eq!(output, expected);
     }
 
@@ -338,4 +366,15 @@ mod tests {
         let expected = "Qk";
         assert_eq!(output, expected);
     }
+
+    #[test]
+    fn test_castle_perm_from_str_display_round_trip() {
+        for value in 0..NUM_CASTLE_PERM as u8 {
+            let input = CastlePerm(value);
+            let output = input.to_string().parse::<CastlePerm>();
+            let expected = Ok(input);
+            assert_eq!(output, expected);
+            assert_eq!(input.to_string(), CASTLE_PERM_FENS[value as usize]);
+        }
+    }
 }
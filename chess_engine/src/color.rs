@@ -17,12 +17,17 @@ impl From<Color> for char {
 }
 
 impl Color {
-    pub fn toggle(&mut self) {
+    /// The other Color, returned by value. Use toggle to flip a Color in place.
+    pub fn opponent(self) -> Color {
         match self {
-            Color::White => *self = Color::Black,
-            Color::Black => *self = Color::White,
+            Color::White => Color::Black,
+            Color::Black => Color::White,
         }
     }
+
+    pub fn toggle(&mut self) {
+        *self = self.opponent();
+    }
 }
 
 #[cfg(test)]
@@ -45,4 +50,18 @@ mod tests {
         let expected = Color::White;
         assert_eq!(output, expected);
     }
+
+    #[test]
+    fn test_color_opponent_white() {
+        let output = Color::White.opponent();
+        let expected = Color::Black;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_color_opponent_black() {
+        let output = Color::Black.opponent();
+        let expected = Color::White;
+        assert_eq!(output, expected);
+    }
 }
@@ -0,0 +1,161 @@
+use crate::{error::EpdError, gamestate::Gamestate, moves::Move, pgn::san_to_move};
+
+// CONSTANTS:
+/// EPD has no halfmove clock or fullmove count so these are used to build the Gamestate
+const EPD_MOVE_COUNTERS: &str = "0 1";
+
+/// A test position from an EPD (Extended Position Description) line. Only the operations
+/// needed to check the search are kept: bm (best moves), am (moves to avoid) and id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpdRecord {
+    /// FEN of the position, using EPD_MOVE_COUNTERS for the missing fields
+    pub fen: String,
+    pub id: Option<String>,
+    pub best_moves: Vec<Move>,
+    pub avoid_moves: Vec<Move>,
+}
+
+impl EpdRecord {
+    pub fn gamestate(&self) -> Result<Gamestate, EpdError> {
+        Ok(Gamestate::try_from(self.fen.as_str())?)
+    }
+
+    /// Whether playing move_ solves the position: it has to be one of the best moves (if
+    /// any are given) and none of the moves to avoid
+    pub fn is_solved_by(&self, move_: Move) -> bool {
+        (self.best_moves.is_empty() || self.best_moves.contains(&move_))
+            && !self.avoid_moves.contains(&move_)
+    }
+}
+
+/// Parse every EPD line in the string, skipping blank lines and lines starting with #
+pub fn parse_epd_file(epd: &str) -> Result<Vec<EpdRecord>, EpdError> {
+    epd.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(parse_epd)
+        .collect()
+}
+
+/// Parse a line like: 6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - bm Rd8#; id "back rank";
+/// Moves in bm and am are given in SAN
+pub fn parse_epd(line: &str) -> Result<EpdRecord, EpdError> {
+    let mut fields = line.trim().splitn(5, ' ');
+    let position = fields.by_ref().take(4).collect::<Vec<_>>();
+    if position.len() < 4 {
+        return Err(EpdError::MissingFields {
+            line: line.to_string(),
+        });
+    }
+
+    let fen = format!("{} {EPD_MOVE_COUNTERS}", position.join(" "));
+    let mut gamestate = Gamestate::try_from(fen.as_str())?;
+    let mut record = EpdRecord {
+        fen,
+        id: None,
+        best_moves: Vec::new(),
+        avoid_moves: Vec::new(),
+    };
+
+    let operations = fields.next().unwrap_or_default();
+    for operation in split_operations(operations)? {
+        let (opcode, operands) = operation.split_once(' ').unwrap_or((&operation, ""));
+        match opcode {
+            "bm" | "am" => {
+                let moves = operands
+                    .split_whitespace()
+                    .map(|san| san_to_move(&mut gamestate, san))
+                    .collect::<Result<Vec<_>, _>>()?;
+                match opcode {
+                    "bm" => record.best_moves = moves,
+                    _ => record.avoid_moves = moves,
+                }
+            }
+            "id" => record.id = Some(operands.trim().trim_matches('"').to_string()),
+            // NOTE: other opcodes (c0, dm, acd, ...) aren't needed yet
+            _ => {}
+        }
+    }
+
+    Ok(record)
+}
+
+/// Split the operations section on semicolons, ignoring any inside quoted strings
+fn split_operations(operations: &str) -> Result<Vec<String>, EpdError> {
+    let mut split = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    for c in operations.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                current.push(c);
+            }
+            ';' if !in_quotes => {
+                split.push(current.trim().to_string());
+                current.clear();
+            }
+            _ => current.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(EpdError::InvalidOperation {
+            operation: current.trim().to_string(),
+        });
+    }
+    if !current.trim().is_empty() {
+        split.push(current.trim().to_string());
+    }
+    split.retain(|operation| !operation.is_empty());
+
+    Ok(split)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{piece::Piece, square::Square};
+
+    #[test]
+    fn test_parse_epd_bm_and_id() {
+        let output = parse_epd(r#"6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - bm Rd8#; id "back; rank";"#);
+        let expected = Ok(EpdRecord {
+            fen: "6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1".to_string(),
+            id: Some("back; rank".to_string()),
+            best_moves: vec![Move::new(
+                Square::D1,
+                Square::D8,
+                None,
+                false,
+                false,
+                None,
+                false,
+                Piece::WhiteRook,
+            )],
+            avoid_moves: Vec::new(),
+        });
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_parse_epd_am() {
+        let record = parse_epd("4k3/2p5/3p4/8/8/8/8/3QK3 w - - am Qxd6;").unwrap();
+        let mut gamestate = record.gamestate().unwrap();
+        let blunder = san_to_move(&mut gamestate, "Qxd6").unwrap();
+        let safe = san_to_move(&mut gamestate, "Qd2").unwrap();
+
+        assert!(!record.is_solved_by(blunder));
+        assert!(record.is_solved_by(safe));
+    }
+
+    #[test]
+    fn test_parse_epd_missing_fields() {
+        let output = parse_epd("6k1/5ppp/8/8/8/8/5PPP/3R2K1 w -");
+        let expected = Err(EpdError::MissingFields {
+            line: "6k1/5ppp/8/8/8/8/5PPP/3R2K1 w -".to_string(),
+        });
+        assert_eq!(output, expected);
+    }
+}
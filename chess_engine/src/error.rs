@@ -1,10 +1,11 @@
 use std::num::ParseIntError;
 
 use crate::{
-    board::bitboard::BitBoard,
+    board::{bitboard::BitBoard, MAX_RANK_FEN_LEN},
+    castle_perm::{Castle, MAX_CASTLE_PERM_FEN_LEN},
     color::Color,
     file::File,
-    gamestate::{HALF_MOVE_MAX, MAX_GAME_MOVES, NUM_FEN_SECTIONS},
+    gamestate::{HALF_MOVE_STRICT_MAX, MAX_FEN_LEN, MAX_GAME_MOVES, NUM_FEN_SECTIONS},
     moves::Move,
     piece::Piece,
     rank::Rank,
@@ -36,6 +37,9 @@ pub enum UndoMoveError {
     #[error(transparent)]
     ClearPiece(#[from] ClearPieceError),
 
+    #[error(transparent)]
+    MovePiece(#[from] MovePieceError),
+
     #[error(
         "Attempted to undo a Move, but even the initial state dummy Move was not found in history"
     )]
@@ -62,6 +66,9 @@ pub enum MakeMoveError {
     #[error(transparent)]
     ClearPiece(#[from] ClearPieceError),
 
+    #[error(transparent)]
+    AddPiece(#[from] AddPieceError),
+
     // TODO: figure out why I have to do this right now?
     #[error(transparent)]
     MoveDeserialize(#[from] MoveDeserializeError),
@@ -77,6 +84,18 @@ pub enum MakeMoveError {
 
     #[error("Cannot move into position that would put the moving side in check")]
     MoveWouldPutMovingSideInCheck,
+
+    #[error("It is {expected}'s turn but the move is for {found}")]
+    WrongColorToMove { expected: Color, found: Color },
+
+    #[error("Move {move_} is not legal in the current position")]
+    IllegalMove { move_: Move },
+
+    #[error("Move {index}: {uci} is not a legal move in UCI notation")]
+    InvalidUciMove { index: usize, uci: String },
+
+    #[error(transparent)]
+    MoveGen(#[from] MoveGenError),
 }
 
 #[derive(Error, Debug, PartialEq)]
@@ -181,7 +200,7 @@ pub enum MoveValidityError {
     },
 
     #[error(
-        "The square {start_square} is not a valid end square for castling with the {piece_moved}"
+        "The square {start_square} is not a valid start square for castling with the {piece_moved}"
     )]
     CastleStartSquare {
         start_square: Square,
@@ -233,6 +252,120 @@ pub enum MoveGenError {
     GamestateValidityCheck(#[from] GamestateValidityCheckError),
 }
 
+#[derive(Error, Debug, PartialEq)]
+pub enum PgnError {
+    #[error("Could not parse SAN move: {san}")]
+    InvalidSan { san: String },
+
+    #[error("SAN move {san} does not match any legal move")]
+    IllegalMove { san: String },
+
+    #[error("SAN move {san} matches more than one legal move")]
+    AmbiguousMove { san: String },
+
+    #[error("Tag pair {tag} is malformed")]
+    InvalidTag { tag: String },
+
+    #[error("Move {index}: {san} could not be played")]
+    SanMove {
+        index: usize,
+        san: String,
+        #[source]
+        source: Box<PgnError>,
+    },
+
+    #[error(transparent)]
+    GamestateBuild(#[from] GamestateBuildError),
+
+    #[error(transparent)]
+    MoveGen(#[from] MoveGenError),
+
+    #[error(transparent)]
+    MakeMove(#[from] MakeMoveError),
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum OpeningBookError {
+    #[error("Could not read opening book directory {path}: {message}")]
+    ReadDir { path: String, message: String },
+
+    #[error("Could not read PGN file {path}: {message}")]
+    ReadFile { path: String, message: String },
+
+    #[error(transparent)]
+    Pgn(#[from] PgnError),
+
+    #[error(transparent)]
+    GamestateBuild(#[from] GamestateBuildError),
+
+    #[error(transparent)]
+    MakeMove(#[from] MakeMoveError),
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum EpdError {
+    #[error("EPD line should start with 4 position fields: {line}")]
+    MissingFields { line: String },
+
+    #[error("Invalid EPD operation: {operation}")]
+    InvalidOperation { operation: String },
+
+    #[error(transparent)]
+    GamestateBuild(#[from] GamestateBuildError),
+
+    #[error(transparent)]
+    Pgn(#[from] PgnError),
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ZobristKeysError {
+    #[error("Zobrist {table} keys should have {expected} entries but have {found}")]
+    WrongNumKeys {
+        table: &'static str,
+        expected: usize,
+        found: usize,
+    },
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum GameBytesError {
+    #[error("Game bytes should start with the magic header {expected:?}")]
+    InvalidMagic { expected: [u8; 4] },
+
+    #[error("Game bytes version {version} is not supported, expected {expected}")]
+    UnsupportedVersion { version: u8, expected: u8 },
+
+    #[error("Game bytes could not be decoded at offset {offset}")]
+    Decode { offset: usize },
+
+    #[error("Game bytes length prefix at offset {offset} is {length} but only {remaining} bytes are left")]
+    LengthTooLong {
+        offset: usize,
+        length: usize,
+        remaining: usize,
+    },
+
+    #[error("Game bytes have {count} unexpected bytes after the moves")]
+    TrailingBytes { count: usize },
+
+    #[error("Move {index} of the game bytes is invalid")]
+    InvalidMove {
+        index: usize,
+        #[source]
+        source: MoveValidityError,
+    },
+
+    #[error("Move {index} of the game bytes could not be played")]
+    IllegalMove {
+        index: usize,
+        #[source]
+        source: MakeMoveError,
+    },
+
+    #[error(transparent)]
+    GamestateBuild(#[from] GamestateBuildError),
+}
+
 #[derive(Error, Debug, PartialEq)]
 pub enum RankFenDeserializeError {
     #[error("Failed to deserialize pieces of rank from rank fen due to invalid char")]
@@ -241,6 +374,12 @@ pub enum RankFenDeserializeError {
     #[error("Rank FEN is empty")]
     Empty,
 
+    #[error(
+        "Rank FEN is {length} characters long, more than the {} allowed",
+        MAX_RANK_FEN_LEN
+    )]
+    TooLong { length: usize },
+
     #[error(
         "Rank FEN {rank_fen} should represent {} squares but does not",
         File::COUNT
@@ -287,13 +426,9 @@ pub enum BoardValidityCheckError {
     },
 
     #[error(
-        "A player has more excess big pieces than missing pawns which is not allowed:
-            \nWhite:
-            \nNumber of excess big pieces: {num_excess_big_pieces_white}
-            \nNumber of missing pawns: {num_missing_pawns_white}
-            \nBlack:
-            \nNumber of excess big pieces: {num_excess_big_pieces_black}
-            \nNumber of missing pawns: {num_missing_pawns_black}"
+        "A player has more excess big pieces than missing pawns which is not allowed. \
+        White has {num_excess_big_pieces_white} excess big pieces and {num_missing_pawns_white} missing pawns, \
+        Black has {num_excess_big_pieces_black} excess big pieces and {num_missing_pawns_black} missing pawns"
     )]
     StrictMoreExcessBigPiecesThanMissingPawns {
         num_excess_big_pieces_white: u8,
@@ -308,10 +443,13 @@ pub enum BoardValidityCheckError {
     #[error("Board has a BlackPawn in Rank8 which is not a valid position")]
     StrictBlackPawnInLastRank,
 
+    #[error("Board has a {pawn} on {square}, which is the rank it should have been promoted on")]
+    StrictPawnInPromotionRank { pawn: Piece, square: Square },
+
     #[error(
-        "Board has Kings less than 2 squares apart from each other which is not allowed. 
-    WhiteKing is at Square {white_king_square}, BlackKing is at Square{black_king_square} 
-    and the distance between them is {kings_distance}"
+        "Board has Kings less than 2 squares apart from each other which is not allowed. \
+        WhiteKing is at Square {white_king_square}, BlackKing is at Square {black_king_square} \
+        and the distance between them is {kings_distance}"
     )]
     StrictKingsLessThanTwoSquaresApart {
         white_king_square: Square,
@@ -381,12 +519,24 @@ pub enum GamestateValidityCheckError {
         expected_piece: Piece,
     },
 
+    #[error("En passant square {en_passant_square} is impossible since the King to move is checked by {checker}, which the double pawn push can't explain")]
+    StrictEnPassantImpossibleCheck {
+        en_passant_square: Square,
+        checker: Square,
+    },
+
+    #[error("Castle permission {castle} is impossible since the King is not on a square it could castle from")]
+    StrictCastlePermKingMoved { castle: Castle },
+
+    #[error("Castle permission {castle} is impossible since there is no Rook to castle with")]
+    StrictCastlePermRookMissing { castle: Castle },
+
     #[error("En passant square has rank {rank} which is impossible given color {active_color}. Only valid combinations are (White, 6) and (Black, 3)")]
     StrictColorRankMismatch { active_color: Color, rank: Rank },
 
     #[error(
-        "Halfmove clock: {halfmove_clock} should be in range 0..{}",
-        HALF_MOVE_MAX
+        "Halfmove clock: {halfmove_clock} should be in range 0..={}",
+        HALF_MOVE_STRICT_MAX
     )]
     StrictHalfmoveClockExceedsMax { halfmove_clock: u8 },
 
@@ -436,6 +586,12 @@ pub enum GamestateFenDeserializeError {
     #[error("FEN is invalid because it is empty")]
     Empty,
 
+    #[error(
+        "FEN is {length} characters long, more than the {} allowed",
+        MAX_FEN_LEN
+    )]
+    TooLong { length: usize },
+
     #[error(
         "number of subsections of FEN &str is {num_fen_sections}, but should be {}",
         NUM_FEN_SECTIONS
@@ -484,6 +640,12 @@ pub enum CastlePermConversionError {
 
     #[error("could not convert {invalid_string} into a CastlePerm")]
     FromStr { invalid_string: String },
+
+    #[error(
+        "could not convert a {length} character string into a CastlePerm, which is at most {} characters",
+        MAX_CASTLE_PERM_FEN_LEN
+    )]
+    FromStrTooLong { length: usize },
 }
 
 #[derive(Error, Debug, PartialEq)]
@@ -502,6 +664,9 @@ pub enum SquareConversionError {
 
     #[error("could not convert usize: {index} into a Square")]
     FromUsize { index: usize },
+
+    #[error("could not convert \"{token}\" from a list of squares into a Square")]
+    FromListToken { token: String },
 }
 
 #[derive(Error, Debug, PartialEq)]
@@ -530,3 +695,54 @@ pub enum FileConversionError {
     #[error("could not convert usize {invalid_usize} into a File")]
     FromUsize { invalid_usize: usize },
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error as StdError;
+
+    #[test]
+    fn test_error_display_interpolates_fields() {
+        let error = GamestateValidityCheckError::StrictEnPassantSquareAheadUnexpectedPiece {
+            square_ahead: Square::E4,
+            invalid_piece: Piece::WhiteKnight,
+            expected_piece: Piece::WhitePawn,
+        };
+        let output = error.to_string();
+        let expected = format!(
+            "Square ahead en passant square E4 is occupied by {} which is not a {}",
+            Piece::WhiteKnight,
+            Piece::WhitePawn
+        );
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_error_display_multiline_message() {
+        let error = BoardValidityCheckError::StrictKingsLessThanTwoSquaresApart {
+            white_king_square: Square::E4,
+            black_king_square: Square::E5,
+            kings_distance: 1,
+        };
+        let output = error.to_string();
+        let expected = "Board has Kings less than 2 squares apart from each other which is not allowed. \
+            WhiteKing is at Square E4, BlackKing is at Square E5 and the distance between them is 1";
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_error_source_chain() {
+        let board_error = BoardValidityCheckError::StrictWhitePawnInFirstRank;
+        let error = MoveGenError::from(GamestateValidityCheckError::from(board_error));
+
+        let output = std::iter::successors(Some(&error as &dyn StdError), |&err| err.source())
+            .map(|err| err.to_string())
+            .collect::<Vec<_>>();
+        let expected = vec![
+            "Cannot generate moves for invalid Gamestate".to_string(),
+            "Board is invalid".to_string(),
+            "Board has a WhitePawn in Rank1 which is not a valid position".to_string(),
+        ];
+        assert_eq!(output, expected);
+    }
+}
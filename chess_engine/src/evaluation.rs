@@ -0,0 +1,317 @@
+use strum::EnumCount;
+
+use crate::{
+    board::{bitboard::BitBoard, Board},
+    color::Color,
+    gamestate::Gamestate,
+    piece::{Piece, PieceType},
+    rank::Rank,
+    square::Square64,
+};
+
+// CONSTANTS:
+/// XOR-ing a Square64 index with this flips its rank, which lets Black reuse White's tables
+const MIRROR_RANK: usize = 56;
+/// Bonus for every square a Knight, Bishop, Rook or Queen can move to, see Gamestate::mobility
+const MOBILITY_BONUS: i32 = 2;
+/// Bonus for a passed pawn by how many ranks it has crossed, so it grows as it gets closer
+/// to promoting. Pawns are never on the first or last rank of their color.
+const PASSED_PAWN_BONUS: [i32; Rank::COUNT] = [0, 10, 15, 25, 40, 60, 90, 0];
+/// Penalty for a pawn without friendly pawns on the files next to it
+const ISOLATED_PAWN_PENALTY: i32 = 15;
+/// Penalty for every pawn beyond the first one on a file
+const DOUBLED_PAWN_PENALTY: i32 = 10;
+
+// NOTE: Piece-square tables are written from White's point of view and indexed by Square64,
+// so the first row is Rank1 (A1..H1) and the last row is Rank8 (A8..H8)
+
+#[rustfmt::skip]
+const PAWN_TABLE: [i32; Square64::COUNT] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+     5,  10,  10, -20, -20,  10,  10,   5,
+     5,  -5, -10,   0,   0, -10,  -5,   5,
+     0,   0,   0,  20,  20,   0,   0,   0,
+     5,   5,  10,  25,  25,  10,   5,   5,
+    10,  10,  20,  30,  30,  20,  10,  10,
+    50,  50,  50,  50,  50,  50,  50,  50,
+     0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const KNIGHT_TABLE: [i32; Square64::COUNT] = [
+    -50, -40, -30, -30, -30, -30, -40, -50,
+    -40, -20,   0,   5,   5,   0, -20, -40,
+    -30,   5,  10,  15,  15,  10,   5, -30,
+    -30,   0,  15,  20,  20,  15,   0, -30,
+    -30,   5,  15,  20,  20,  15,   5, -30,
+    -30,   0,  10,  15,  15,  10,   0, -30,
+    -40, -20,   0,   0,   0,   0, -20, -40,
+    -50, -40, -30, -30, -30, -30, -40, -50,
+];
+
+#[rustfmt::skip]
+const BISHOP_TABLE: [i32; Square64::COUNT] = [
+    -20, -10, -10, -10, -10, -10, -10, -20,
+    -10,   5,   0,   0,   0,   0,   5, -10,
+    -10,  10,  10,  10,  10,  10,  10, -10,
+    -10,   0,  10,  10,  10,  10,   0, -10,
+    -10,   5,   5,  10,  10,   5,   5, -10,
+    -10,   0,   5,  10,  10,   5,   0, -10,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -20, -10, -10, -10, -10, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const ROOK_TABLE: [i32; Square64::COUNT] = [
+     0,   0,   0,   5,   5,   0,   0,   0,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+     5,  10,  10,  10,  10,  10,  10,   5,
+     0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const QUEEN_TABLE: [i32; Square64::COUNT] = [
+    -20, -10, -10,  -5,  -5, -10, -10, -20,
+    -10,   0,   5,   0,   0,   0,   0, -10,
+    -10,   5,   5,   5,   5,   5,   0, -10,
+      0,   0,   5,   5,   5,   5,   0,  -5,
+     -5,   0,   5,   5,   5,   5,   0,  -5,
+    -10,   0,   5,   5,   5,   5,   0, -10,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -20, -10, -10,  -5,  -5, -10, -10, -20,
+];
+
+/// Keeps the King tucked away behind its pawns while there are still pieces around to attack it
+#[rustfmt::skip]
+const KING_OPENING_TABLE: [i32; Square64::COUNT] = [
+     20,  30,  10,   0,   0,  10,  30,  20,
+     20,  20,   0,   0,   0,   0,  20,  20,
+    -10, -20, -20, -20, -20, -20, -20, -10,
+    -20, -30, -30, -40, -40, -30, -30, -20,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+];
+
+/// Once most pieces are off the board the King should head for the center
+#[rustfmt::skip]
+const KING_ENDGAME_TABLE: [i32; Square64::COUNT] = [
+    -50, -30, -30, -30, -30, -30, -30, -50,
+    -30, -30,   0,   0,   0,   0, -30, -30,
+    -30, -10,  20,  30,  30,  20, -10, -30,
+    -30, -10,  30,  40,  40,  30, -10, -30,
+    -30, -10,  30,  40,  40,  30, -10, -30,
+    -30, -10,  20,  30,  30,  20, -10, -30,
+    -30, -20, -10,   0,   0, -10, -20, -30,
+    -50, -40, -30, -20, -20, -30, -40, -50,
+];
+
+/// Scores positions for the search. Implement this to plug custom heuristics into the
+/// search without changing it.
+pub trait Evaluator {
+    /// Statically evaluate the Gamestate in centipawns. The score must be relative to the
+    /// active color, so a positive value means the side to move is better off.
+    fn evaluate(&self, gamestate: &Gamestate) -> i32;
+}
+
+/// Counts material only
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MaterialEvaluator;
+
+impl Evaluator for MaterialEvaluator {
+    fn evaluate(&self, gamestate: &Gamestate) -> i32 {
+        let score = gamestate.board().material_balance();
+        match gamestate.active_color() {
+            Color::White => score,
+            Color::Black => -score,
+        }
+    }
+}
+
+/// Material, piece-square tables, mobility and pawn structure, with the King tables tapered
+/// by game phase. This is the evaluation the search uses by default, see evaluate.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TaperedEvaluator;
+
+impl Evaluator for TaperedEvaluator {
+    fn evaluate(&self, gamestate: &Gamestate) -> i32 {
+        evaluate(gamestate)
+    }
+}
+
+/// Look up the piece-square bonus for a piece standing on the given square. Tables that
+/// differ between opening and endgame are blended by phase_factor (1.0 is the opening)
+fn piece_square_value(piece: Piece, square: Square64, phase_factor: f32) -> i32 {
+    let index = match piece.get_color() {
+        Color::White => square as usize,
+        Color::Black => square as usize ^ MIRROR_RANK,
+    };
+
+    match piece.get_piece_type() {
+        PieceType::Pawn => PAWN_TABLE[index],
+        PieceType::Knight => KNIGHT_TABLE[index],
+        PieceType::Bishop => BISHOP_TABLE[index],
+        PieceType::Rook => ROOK_TABLE[index],
+        PieceType::Queen => QUEEN_TABLE[index],
+        PieceType::King => {
+            let opening = KING_OPENING_TABLE[index] as f32;
+            let endgame = KING_ENDGAME_TABLE[index] as f32;
+            (opening * phase_factor + endgame * (1.0 - phase_factor)).round() as i32
+        }
+    }
+}
+
+/// Score the passed, isolated and doubled pawns of the provided color in centipawns, from
+/// that color's point of view. Uses the pawn bitboards so each term is a few mask checks.
+pub fn pawn_structure(board: &Board, color: Color) -> i32 {
+    let own_pawns = board.pawns[color as usize].0;
+    let enemy_pawns = board.pawns[color.opponent() as usize].0;
+    let pawn = match color {
+        Color::White => Piece::WhitePawn,
+        Color::Black => Piece::BlackPawn,
+    };
+
+    let mut score = 0;
+    for &square in &board.piece_list[pawn as usize] {
+        let file = square.get_file();
+        let rank = square.get_rank();
+        let file_mask = file.mask().0;
+        let adjacent_files_mask = BitBoard::adjacent_files_mask(file).0;
+
+        let ranks_ahead_mask = BitBoard::ranks_ahead_mask(rank, color).0;
+        if enemy_pawns & (file_mask | adjacent_files_mask) & ranks_ahead_mask == 0 {
+            score += PASSED_PAWN_BONUS[rank.relative_to(color) as usize];
+        }
+        if own_pawns & adjacent_files_mask == 0 {
+            score -= ISOLATED_PAWN_PENALTY;
+        }
+        // NOTE: only the pawns in front of this one count, so each extra pawn on the file is
+        // penalized once
+        if own_pawns & file_mask & ranks_ahead_mask != 0 {
+            score -= DOUBLED_PAWN_PENALTY;
+        }
+    }
+    score
+}
+
+/// Statically evaluate the Gamestate in centipawns. The score is relative to the
+/// active color, so a positive value means the side to move is better off.
+pub fn evaluate(gamestate: &Gamestate) -> i32 {
+    let board = gamestate.board();
+
+    // NOTE: Kings are included in the material but they cancel each other out
+    let mut score = board.material_balance();
+    let phase_factor = board.phase_factor();
+
+    for (piece, squares) in Piece::all().zip(board.piece_list.iter()) {
+        for &square in squares {
+            match piece.get_color() {
+                Color::White => {
+                    score += piece_square_value(piece, Square64::from(square), phase_factor)
+                }
+                Color::Black => {
+                    score -= piece_square_value(piece, Square64::from(square), phase_factor)
+                }
+            }
+        }
+    }
+
+    score += MOBILITY_BONUS * (gamestate.mobility(Color::White) - gamestate.mobility(Color::Black));
+    score += pawn_structure(board, Color::White) - pawn_structure(board, Color::Black);
+
+    match gamestate.active_color() {
+        Color::White => score,
+        Color::Black => -score,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluate_starting_position_is_balanced() {
+        let gamestate = Gamestate::default();
+        let output = evaluate(&gamestate);
+        let expected = 0;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_evaluate_is_relative_to_active_color() {
+        // White is up a Queen
+        let white_to_move = Gamestate::try_from("4k3/8/8/8/8/8/8/3QK3 w - - 0 1").unwrap();
+        let black_to_move = Gamestate::try_from("4k3/8/8/8/8/8/8/3QK3 b - - 0 1").unwrap();
+        let output = evaluate(&white_to_move);
+        let expected = -evaluate(&black_to_move);
+        assert_eq!(output, expected);
+        assert!(output > 0);
+    }
+
+    #[test]
+    fn test_evaluate_centralized_king_in_endgame() {
+        let centralized = Gamestate::try_from("4k3/pppp4/8/8/4K3/8/4PPPP/8 w - - 0 1").unwrap();
+        let cornered = Gamestate::try_from("4k3/pppp4/8/8/8/8/4PPPP/K7 w - - 0 1").unwrap();
+        assert!(evaluate(&centralized) > evaluate(&cornered));
+    }
+
+    #[test]
+    fn test_evaluate_rewards_mobility() {
+        // same material and piece-square values, but the Rook on e4 is hemmed in by Black's
+        // pawns while the one on d4 has an open file
+        let mobile = Gamestate::try_from("4k3/8/8/2p1p3/3R4/8/8/4K3 w - - 0 1").unwrap();
+        let blocked = Gamestate::try_from("4k3/8/8/3p1p2/4R3/8/8/4K3 w - - 0 1").unwrap();
+        assert!(mobile.mobility(Color::White) > blocked.mobility(Color::White));
+        assert!(evaluate(&mobile) > evaluate(&blocked));
+    }
+
+    #[test]
+    fn test_pawn_structure_passed_pawn() {
+        // d5 is passed, e4 isn't because of the f7 pawn, and they protect each other's flank
+        let gamestate = Gamestate::try_from("4k3/5p2/8/3P4/4P3/8/8/4K3 w - - 0 1").unwrap();
+        let output = pawn_structure(gamestate.board(), Color::White);
+        let expected = PASSED_PAWN_BONUS[Rank::Rank5 as usize];
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_pawn_structure_passed_pawn_black() {
+        // mirrored, so Black's pawn on d4 has crossed as many ranks as White's on d5
+        let gamestate = Gamestate::try_from("4k3/8/8/4p3/3p4/8/5P2/4K3 w - - 0 1").unwrap();
+        let output = pawn_structure(gamestate.board(), Color::Black);
+        let expected = PASSED_PAWN_BONUS[Rank::Rank5 as usize];
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_pawn_structure_doubled_pawns() {
+        // the d7 pawn stops all of White's pawns from being passed
+        let gamestate = Gamestate::try_from("4k3/3p4/8/8/8/2PP4/3P4/4K3 w - - 0 1").unwrap();
+        let output = pawn_structure(gamestate.board(), Color::White);
+        let expected = -DOUBLED_PAWN_PENALTY;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_pawn_structure_isolated_pawn() {
+        let gamestate = Gamestate::try_from("4k3/2p5/8/8/8/8/P1P5/4K3 w - - 0 1").unwrap();
+        let output = pawn_structure(gamestate.board(), Color::White);
+        // a2 is passed and isolated, c2 is isolated and blocked by c7
+        let expected = PASSED_PAWN_BONUS[Rank::Rank2 as usize] - 2 * ISOLATED_PAWN_PENALTY;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_material_evaluator_ignores_piece_squares() {
+        let centralized = Gamestate::try_from("4k3/pppp4/8/8/4K3/8/4PPPP/8 b - - 0 1").unwrap();
+        let cornered = Gamestate::try_from("4k3/pppp4/8/8/8/8/4PPPP/K7 b - - 0 1").unwrap();
+        let output = MaterialEvaluator.evaluate(&centralized);
+        let expected = MaterialEvaluator.evaluate(&cornered);
+        assert_eq!(output, expected);
+    }
+}
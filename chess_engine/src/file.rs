@@ -1,5 +1,5 @@
 use crate::{
-    board::{NUM_EXTERNAL_BOARD_SQUARES, NUM_INTERNAL_BOARD_SQUARES},
+    board::{bitboard::BitBoard, NUM_EXTERNAL_BOARD_SQUARES, NUM_INTERNAL_BOARD_SQUARES},
     error::FileConversionError,
 };
 use strum::{EnumCount, IntoEnumIterator};
@@ -45,6 +45,19 @@ pub enum File {
     FileH,
 }
 
+impl File {
+    /// The files right next to this one, one for the A and H files and two for the rest
+    pub fn adjacent(&self) -> impl Iterator<Item = File> {
+        let this = *self;
+        Self::iter().filter(move |file| (*file as i8 - this as i8).abs() == 1)
+    }
+
+    /// Every square on this file
+    pub fn mask(&self) -> BitBoard {
+        BitBoard::file_mask(*self)
+    }
+}
+
 impl TryFrom<usize> for File {
     type Error = FileConversionError;
     fn try_from(value: usize) -> Result<Self, Self::Error> {
@@ -70,3 +83,32 @@ impl From<File> for char {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_adjacent_edge_file() {
+        let output = (
+            File::FileA.adjacent().collect::<Vec<_>>(),
+            File::FileH.adjacent().collect::<Vec<_>>(),
+        );
+        let expected = (vec![File::FileB], vec![File::FileG]);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_file_adjacent_inner_file() {
+        let output = File::FileB.adjacent().collect::<Vec<_>>();
+        let expected = vec![File::FileA, File::FileC];
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_file_mask() {
+        let output = File::FileB.mask();
+        let expected = BitBoard(0x02_02_02_02_02_02_02_02);
+        assert_eq!(output, expected);
+    }
+}
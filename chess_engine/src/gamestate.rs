@@ -1,28 +1,39 @@
+use nanoserde::{DeBin, SerBin};
+use rand::{seq::SliceRandom, Rng};
 use std::{
+    collections::HashMap,
     default,
     fmt::{self, write},
+    mem,
     num::ParseIntError,
 };
-use strum::EnumCount;
+use strum::{EnumCount, IntoEnumIterator};
 use strum_macros::{Display as EnumDisplay, EnumCount as EnumCountMacro};
 
 use crate::{
-    board::{Board, BoardBuilder, NUM_BOARD_COLUMNS, NUM_BOARD_ROWS, NUM_INTERNAL_BOARD_SQUARES},
+    board::{
+        bitboard::BitBoard, Board, BoardBuilder, NUM_BOARD_COLUMNS, NUM_BOARD_ROWS,
+        NUM_INTERNAL_BOARD_SQUARES,
+    },
     castle_perm::{self, Castle, CastlePerm, NUM_CASTLE_PERM},
     color::Color,
     error::{
-        AddPieceError, BoardFenDeserializeError, ClearPieceError, GamestateBuildError,
-        GamestateFenDeserializeError, GamestateValidityCheckError, MakeMoveError, MoveGenError,
-        MovePieceError, RankFenDeserializeError, SquareConversionError, UndoMoveError,
+        AddPieceError, BoardFenDeserializeError, ClearPieceError, GameBytesError,
+        GamestateBuildError, GamestateFenDeserializeError, GamestateValidityCheckError,
+        MakeMoveError, MoveGenError, MovePieceError, PgnError, RankFenDeserializeError,
+        SquareConversionError, UndoMoveError,
     },
+    evaluation::Evaluator,
     file::File,
     moves::{Move, MoveList},
+    pgn::{move_to_san, san_to_move},
     piece::{
         self, Piece, PieceType, BLACK_PAWN_PROMOTION_TARGETS, BLACK_PAWN_VERTICAL_DIRECTION,
         WHITE_PAWN_PROMOTION_TARGETS, WHITE_PAWN_VERTICAL_DIRECTION,
     },
     position_key::PositionKey,
     rank::Rank,
+    search::search_with_evaluator,
     square::{Square, Square64},
     zobrist::ZOBRIST,
 };
@@ -33,8 +44,80 @@ pub const MAX_GAME_MOVES: usize = 1024;
 /// When we reach 50 moves (aka 100 half moves) without a pawn advance or a piece capture the game ends
 /// immediately in a tie
 pub const HALF_MOVE_MAX: u8 = 100;
+/// Halfmove clocks above this can't come from a real game (a draw would have been claimed long
+/// before) so Strict validity checking rejects them
+pub const HALF_MOVE_STRICT_MAX: u8 = 150;
 pub const NUM_FEN_SECTIONS: usize = 6;
-const DEFAULT_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+/// Longest Gamestate FEN accepted. Real FENs stay well under this (the board section is at
+/// most 71 characters) so anything longer is rejected before being split up.
+pub const MAX_FEN_LEN: usize = 100;
+pub(crate) const DEFAULT_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+/// First bytes of every game saved with Gamestate::to_bytes
+pub const GAME_BYTES_MAGIC: [u8; 4] = *b"RCGM";
+/// Bumped whenever the layout after the magic header changes
+pub const GAME_BYTES_VERSION: u8 = 1;
+
+/// How a finished game ended
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum GameResult {
+    Checkmate {
+        winner: Color,
+    },
+    Stalemate,
+    DrawFiftyMove,
+    /// Neither side can ever capture or move a pawn again, see is_dead_position
+    DrawDeadPosition,
+}
+
+/// Whether a move can be played in the current position and if not why, see explain_move
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MoveLegality {
+    Legal,
+    /// The piece on the start square belongs to the side that isn't to move
+    WrongTurn,
+    /// The start square is empty
+    NoPieceThere,
+    /// The end square holds another piece of the mover's color
+    OwnPieceOnTarget,
+    /// The piece moves like this, but something is in the way (including a piece in front
+    /// of a pawn, or between the King and Rook when castling)
+    PieceBlocked,
+    /// The move would leave the mover's King in check, e.g. moving a pinned piece
+    WouldLeaveKingInCheck,
+    /// A pawn reached the last rank without a promotion piece, or a promotion piece was
+    /// given for a move that doesn't promote
+    InvalidPromotion,
+    /// The King or the Rook has already moved
+    CastlingRightLost,
+    /// The King is in check, or would cross or land on an attacked square
+    CastlingThroughCheck,
+    /// The piece can't move like this at all, e.g. a Knight moving in a straight line or a
+    /// pawn moving diagonally without capturing
+    NotALegalPattern,
+}
+
+/// How a move from the history compares to the engine's choice, see annotate_history
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Annotation {
+    pub played: Move,
+    pub best_move: Move,
+    /// Scores in centipawns relative to the side that played the move
+    pub best_score: i32,
+    pub played_score: i32,
+    /// Centipawns given up by playing the played move instead of the best one, never negative
+    pub delta: i32,
+}
+
+/// What happened when a move was made, see make_move_full
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MoveOutcome {
+    pub captured: Option<Piece>,
+    /// Whether the move put the opponent in check (also true for checkmate)
+    pub is_check: bool,
+    pub is_checkmate: bool,
+    /// The move in SAN, written for the position it was played from
+    pub san: String,
+}
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct Undo {
@@ -45,13 +128,19 @@ pub struct Undo {
     position_key: PositionKey,
 }
 
-// NOTE: There might be more variants in the future like Chess960, or editor mode
+// NOTE: There might be more variants in the future like editor mode
 /// MODE EXPLANATION:
 ///
 /// Strict: Denotes a bunch of checks that will, in isolation, try to make sure that your
 ///         Gamestate/Board are valid (as much as posible). This one should be used when
 ///         initially building the Gamestate/Board.
 ///
+/// Chess960: Same checks as Strict except for castle permissions. Strict requires the King
+///           and Rook of each castle permission on their classic starting squares (e.g. e1
+///           and h1 for White's King side), Chess960 only requires the King on its back rank
+///           with a Rook of the same color on the matching side of it, since Chess960 Kings
+///           and Rooks can start on any file.
+///
 /// Move: Denotes the tests that are going to be run
 ///       each time a move is made. These tests are not sufficient in isolation to
 ///       check for validity, but these should be the subset of tests that aren't
@@ -65,6 +154,7 @@ pub struct Undo {
 #[derive(Debug, Clone, Copy)]
 pub enum ValidityCheck {
     Strict,
+    Chess960,
     Move,
     Basic,
 }
@@ -115,99 +205,25 @@ impl GamestateBuilder {
         }
     }
 
-    // TODO: make sure that on the frontend the number of characters that can be passed is limited to something reasonable
     // TODO: look into X-FEN and Shredder-FEN for Chess960)
     pub fn new_with_fen(gamestate_fen: &str) -> Result<Self, GamestateFenDeserializeError> {
-        let mut board = None;
-        let mut active_color = None;
-        let mut castle_perm = None;
-        let mut en_passant = None;
-        let mut halfmove_clock = None;
-        let mut fullmove_count = None;
-
-        // Allow for extra spaces in between sections but not in the middle of sections
-        let fen_sections = gamestate_fen
-            .split(' ')
-            .filter(|section| !section.is_empty())
-            .collect::<Vec<_>>();
-
-        match fen_sections.len() {
-            NUM_FEN_SECTIONS => {
-                for (index, section) in fen_sections.into_iter().enumerate() {
-                    match index {
-                        // Turn off board checking default so that it can be set by Gamestate
-                        0 => {
-                            board = Some(
-                                BoardBuilder::new_with_fen(section)?
-                                    .validity_check(ValidityCheck::Basic)
-                                    .build()?,
-                            )
-                        }
-                        // active_color should be either "w" or "b"
-                        1 => {
-                            active_color = match section {
-                                white if white == char::from(Color::White).to_string() => {
-                                    Some(Color::White)
-                                }
-                                black if black == char::from(Color::Black).to_string() => {
-                                    Some(Color::Black)
-                                }
-                                _ => {
-                                    return Err(GamestateFenDeserializeError::ActiveColor {
-                                        gamestate_fen: gamestate_fen.to_owned(),
-                                        invalid_color: section.to_owned(),
-                                    });
-                                }
-                            }
-                        }
-                        2 => castle_perm = Some(CastlePerm::try_from(section)?),
-                        3 => {
-                            en_passant = match section {
-                                "-" => None,
-                                _ => Some(Square64::try_from(section.to_uppercase().as_str())?),
-                            }
-                        }
-                        4 => {
-                            halfmove_clock = Some(section.parse::<u8>().map_err(|_err| {
-                                GamestateFenDeserializeError::HalfmoveClock {
-                                    halfmove_fen: section.to_owned(),
-                                }
-                            })?)
-                        }
-                        5 => {
-                            fullmove_count = Some(section.parse::<usize>().map_err(|_err| {
-                                GamestateFenDeserializeError::FullmoveCount {
-                                    fullmove_fen: section.to_owned(),
-                                }
-                            })?)
-                        }
-                        _ => panic!(
-                            "Expected index to be in range 0..=5. Found index greater than 5"
-                        ),
-                    }
-                }
+        let mut fen_fields = FenFields::parse(gamestate_fen)?;
+        // Turn off board checking default so that it can be set by Gamestate
+        let board = fen_fields
+            .board_builder
+            .validity_check(ValidityCheck::Basic)
+            .build()?;
 
-                let board = board.unwrap();
-                let active_color = active_color.unwrap();
-                let castle_perm = castle_perm.unwrap();
-                let halfmove_clock = halfmove_clock.unwrap();
-                let fullmove_count = fullmove_count.unwrap();
-
-                Ok(GamestateBuilder {
-                    validity_check: ValidityCheck::Strict,
-                    board,
-                    active_color,
-                    castle_perm,
-                    en_passant,
-                    halfmove_clock,
-                    fullmove_count,
-                    history: vec![],
-                })
-            }
-            _ => Err(GamestateFenDeserializeError::WrongNumFENSections {
-                num_fen_sections: fen_sections.len(),
-            }),
-        }
+        Ok(GamestateBuilder {
+            validity_check: ValidityCheck::Strict,
+            board,
+            active_color: fen_fields.active_color,
+            castle_perm: fen_fields.castle_perm,
+            en_passant: fen_fields.en_passant,
+            halfmove_clock: fen_fields.halfmove_clock,
+            fullmove_count: fen_fields.fullmove_count,
+            history: vec![],
+        })
     }
 
     pub fn validity_check(mut self, validity_check: ValidityCheck) -> Self {
@@ -255,6 +271,7 @@ impl GamestateBuilder {
             fullmove_count: self.fullmove_count,
             position_key: PositionKey(0),
             history: self.history.clone(),
+            legal_moves_cache: None,
         };
 
         // Update position_key
@@ -272,7 +289,7 @@ impl GamestateBuilder {
             gamestate.history.push(initial_state);
         }
 
-        if let ValidityCheck::Strict = self.validity_check {
+        if let ValidityCheck::Strict | ValidityCheck::Chess960 = self.validity_check {
             gamestate.check_gamestate(self.validity_check)?;
         }
 
@@ -280,13 +297,110 @@ impl GamestateBuilder {
     }
 }
 
+/// The sections of a Gamestate FEN once parsed. The board is kept as a BoardBuilder so that it
+/// can either be built into a new Board or into an existing one.
+struct FenFields {
+    board_builder: BoardBuilder,
+    active_color: Color,
+    castle_perm: CastlePerm,
+    en_passant: Option<Square64>,
+    halfmove_clock: u8,
+    fullmove_count: usize,
+}
+
+impl FenFields {
+    fn parse(gamestate_fen: &str) -> Result<Self, GamestateFenDeserializeError> {
+        let mut board_builder = None;
+        let mut active_color = None;
+        let mut castle_perm = None;
+        let mut en_passant = None;
+        let mut halfmove_clock = None;
+        let mut fullmove_count = None;
+
+        if gamestate_fen.len() > MAX_FEN_LEN {
+            return Err(GamestateFenDeserializeError::TooLong {
+                length: gamestate_fen.len(),
+            });
+        }
+
+        // Allow for extra spaces in between sections but not in the middle of sections
+        let fen_sections = gamestate_fen
+            .split(' ')
+            .filter(|section| !section.is_empty())
+            .collect::<Vec<_>>();
+
+        match fen_sections.len() {
+            NUM_FEN_SECTIONS => {
+                for (index, section) in fen_sections.into_iter().enumerate() {
+                    match index {
+                        0 => board_builder = Some(BoardBuilder::new_with_fen(section)?),
+                        // active_color should be either "w" or "b"
+                        1 => {
+                            active_color = match section {
+                                white if white == char::from(Color::White).to_string() => {
+                                    Some(Color::White)
+                                }
+                                black if black == char::from(Color::Black).to_string() => {
+                                    Some(Color::Black)
+                                }
+                                _ => {
+                                    return Err(GamestateFenDeserializeError::ActiveColor {
+                                        gamestate_fen: gamestate_fen.to_owned(),
+                                        invalid_color: section.to_owned(),
+                                    });
+                                }
+                            }
+                        }
+                        2 => castle_perm = Some(CastlePerm::try_from(section)?),
+                        3 => {
+                            en_passant = match section {
+                                "-" => None,
+                                _ => Some(Square64::try_from(section.to_uppercase().as_str())?),
+                            }
+                        }
+                        4 => {
+                            halfmove_clock = Some(section.parse::<u8>().map_err(|_err| {
+                                GamestateFenDeserializeError::HalfmoveClock {
+                                    halfmove_fen: section.to_owned(),
+                                }
+                            })?)
+                        }
+                        5 => {
+                            fullmove_count = Some(section.parse::<usize>().map_err(|_err| {
+                                GamestateFenDeserializeError::FullmoveCount {
+                                    fullmove_fen: section.to_owned(),
+                                }
+                            })?)
+                        }
+                        _ => panic!(
+                            "Expected index to be in range 0..=5. Found index greater than 5"
+                        ),
+                    }
+                }
+
+                Ok(FenFields {
+                    board_builder: board_builder.unwrap(),
+                    active_color: active_color.unwrap(),
+                    castle_perm: castle_perm.unwrap(),
+                    en_passant,
+                    halfmove_clock: halfmove_clock.unwrap(),
+                    fullmove_count: fullmove_count.unwrap(),
+                })
+            }
+            _ => Err(GamestateFenDeserializeError::WrongNumFENSections {
+                num_fen_sections: fen_sections.len(),
+            }),
+        }
+    }
+}
+
 impl Default for GamestateBuilder {
     fn default() -> Self {
         GamestateBuilder::new_with_board(Board::default()).validity_check(ValidityCheck::Basic)
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug)]
 pub struct Gamestate {
     board: Board,
     active_color: Color,
@@ -298,15 +412,51 @@ pub struct Gamestate {
     fullmove_count: usize,
     position_key: PositionKey,
     history: Vec<Undo>,
+    /// Legal moves last generated by cached_legal_moves, along with the PositionKey of the
+    /// position they were generated for. Cleared whenever a move is made or undone.
+    /// NOTE: not part of the position, so it is ignored by PartialEq and never serialized
+    legal_moves_cache: Option<(PositionKey, MoveList)>,
+}
+
+// NOTE: implemented by hand so that legal_moves_cache doesn't affect equality
+impl PartialEq for Gamestate {
+    fn eq(&self, other: &Self) -> bool {
+        self.board == other.board
+            && self.active_color == other.active_color
+            && self.castle_perm == other.castle_perm
+            && self.en_passant == other.en_passant
+            && self.halfmove_clock == other.halfmove_clock
+            && self.fullmove_count == other.fullmove_count
+            && self.position_key == other.position_key
+            && self.history == other.history
+    }
+}
+
+impl Eq for Gamestate {}
+
+/// A clone is a true snapshot: making or undoing moves on it leaves the original untouched.
+/// The position_key is copied rather than recomputed with init_position_key.
+// NOTE: implemented by hand since the legal_moves_cache is left behind, the clone regenerates
+// it the first time it is needed
+impl Clone for Gamestate {
+    fn clone(&self) -> Self {
+        Gamestate {
+            board: self.board.clone(),
+            active_color: self.active_color,
+            castle_perm: self.castle_perm,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_count: self.fullmove_count,
+            position_key: self.position_key,
+            history: self.history.clone(),
+            legal_moves_cache: None,
+        }
+    }
 }
 
 impl Default for Gamestate {
     fn default() -> Self {
-        GamestateBuilder::new_with_board(Board::default())
-            .validity_check(ValidityCheck::Basic)
-            .castle_perm(CastlePerm(0b_1111))
-            .build()
-            .expect("starting gamestate should never fail to build")
+        Self::starting_position()
     }
 }
 
@@ -337,34 +487,116 @@ impl fmt::Display for Gamestate {
 }
 
 impl Gamestate {
+    /// The canonical state of a new game: the standard starting position with White to move,
+    /// full castling rights, no en passant square and fresh move counters (i.e. DEFAULT_FEN).
+    /// It passes the Strict validity check.
+    pub fn starting_position() -> Self {
+        GamestateBuilder::new_with_board(Board::default())
+            .validity_check(ValidityCheck::Strict)
+            .castle_perm(CastlePerm(0b_1111))
+            .build()
+            .expect("starting gamestate should never fail to build")
+    }
+
+    /// Put a Gamestate together from its components without a FEN or a GamestateBuilder.
+    /// The position key is computed from them and the result has to pass the Strict
+    /// validity check.
+    pub fn from_parts(
+        board: Board,
+        active_color: Color,
+        castle_perm: CastlePerm,
+        en_passant: Option<Square64>,
+        halfmove_clock: u8,
+        fullmove_count: usize,
+    ) -> Result<Gamestate, GamestateBuildError> {
+        GamestateBuilder::new_with_board(board)
+            .validity_check(ValidityCheck::Strict)
+            .active_color(active_color)
+            .castle_perm(castle_perm)
+            .en_passant(en_passant)
+            .halfmove_clock(halfmove_clock)
+            .fullmove_count(fullmove_count)
+            .build()
+    }
+
+    //================================= GETTERS ===============================
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn active_color(&self) -> Color {
+        self.active_color
+    }
+
+    pub fn castle_permissions(&self) -> CastlePerm {
+        self.castle_perm
+    }
+
+    /// Square a pawn can be captured on en passant, if the last move was a double pawn push
+    pub fn en_passant(&self) -> Option<Square64> {
+        self.en_passant
+    }
+
+    pub fn halfmove_clock(&self) -> u8 {
+        self.halfmove_clock
+    }
+
+    pub fn fullmove_count(&self) -> usize {
+        self.fullmove_count
+    }
+
+    /// The most recently made Move, if any Moves are left in the history
+    pub fn last_move(&self) -> Option<Move> {
+        // NOTE: skip the dummy Undo for the initial state, its Move was never made
+        self.history.iter().skip(1).last().map(|undo| undo.move_)
+    }
+
+    /// Square of the given color's King, None if there is no King on the board
+    pub fn king_square(&self, color: Color) -> Option<Square> {
+        self.board.kings_square[color as usize]
+    }
+
+    /// Zobrist key covering the pieces on the board, the active color, castle permissions and
+    /// the en passant square. The halfmove clock and fullmove count are never hashed, so the
+    /// key identifies a position for repetition detection regardless of how it was reached.
+    pub fn position_key(&self) -> PositionKey {
+        self.position_key
+    }
+
+    /// Key to store search results under. It's the position_key, since two positions that only
+    /// differ in their move counters have the same moves and should share an entry.
+    /// NOTE: the fifty move rule means the counters can still matter near HALF_MOVE_MAX
+    pub fn transposition_key(&self) -> PositionKey {
+        self.position_key
+    }
+
     //================================= MAKING MOVES ==========================
 
     /// If successful it will return the Move that was undone
     pub fn undo_move(&mut self) -> Result<Move, UndoMoveError> {
-        self.check_gamestate(ValidityCheck::Move)?;
-
-        // Rewind fullmove_count if you're undoing Black's Move (aka currently White's Move)
-        if self.active_color == Color::White {
-            self.fullmove_count -= 1;
-        }
-
+        // NOTE: no validity check up front since make_move relies on undo_move to
+        // back out of moves that leave the Gamestate invalid (e.g. Kings touching)
         // NOTE: Initial state stored in Undo like castle_perm, etc. is stored
         // in history with a dummy move.
+        self.legal_moves_cache = None;
         match self.history.len() {
             0 => Err(UndoMoveError::NoInitialState),
             1 => Err(UndoMoveError::NoMoveToUndo),
             _ => {
+                // Rewind fullmove_count if you're undoing Black's Move (aka currently White's Move)
+                if self.active_color == Color::White {
+                    self.fullmove_count -= 1;
+                }
+
+                // The Undo we pop holds the state from right before its Move was made
+                let previous_state = self.history.pop().ok_or(UndoMoveError::NoMoveToUndo)?;
                 // Grab the Move that you want to undo
-                let move_ = self.history.pop().ok_or(UndoMoveError::NoMoveToUndo)?.move_;
+                let move_ = previous_state.move_;
 
                 // TODO: confirm that these checks aren't needed at this point
                 // move_.check_move_strict()?;
 
-                let previous_state = self
-                    .history
-                    .last()
-                    .expect("Expected at least one Undo to be left in history since len > 1");
-
                 let start_square = Square::try_from(move_.get_start_raw())?;
                 let end_square = Square::try_from(move_.get_end_raw())?;
 
@@ -405,16 +637,16 @@ impl Gamestate {
                     // Move rooks back
                     match end_square {
                         Square::C1 => {
-                            self.move_piece(Square::D1, Square::A1);
+                            self.move_piece(Square::D1, Square::A1)?;
                         }
                         Square::G1 => {
-                            self.move_piece(Square::F1, Square::H1);
+                            self.move_piece(Square::F1, Square::H1)?;
                         }
                         Square::C8 => {
-                            self.move_piece(Square::D8, Square::A8);
+                            self.move_piece(Square::D8, Square::A8)?;
                         }
                         Square::G8 => {
-                            self.move_piece(Square::F8, Square::H8);
+                            self.move_piece(Square::F8, Square::H8)?;
                         }
                         _ => {
                             return Err(UndoMoveError::CastleEndSquare { end_square });
@@ -423,19 +655,20 @@ impl Gamestate {
                 }
 
                 // Move piece that was moved back to previous position
-                self.move_piece(end_square, start_square);
+                self.move_piece(end_square, start_square)?;
 
                 let piece_moved = move_.get_piece_moved()?;
 
                 // Reset kings_square if needed
                 if piece_moved.is_king() {
-                    self.board.kings_square[self.active_color as usize] = Some(end_square);
+                    self.board.kings_square[self.active_color as usize] = Some(start_square);
                 }
 
-                // Reset any captured pieces
+                // Reset any captured pieces (en passant captures were already dealt with above
+                // since the captured pawn isn't on the end square)
                 if let Some(captured_piece) = move_.get_piece_captured()? {
-                    if self.en_passant.is_none() {
-                        self.add_piece(end_square, captured_piece);
+                    if !move_.is_en_passant() {
+                        self.add_piece(end_square, captured_piece)?;
                     }
                 }
 
@@ -447,23 +680,50 @@ impl Gamestate {
                         Color::White => Piece::WhitePawn,
                         Color::Black => Piece::BlackPawn,
                     };
-                    self.add_piece(start_square, pawn);
+                    self.add_piece(start_square, pawn)?;
                 }
 
                 self.check_gamestate(ValidityCheck::Move)?;
 
+                #[cfg(debug_assertions)]
+                if let Err(err) = self.debug_invariants() {
+                    panic!("undoing {} desynced the board: {err}", move_.to_uci());
+                }
+
                 Ok(move_)
             }
         }
     }
 
+    /// Make a legal move of the current position, see make_move_full. Illegal moves are
+    /// rejected before anything in the Gamestate changes.
     pub fn make_move(&mut self, move_: Move) -> Result<(), MakeMoveError> {
+        self.make_move_full(move_).map(|_| ())
+    }
+
+    /// Reject moves of the side that isn't to move with a dedicated error, so callers like the
+    /// server can tell them apart from otherwise illegal moves
+    fn check_color_to_move(&self, move_: Move) -> Result<(), MakeMoveError> {
+        let found = move_.get_piece_moved()?.get_color();
+        if found != self.active_color {
+            return Err(MakeMoveError::WrongColorToMove {
+                expected: self.active_color,
+                found,
+            });
+        }
+        Ok(())
+    }
+
+    /// Same as make_move but without checking the Move against the legal moves, writing its
+    /// SAN or validating the resulting Gamestate. Only meant for moves generated for the
+    /// current position (e.g. in search and perft), which keep a valid Gamestate valid. A
+    /// move that would leave the mover's King in check is still undone and rejected.
+    pub fn make_move_unchecked(&mut self, move_: Move) -> Result<(), MakeMoveError> {
+        self.legal_moves_cache = None;
+
         // Save current active_color before we toggle it
         let initial_active_color = self.active_color;
 
-        // Check if move_ is valid
-        move_.check_move()?;
-
         // Set up ability to Undo this move
         let undo = Undo {
             move_,
@@ -479,6 +739,9 @@ impl Gamestate {
         let end_square = Square::try_from(move_.get_end_raw())?;
 
         // deal with en_passant moves
+        // NOTE: the captured pawn is cleared before the self-check test below, so an en passant
+        // capture that takes both pawns off a rank shared with the King and an enemy Rook or
+        // Queen is rejected like any other move that exposes the King
         if move_.is_en_passant() {
             match self.active_color {
                 Color::White => {
@@ -497,22 +760,22 @@ impl Gamestate {
                 // White Queenside Castle. Move Rook from A1 to D1.
                 // Presumably King has moved from E1 to C1
                 Square::C1 => {
-                    self.move_piece(Square::A1, Square::D1);
+                    self.move_piece(Square::A1, Square::D1)?;
                 }
                 // White Kingside Castle. Move Rook from H1 to F1.
                 // Presumably King has moved from E1 to G1
                 Square::G1 => {
-                    self.move_piece(Square::H1, Square::F1);
+                    self.move_piece(Square::H1, Square::F1)?;
                 }
                 // Black Queenside Castle. Move Rook from A8 to D8.
                 // Presumably King has moved from E8 to C8
                 Square::C8 => {
-                    self.move_piece(Square::A8, Square::D8);
+                    self.move_piece(Square::A8, Square::D8)?;
                 }
                 // Black Kingside Castle. Move Rook from H8 to F8.
                 // Presumably King has moved from E8 to G8
                 Square::G8 => {
-                    self.move_piece(Square::H8, Square::F8);
+                    self.move_piece(Square::H8, Square::F8)?;
                 }
                 _ => {
                     return Err(MakeMoveError::CastleEndSquare { end_square });
@@ -532,20 +795,13 @@ impl Gamestate {
         // have changed)
         self.position_key.hash_castle_perm(self.castle_perm);
 
-        // Deal with captured pieces and fifty-move rule
-        if move_.is_capture() {
-            // if en_passant, then the pawn_start that caused the en_passant
-            // will have already been checked to make sure that the end_square
-            // is empty
-            if self.en_passant.is_none() {
-                self.clear_piece(end_square);
-            }
-            self.halfmove_clock = 0;
-        } else {
-            self.halfmove_clock += 1;
+        // Deal with captured pieces. en passant captures were already cleared above
+        // since the captured pawn isn't on the end_square
+        if move_.is_capture() && !move_.is_en_passant() {
+            self.clear_piece(end_square)?;
         }
 
-        // Update fullmove_count if this is Black's move
+        // Update fullmove_count once Black has moved (active_color is still the mover here)
         if self.active_color == Color::Black {
             self.fullmove_count += 1;
         }
@@ -557,10 +813,16 @@ impl Gamestate {
         let piece_moved =
             self.board.pieces[start_square as usize].ok_or(MakeMoveError::MovedPieceNotInPieces)?;
 
+        // fifty-move rule. reset half moves since last capture or pawn move
+        // NOTE: a single check covers captures by any piece and pawn captures that promote
+        if move_.is_capture() || piece_moved.is_pawn() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock = self.halfmove_clock.saturating_add(1);
+        }
+
         // Check if new en_passant square was created
         if piece_moved.is_pawn() {
-            // fifty-move rule. reset half moves since last capture or pawn move
-            self.halfmove_clock = 0;
             // pawn starts (move up 2) create en_passant squares
             if move_.is_pawn_start() {
                 match self.active_color {
@@ -582,13 +844,13 @@ impl Gamestate {
         }
 
         // Actually move our piece
-        self.move_piece(start_square, end_square);
+        self.move_piece(start_square, end_square)?;
 
         // Deal with promotions
         if let Some(promoted_piece) = move_.get_piece_promoted()? {
             // NOTE: this is after move so you're clearing the Pawn
-            self.clear_piece(end_square);
-            self.add_piece(end_square, promoted_piece);
+            self.clear_piece(end_square)?;
+            self.add_piece(end_square, promoted_piece)?;
         }
 
         // TODO: check if can be removed.
@@ -601,30 +863,236 @@ impl Gamestate {
         self.active_color.toggle();
         self.position_key.hash_color();
 
-        // TODO: is this necessary?
-        self.check_gamestate(ValidityCheck::Move)?;
-
         // check if move puts active_color in check
         if (self.is_square_attacked(
             self.active_color,
             self.board.kings_square[initial_active_color as usize]
                 .expect("Expected King's square to be stored in kings_square"),
         )) {
-            self.undo_move();
+            self.undo_move()
+                .expect("undoing a move that was just made should never fail");
             return Err(MakeMoveError::MoveWouldPutMovingSideInCheck);
         }
 
+        #[cfg(debug_assertions)]
+        if let Err(err) = self.debug_invariants() {
+            panic!("making {} desynced the board: {err}", move_.to_uci());
+        }
+
         Ok(())
     }
 
-    /// Moves a piece and updates all appropriate places in the Board as well as
-    /// the position key. Returns an Err if there is no piece on start_square
-    /// or a capture is attempted (or if piece not found in piece_list).
-    fn move_piece(
-        &mut self,
-        start_square: Square,
-        end_square: Square,
-    ) -> Result<(), MovePieceError> {
+    /// Make the move only if it is one of the legal moves of the current position. Same as
+    /// make_move, which only accepts legal moves.
+    pub fn make_move_checked(&mut self, move_: Move) -> Result<(), MakeMoveError> {
+        self.make_move(move_)
+    }
+
+    /// The FEN of the position the move would lead to, e.g. to preview a move in the GUI.
+    /// The move is made and undone again, so the Gamestate ends up unchanged.
+    pub fn fen_after(&mut self, move_: Move) -> Result<String, MakeMoveError> {
+        self.make_move(move_)?;
+        let fen = self.to_fen();
+        self.undo_move()
+            .expect("undoing a move that was just made should never fail");
+        Ok(fen)
+    }
+
+    /// Tell why a move can't be played, e.g. so the GUI can show a beginner more than just
+    /// that the move was rejected. Only the start and end squares and the promotion piece of
+    /// move_ are looked at, everything else comes from the position. Nothing is changed.
+    pub fn explain_move(&mut self, move_: Move) -> Result<MoveLegality, MoveGenError> {
+        let (Ok(start), Ok(end), Ok(promoted)) = (
+            move_.get_start(),
+            move_.get_end(),
+            move_.get_piece_promoted(),
+        ) else {
+            return Ok(MoveLegality::NotALegalPattern);
+        };
+        let Some(piece) = self.board.pieces[start as usize] else {
+            return Ok(MoveLegality::NoPieceThere);
+        };
+        if piece.get_color() != self.active_color {
+            return Ok(MoveLegality::WrongTurn);
+        }
+        if start == end {
+            return Ok(MoveLegality::NotALegalPattern);
+        }
+        if self.board.pieces[end as usize]
+            .is_some_and(|target| target.get_color() == piece.get_color())
+        {
+            return Ok(MoveLegality::OwnPieceOnTarget);
+        }
+
+        let candidates = self
+            .gen_move_list()?
+            .moves
+            .into_iter()
+            .flatten()
+            .filter(|candidate| {
+                candidate.get_start() == Ok(start) && candidate.get_end() == Ok(end)
+            })
+            .collect::<Vec<_>>();
+        if let Some(&candidate) = candidates
+            .iter()
+            .find(|candidate| candidate.get_piece_promoted() == Ok(promoted))
+        {
+            if self.make_move_unchecked(candidate).is_err() {
+                return Ok(MoveLegality::WouldLeaveKingInCheck);
+            }
+            self.undo_move()
+                .expect("undoing a move that was just made should never fail");
+            return Ok(MoveLegality::Legal);
+        }
+        if !candidates.is_empty() {
+            return Ok(MoveLegality::InvalidPromotion);
+        }
+
+        // NOTE: castling is only generated when it's possible, so work out what stops it
+        if piece.get_piece_type() == PieceType::King {
+            for castle in Castle::iter() {
+                let (color, king_path, between) = Self::castle_squares(castle);
+                if color != self.active_color || start != king_path[0] || end != king_path[2] {
+                    continue;
+                }
+                let legality = if self.castle_perm.0 & castle as u8 == 0 {
+                    MoveLegality::CastlingRightLost
+                } else if between
+                    .iter()
+                    .any(|&square| self.board.pieces[square as usize].is_some())
+                {
+                    MoveLegality::PieceBlocked
+                } else {
+                    MoveLegality::CastlingThroughCheck
+                };
+                return Ok(legality);
+            }
+        }
+
+        // Anything left either moves like the piece does but is blocked, or doesn't
+        let file_distance = (end.get_file() as i8 - start.get_file() as i8).abs();
+        let rank_delta = end.get_rank() as i8 - start.get_rank() as i8;
+        let rank_distance = rank_delta.abs();
+        let fits_pattern = match piece.get_piece_type() {
+            PieceType::Pawn => {
+                let (forward, start_rank) = match self.active_color {
+                    Color::White => (1, Rank::Rank2),
+                    Color::Black => (-1, Rank::Rank7),
+                };
+                file_distance == 0
+                    && (rank_delta == forward
+                        || (rank_delta == 2 * forward && start.get_rank() == start_rank))
+            }
+            PieceType::Knight => {
+                (file_distance, rank_distance) == (1, 2) || (file_distance, rank_distance) == (2, 1)
+            }
+            PieceType::Bishop => file_distance == rank_distance,
+            PieceType::Rook => file_distance == 0 || rank_distance == 0,
+            PieceType::Queen => {
+                file_distance == rank_distance || file_distance == 0 || rank_distance == 0
+            }
+            PieceType::King => file_distance.max(rank_distance) == 1,
+        };
+        Ok(match fits_pattern {
+            true => MoveLegality::PieceBlocked,
+            false => MoveLegality::NotALegalPattern,
+        })
+    }
+
+    /// Make a legal move of the current position and report the captured piece, whether the
+    /// move gave check or checkmate and its SAN, so callers don't have to work these out
+    /// afterwards. Illegal moves are rejected before anything in the Gamestate changes.
+    /// Writing the SAN costs a legal move generation, so prefer make_move_unchecked anywhere
+    /// speed matters (e.g. search and perft).
+    pub fn make_move_full(&mut self, move_: Move) -> Result<MoveOutcome, MakeMoveError> {
+        // Check if move_ is valid
+        move_.check_move()?;
+        self.check_color_to_move(move_)?;
+
+        // NOTE: move_to_san rejects illegal moves and already works out check and checkmate
+        // for its + and # suffixes
+        let san = move_to_san(self, move_)?;
+        let captured = move_.get_piece_captured()?;
+
+        self.make_move_unchecked(move_)?;
+
+        // TODO: is this necessary?
+        // NOTE: undo before bailing so a rejected move never leaves the Gamestate half-updated
+        if let Err(err) = self.check_gamestate(ValidityCheck::Move) {
+            self.undo_move()
+                .expect("undoing a move that was just made should never fail");
+            return Err(err.into());
+        }
+
+        Ok(MoveOutcome {
+            captured,
+            is_check: san.ends_with(['+', '#']),
+            is_checkmate: san.ends_with('#'),
+            san,
+        })
+    }
+
+    /// Whether making the move would put the opponent in check. The move is made, tested and
+    /// undone rather than worked out geometrically, so discovered checks, double checks and
+    /// en passant captures that open a line to the King are all handled the same way.
+    /// Fails without changing anything if the move can't be made.
+    pub fn gives_check(&mut self, move_: Move) -> Result<bool, MakeMoveError> {
+        // NOTE: not make_move, which writes the SAN and so calls this
+        move_.check_move()?;
+        self.check_color_to_move(move_)?;
+        self.make_move_unchecked(move_)?;
+        let is_check = self.is_in_check();
+        self.undo_move()
+            .expect("undoing a move that was just made should never fail");
+        Ok(is_check)
+    }
+
+    /// Apply a sequence of moves in UCI long algebraic notation (e.g. "e2e4", "e7e8q"),
+    /// matching each against the legal moves of the current position. Stops at the
+    /// first token that is not a legal move, leaving the moves before it applied.
+    pub fn apply_uci_moves(&mut self, moves: &[&str]) -> Result<(), MakeMoveError> {
+        for (index, &uci) in moves.iter().enumerate() {
+            let legal_moves = self.gen_legal_moves()?;
+            let move_ = legal_moves
+                .moves
+                .into_iter()
+                .flatten()
+                .find(|move_| move_.to_uci() == uci.to_lowercase())
+                .ok_or_else(|| MakeMoveError::InvalidUciMove {
+                    index,
+                    uci: uci.to_string(),
+                })?;
+            self.make_move_unchecked(move_)?;
+        }
+
+        Ok(())
+    }
+
+    /// Play a bare list of SAN moves (e.g. ["e4", "e5", "Nf3"]) from the starting position.
+    /// The returned Gamestate keeps the full history so the game can be stepped back
+    /// through. Fails on the first move that can't be resolved, reporting its index.
+    pub fn from_san_moves(moves: &[&str]) -> Result<Gamestate, PgnError> {
+        let mut gamestate = Gamestate::default();
+        for (index, &san) in moves.iter().enumerate() {
+            let move_ = san_to_move(&mut gamestate, san).map_err(|err| PgnError::SanMove {
+                index,
+                san: san.to_string(),
+                source: Box::new(err),
+            })?;
+            gamestate.make_move(move_)?;
+        }
+
+        Ok(gamestate)
+    }
+
+    /// Moves a piece and updates all appropriate places in the Board as well as
+    /// the position key. Returns an Err if there is no piece on start_square
+    /// or a capture is attempted (or if piece not found in piece_list).
+    fn move_piece(
+        &mut self,
+        start_square: Square,
+        end_square: Square,
+    ) -> Result<(), MovePieceError> {
         let piece = self.board.pieces[start_square as usize]
             .ok_or(MovePieceError::NoPieceAtMoveStart { start_square })?;
 
@@ -801,111 +1269,131 @@ impl Gamestate {
 
     /// Generates castling moves for given Color
     fn gen_castling_moves(&self, active_color: Color, move_list: &mut MoveList) {
+        let (castles, king) = match active_color {
+            Color::White => ([Castle::WhiteKing, Castle::WhiteQueen], Piece::WhiteKing),
+            Color::Black => ([Castle::BlackKing, Castle::BlackQueen], Piece::BlackKing),
+        };
+
+        for castle in castles {
+            if self.can_castle(castle) {
+                let (_, king_path, _) = Self::castle_squares(castle);
+                move_list.add_move(Move::new(
+                    king_path[0],
+                    king_path[2],
+                    None,
+                    false,
+                    false,
+                    None,
+                    true,
+                    king,
+                ));
+            }
+        }
+    }
+
+    /// The Color castling, the squares the King starts on, crosses and lands on, and the
+    /// squares between the King and the Rook
+    fn castle_squares(castle: Castle) -> (Color, [Square; 3], &'static [Square]) {
+        match castle {
+            Castle::WhiteKing => (
+                Color::White,
+                [Square::E1, Square::F1, Square::G1],
+                &[Square::F1, Square::G1],
+            ),
+            Castle::WhiteQueen => (
+                Color::White,
+                [Square::E1, Square::D1, Square::C1],
+                &[Square::D1, Square::C1, Square::B1],
+            ),
+            Castle::BlackKing => (
+                Color::Black,
+                [Square::E8, Square::F8, Square::G8],
+                &[Square::F8, Square::G8],
+            ),
+            Castle::BlackQueen => (
+                Color::Black,
+                [Square::E8, Square::D8, Square::C8],
+                &[Square::D8, Square::C8, Square::B8],
+            ),
+        }
+    }
+
+    /// Whether castling is possible right now, ignoring whose turn it is
+    fn can_castle(&self, castle: Castle) -> bool {
         // NOTE: Castling Permission will only be available if King hasn't moved
         // and Rook hasn't either. We won't be checking that here.
-        match active_color {
-            Color::White => {
-                let non_active_color = Color::Black;
-
-                // Check if White has Kingside Castling Permission
-                if (self.castle_perm.0 & (Castle::WhiteKing as u8)) > 0
-                    // Check that squares between King and Rook are empty
-                    && self.board.pieces[Square::F1 as usize].is_none()
-                    && self.board.pieces[Square::G1 as usize].is_none()
-                    // The King can't start in check and any square the King crosses
-                    // or ends up in can't be attacked.
-                    // NOTE: we won't check the square that the King would land on
-                    // since we will be checking that when actually trying to make the move
-                    // and we don't want to do duplicate work if we can avoid it
-                    && !self.is_square_attacked(non_active_color, Square::E1)
-                    && !self.is_square_attacked(non_active_color, Square::F1)
-                {
-                    move_list.add_move(Move::new(
-                        Square::E1,
-                        Square::G1,
-                        None,
-                        false,
-                        false,
-                        None,
-                        true,
-                        Piece::WhiteKing,
-                    ));
-                }
+        let (color, king_path, between) = Self::castle_squares(castle);
+        (self.castle_perm.0 & (castle as u8)) > 0
+            && between
+                .iter()
+                .all(|&square| self.board.pieces[square as usize].is_none())
+            // The King can't start in check (from any piece, since its starting square is
+            // checked like any other square), cross an attacked square or land on one
+            && king_path
+                .iter()
+                .all(|&square| !self.is_square_attacked(color.opponent(), square))
+    }
 
-                // Check if White has Queenside Castling Permission
-                if (self.castle_perm.0 & (Castle::WhiteQueen as u8)) > 0
-                    && self.board.pieces[Square::D1 as usize].is_none()
-                    && self.board.pieces[Square::C1 as usize].is_none()
-                    && self.board.pieces[Square::B1 as usize].is_none()
-                    && !self.is_square_attacked(non_active_color, Square::E1)
-                    && !self.is_square_attacked(non_active_color, Square::D1)
-                {
-                    move_list.add_move(Move::new(
-                        Square::E1,
-                        Square::C1,
-                        None,
-                        false,
-                        false,
-                        None,
-                        true,
-                        Piece::WhiteKing,
-                    ));
-                }
-            }
-            Color::Black => {
-                let non_active_color = Color::White;
-
-                // Check if Black has Kingside Castling Permission
-                if (self.castle_perm.0 & (Castle::BlackKing as u8)) > 0
-                    && self.board.pieces[Square::F8 as usize].is_none()
-                    && self.board.pieces[Square::G8 as usize].is_none()
-                    && !self.is_square_attacked(non_active_color, Square::E8)
-                    && !self.is_square_attacked(non_active_color, Square::F8)
-                {
-                    move_list.add_move(Move::new(
-                        Square::E8,
-                        Square::G8,
-                        None,
-                        false,
-                        false,
-                        None,
-                        true,
-                        Piece::BlackKing,
-                    ));
-                }
+    /// Whether the provided color could castle kingside right now, e.g. for a GUI to show
+    /// castling as an option
+    pub fn can_castle_kingside(&self, color: Color) -> bool {
+        match color {
+            Color::White => self.can_castle(Castle::WhiteKing),
+            Color::Black => self.can_castle(Castle::BlackKing),
+        }
+    }
 
-                // Check if Black has Queenside Castling Permission
-                if (self.castle_perm.0 & (Castle::BlackQueen as u8)) > 0
-                    && self.board.pieces[Square::D8 as usize].is_none()
-                    && self.board.pieces[Square::C8 as usize].is_none()
-                    && self.board.pieces[Square::B8 as usize].is_none()
-                    && !self.is_square_attacked(non_active_color, Square::E8)
-                    && !self.is_square_attacked(non_active_color, Square::D8)
-                {
-                    move_list.add_move(Move::new(
-                        Square::E8,
-                        Square::C8,
-                        None,
-                        false,
-                        false,
-                        None,
-                        true,
-                        Piece::BlackKing,
-                    ));
-                }
-            }
+    /// Whether the provided color could castle queenside right now
+    pub fn can_castle_queenside(&self, color: Color) -> bool {
+        match color {
+            Color::White => self.can_castle(Castle::WhiteQueen),
+            Color::Black => self.can_castle(Castle::BlackQueen),
         }
     }
 
     /// Generates quite moves and captures for non pawn Pieces of specified active Color
     fn gen_non_pawn_moves(&self, active_color: Color, move_list: &mut MoveList) {
+        self.visit_non_pawn_moves(
+            active_color,
+            |piece, start_square, end_square, piece_captured| {
+                move_list.add_move(Move::new(
+                    start_square,
+                    end_square,
+                    piece_captured,
+                    false,
+                    false,
+                    None,
+                    false,
+                    piece,
+                ));
+            },
+        );
+    }
+
+    /// Count the pseudo-legal moves of the non pawn Pieces of the provided color, a measure
+    /// of how active they are. Kings are left out since the evaluation shouldn't encourage
+    /// them to walk out into the open.
+    pub fn mobility(&self, color: Color) -> i32 {
+        let mut mobility = 0;
+        self.visit_non_pawn_moves(color, |piece, _, _, _| {
+            if !piece.is_king() {
+                mobility += 1;
+            }
+        });
+        mobility
+    }
+
+    /// Walks the quite moves and captures for non pawn Pieces of specified active Color,
+    /// calling visit with the piece moved, its start and end squares and the captured piece
+    fn visit_non_pawn_moves(
+        &self,
+        active_color: Color,
+        mut visit: impl FnMut(Piece, Square, Square, Option<Piece>),
+    ) {
         let non_sliding_pieces = gen_non_sliding_pieces!(active_color);
         let sliding_pieces = gen_sliding_pieces!(active_color);
 
-        let non_active_color = match active_color {
-            Color::White => Color::Black,
-            Color::Black => Color::White,
-        };
+        let non_active_color = active_color.opponent();
 
         for piece in non_sliding_pieces {
             let piece_count = self.board.get_piece_count()[piece as usize];
@@ -928,29 +1416,11 @@ impl Gamestate {
                             Some(end_piece) => {
                                 // valid capture
                                 if end_piece.get_color() == non_active_color {
-                                    move_list.add_move(Move::new(
-                                        start_square,
-                                        end_square,
-                                        Some(end_piece),
-                                        false,
-                                        false,
-                                        None,
-                                        false,
-                                        piece,
-                                    ));
+                                    visit(piece, start_square, end_square, Some(end_piece));
                                 }
                             }
                             None => {
-                                move_list.add_move(Move::new(
-                                    start_square,
-                                    end_square,
-                                    None,
-                                    false,
-                                    false,
-                                    None,
-                                    false,
-                                    piece,
-                                ));
+                                visit(piece, start_square, end_square, None);
                             }
                         }
                     }
@@ -974,32 +1444,14 @@ impl Gamestate {
                             Some(end_piece) => {
                                 // valid capture
                                 if end_piece.get_color() == non_active_color {
-                                    move_list.add_move(Move::new(
-                                        start_square,
-                                        end_square,
-                                        Some(end_piece),
-                                        false,
-                                        false,
-                                        None,
-                                        false,
-                                        piece,
-                                    ));
+                                    visit(piece, start_square, end_square, Some(end_piece));
                                 }
                                 // if you hit a piece you can't keep sliding
                                 break;
                             }
                             // No capture
                             None => {
-                                move_list.add_move(Move::new(
-                                    start_square,
-                                    end_square,
-                                    None,
-                                    false,
-                                    false,
-                                    None,
-                                    false,
-                                    piece,
-                                ));
+                                visit(piece, start_square, end_square, None);
                             }
                         }
                         // set up for next slide check
@@ -1021,7 +1473,6 @@ impl Gamestate {
             promotion_rank,
             promotion_targets,
             attack_directions,
-            non_active_color,
             non_active_pawn,
         ) = match active_color {
             Color::White => {
@@ -1031,7 +1482,6 @@ impl Gamestate {
                 let pawn_promotion_rank = Rank::Rank7; // Rank right before promotion occurs
                 let pawn_promotion_targets = WHITE_PAWN_PROMOTION_TARGETS;
                 let pawn_attack_directions = pawn.get_attack_directions();
-                let non_active_color = Color::Black;
                 let non_active_pawn = Piece::BlackPawn;
                 (
                     pawn,
@@ -1040,7 +1490,6 @@ impl Gamestate {
                     pawn_promotion_rank,
                     pawn_promotion_targets,
                     pawn_attack_directions,
-                    non_active_color,
                     non_active_pawn,
                 )
             }
@@ -1051,7 +1500,6 @@ impl Gamestate {
                 let pawn_promotion_rank = Rank::Rank2; // Rank right before promotion occurs
                 let pawn_promotion_targets = BLACK_PAWN_PROMOTION_TARGETS;
                 let pawn_attack_directions = pawn.get_attack_directions();
-                let non_active_color = Color::White;
                 let non_active_pawn = Piece::WhitePawn;
                 (
                     pawn,
@@ -1060,11 +1508,11 @@ impl Gamestate {
                     pawn_promotion_rank,
                     pawn_promotion_targets,
                     pawn_attack_directions,
-                    non_active_color,
                     non_active_pawn,
                 )
             }
         };
+        let non_active_color = active_color.opponent();
 
         let pawn_count = self.board.get_piece_count()[pawn as usize] as usize;
 
@@ -1076,7 +1524,9 @@ impl Gamestate {
             if let Ok(square_ahead) = square_ahead {
                 let rank = start_square.get_rank();
 
-                let mut is_promotion = false;
+                // NOTE: promotion is mandatory. This has to be known even if the square ahead
+                // is blocked since captures from the promotion rank also promote
+                let is_promotion = rank == promotion_rank;
 
                 // Add move to move_list if square ahead is empty (possibly two ahead as well)
                 if self.board.pieces[square_ahead as usize].is_none() {
@@ -1117,8 +1567,6 @@ impl Gamestate {
                         }
                         // Check if promotion (one ahead)
                         pawn_promotion_rank if (pawn_promotion_rank == promotion_rank) => {
-                            is_promotion = true; // NOTE: promotion is mandatory
-
                             for promotion in promotion_targets {
                                 let _move = Move::new(
                                     start_square,
@@ -1256,146 +1704,701 @@ impl Gamestate {
         Ok(move_list)
     }
 
-    //=========================== BUILDING ==============================
+    /// Generate the pseudo-legal captures (including en passant) for the given Color
+    pub fn gen_captures(&self, color: Color, move_list: &mut MoveList) {
+        let mut pseudo_legal_moves = MoveList::new();
+        self.gen_pawn_moves(color, &mut pseudo_legal_moves);
+        self.gen_non_pawn_moves(color, &mut pseudo_legal_moves);
 
-    /// Generate a hash that represents the current position via Zobrist Hashing
-    fn init_position_key(&mut self) {
-        let mut position_key: u64 = 0;
+        for move_ in pseudo_legal_moves.moves.into_iter().flatten() {
+            if move_.is_capture() {
+                move_list.add_move(move_);
+            }
+        }
+    }
 
-        // Color (which player's turn) component
-        if self.active_color == Color::White {
-            let color_key = ZOBRIST
-                .lock()
-                .expect("Mutex holding ZOBRIST should not be poisoned")
-                .color_key;
+    /// Generate the pseudo-legal moves for the given Color that could get its King out of
+    /// check: King moves, captures of the checking piece and blocks. In double check only King
+    /// moves can help. If the King isn't in check every pseudo-legal move is generated.
+    pub fn gen_evasions(&self, color: Color, move_list: &mut MoveList) {
+        let mut pseudo_legal_moves = MoveList::new();
+        self.gen_pawn_moves(color, &mut pseudo_legal_moves);
+        self.gen_non_pawn_moves(color, &mut pseudo_legal_moves);
+
+        let checkers = match self.board.kings_square[color as usize] {
+            Some(kings_square) => self.attackers_of(color.opponent(), kings_square),
+            None => Vec::new(),
+        };
+        if checkers.is_empty() {
+            self.gen_castling_moves(color, &mut pseudo_legal_moves);
+            for move_ in pseudo_legal_moves.moves.into_iter().flatten() {
+                move_list.add_move(move_);
+            }
+            return;
+        }
 
-            // Note Color::Black is encoded via absence
-            position_key ^= color_key;
+        // squares a piece other than the King has to move to in order to resolve the check
+        let targets = match checkers.as_slice() {
+            [checker] => {
+                let kings_square = self.board.kings_square[color as usize]
+                    .expect("King should be on the board since it is in check");
+                let mut targets = squares_between(kings_square, *checker);
+                targets.push(*checker);
+                targets
+            }
+            _ => Vec::new(),
         };
 
-        // Piece location component
-        for (square_index, piece_at_square) in self.board.pieces.iter().enumerate() {
-            if let Some(piece) = *piece_at_square {
-                let piece_keys = ZOBRIST
-                    .lock()
-                    .expect("Mutex holding ZOBRIST should not be poisoned")
-                    .piece_keys;
-
-                // for each piece present on the board find its randomly generated value in the Zobrist
-                // struct's piece_keys array and XOR with the current Gamestate's position_key
-                position_key ^= piece_keys[piece as usize][idx_120_to_64!(square_index)];
+        for move_ in pseudo_legal_moves.moves.into_iter().flatten() {
+            let piece_moved = move_
+                .get_piece_moved()
+                .expect("generated moves should always have a valid moved piece");
+            let end = move_
+                .get_end()
+                .expect("generated moves should always have a valid end");
+            // en passant captures a pawn that isn't on the end square
+            let captured_square = match (move_.is_en_passant(), color) {
+                (true, Color::White) => (end - NUM_BOARD_COLUMNS as i8).ok(),
+                (true, Color::Black) => (end + NUM_BOARD_COLUMNS as i8).ok(),
+                (false, _) => Some(end),
+            };
+
+            if piece_moved.is_king()
+                || targets.contains(&end)
+                || captured_square.is_some_and(|square| targets.contains(&square))
+            {
+                move_list.add_move(move_);
             }
         }
+    }
 
-        // En Passant component
-        if let Some(square) = self.en_passant {
-            let en_passant_keys = ZOBRIST
-                .lock()
-                .expect("Mutex holding ZOBRIST should not be poisoned")
-                .en_passant_keys;
-
-            position_key ^= en_passant_keys[square.get_file() as usize];
+    /// Generate all legal moves for the current Gamestate by filtering the
+    /// pseudo-legal moves from gen_move_list through make_move/undo_move
+    pub fn gen_legal_moves(&mut self) -> Result<MoveList, MoveGenError> {
+        let move_list = self.gen_move_list()?;
+        let mut legal_moves = MoveList::new();
+
+        for move_ in move_list.moves.into_iter().flatten() {
+            if self.make_move_unchecked(move_).is_ok() {
+                self.undo_move()
+                    .expect("undoing a move that was just made should never fail");
+                legal_moves.add_move(move_);
+            }
         }
 
-        // Castle Permissions component
-        let castle_keys = ZOBRIST
-            .lock()
-            .expect("Mutex holding ZOBRIST should not be poisoned")
-            .castle_keys;
+        Ok(legal_moves)
+    }
 
-        position_key ^= castle_keys[self.castle_perm.0 as usize];
+    /// Count the legal moves for the current Gamestate without collecting them
+    pub fn legal_move_count(&mut self) -> Result<usize, MoveGenError> {
+        let move_list = self.gen_move_list()?;
+        let mut count = 0;
+        for move_ in move_list.moves.into_iter().flatten() {
+            if self.make_move_unchecked(move_).is_ok() {
+                self.undo_move()
+                    .expect("undoing a move that was just made should never fail");
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
 
-        self.position_key = PositionKey(position_key);
+    /// Whether the active color has any legal move. Stops at the first legal move found,
+    /// which is all that's needed to tell if the game is over.
+    pub fn has_legal_moves(&mut self) -> Result<bool, MoveGenError> {
+        let move_list = self.gen_move_list()?;
+        for move_ in move_list.moves.into_iter().flatten() {
+            if self.make_move_unchecked(move_).is_ok() {
+                self.undo_move()
+                    .expect("undoing a move that was just made should never fail");
+                return Ok(true);
+            }
+        }
+        Ok(false)
     }
 
-    /// Check that the gamestate is valid for the given a validity check mode
-    pub fn check_gamestate(
-        &self,
-        validity_check: ValidityCheck,
-    ) -> Result<(), GamestateValidityCheckError> {
-        if let ValidityCheck::Strict = validity_check {
-            // check board is valid
-            self.board.check_board(validity_check)?;
+    /// Same as gen_legal_moves, but the result is kept until the next make_move/undo_move so
+    /// that repeated calls on the same position (e.g. every frame of a GUI) don't regenerate it
+    pub fn cached_legal_moves(&mut self) -> Result<&MoveList, MoveGenError> {
+        let is_cached = self
+            .legal_moves_cache
+            .as_ref()
+            .is_some_and(|(position_key, _)| *position_key == self.position_key);
+        if !is_cached {
+            let legal_moves = self.gen_legal_moves()?;
+            self.legal_moves_cache = Some((self.position_key, legal_moves));
+        }
 
-            // check that halfmove clock doesn't violate the 50 move rule
-            if self.halfmove_clock >= HALF_MOVE_MAX {
-                return Err(GamestateValidityCheckError::StrictHalfmoveClockExceedsMax {
-                    halfmove_clock: self.halfmove_clock,
-                });
-            }
+        let (_, legal_moves) = self
+            .legal_moves_cache
+            .as_ref()
+            .expect("legal_moves_cache should have just been filled");
+        Ok(legal_moves)
+    }
 
-            // check that fullmove count is in valid range 1..=MAX_GAME_MOVES
-            if !(1..=MAX_GAME_MOVES).contains(&self.fullmove_count) {
-                return Err(GamestateValidityCheckError::StrictFullmoveCountNotInRange {
-                    fullmove_count: self.fullmove_count,
-                });
-            }
+    /// Legal moves of the piece standing on square, e.g. to show a GUI player where a piece can
+    /// go. Uses cached_legal_moves so it's cheap to call for every square of the same position.
+    pub fn legal_moves_from(&mut self, square: Square) -> Result<Vec<Move>, MoveGenError> {
+        let legal_moves = self.cached_legal_moves()?;
+        Ok(legal_moves
+            .moves
+            .iter()
+            .flatten()
+            .filter(|move_| move_.get_start() == Ok(square))
+            .copied()
+            .collect())
+    }
 
-            // check that fullmove count and halfmove clock are plausible
-            // NOTE: fullmove_count starts at 1 and increments every time black moves
-            // halfmove_clock starts at 0 and increases everytime a player makes a move that does not
-            // move a pawn or capture a piece.
-            // Initial setup is active color: white, fullmove: 1, halfmove: 0
-            // so 2 * (1 - 1) + 0 = 0 which is not less than 0
-            // Now if white moves a knight out, you should have color: black, fullmove: 1, halfmove: 1
-            // so 2*(1 - 1) + 1 = 1 which is not less than 1
-            // Now say black moves a pawn but we get back color: white, fullmove: 2, halfmove: 2
-            // so 2*(2 - 1) + 0 = 2  which is not less than 2
-            // we can't catch that kind of mistake here because for all we know black moved a knight
-            // but let's say that we get back color: white, fullmove: 1, halfmove: 2
-            // in order to get halfmove: 2, white had to play a knight, then black had to play a knight
-            // as well which should have incremented fullmove. That's what's being caught here
-            if (2 * (self.fullmove_count - 1) + self.active_color as usize)
-                < self.halfmove_clock as usize
-            {
-                return Err(
-                GamestateValidityCheckError::StrictFullmoveCountLessThanHalfmoveClockDividedByTwo {
-                    fullmove_count: self.fullmove_count,
-                    halfmove_clock: self.halfmove_clock,
-                });
-            }
+    /// Legal moves grouped by the square of the piece making them, e.g. for a move list UI.
+    /// Pieces without legal moves are left out, and castling is listed under the King.
+    pub fn grouped_legal_moves(&mut self) -> Result<HashMap<Square, Vec<Move>>, MoveGenError> {
+        let legal_moves = self.cached_legal_moves()?;
+        let mut grouped: HashMap<Square, Vec<Move>> = HashMap::new();
+        for move_ in legal_moves.moves.iter().flatten() {
+            let start = move_
+                .get_start()
+                .expect("legal moves should have a valid start square");
+            grouped.entry(start).or_default().push(*move_);
+        }
+        Ok(grouped)
+    }
 
-            //====================== EN PASSANT CHECKS ========================
+    /// Legal move picked uniformly at random, None if there are none (or they can't be
+    /// generated). The weakest possible opponent, also handy for self-play fuzzing.
+    pub fn random_legal_move(&mut self, rng: &mut impl Rng) -> Option<Move> {
+        let legal_moves = self.gen_legal_moves().ok()?;
+        let legal_moves = legal_moves.moves.into_iter().flatten().collect::<Vec<_>>();
+        legal_moves.choose(rng).copied()
+    }
 
-            if let Some(en_passant) = self.en_passant {
-                // TODO: this seems wrong, investigate
-                // Take in a Square64 so that you can't be given an invalid square but then cast it to 120 format
-                // so that we can use it as an index properly
-                let en_passant = Square::from(en_passant);
+    /// Every legal move in UCI notation, e.g. for debug dumps or a UCI frontend. Empty only
+    /// if the game is over by checkmate or stalemate.
+    pub fn legal_moves_uci(&mut self) -> Result<Vec<String>, MoveGenError> {
+        let legal_moves = self.gen_legal_moves()?;
+        Ok(legal_moves
+            .moves
+            .iter()
+            .flatten()
+            .map(Move::to_uci)
+            .collect())
+    }
 
-                // check that if there is an en passant square, the halfmove clock must be 0 (pawn just moved resets clock)
-                if !matches!(self.halfmove_clock, 0) {
-                    return Err(
-                        GamestateValidityCheckError::StrictEnPassantHalfmoveClockNotZero {
-                            halfmove_clock: self.halfmove_clock,
-                        },
-                    );
+    /// Determine whether the game is over, and if so how it ended. Checkmate takes precedence
+    /// over the fifty move rule, which takes precedence over a dead position
+    pub fn status(&mut self) -> Result<Option<GameResult>, MoveGenError> {
+        if !self.has_legal_moves()? {
+            let result = if self.is_in_check() {
+                GameResult::Checkmate {
+                    winner: self.active_color.opponent(),
                 }
+            } else {
+                GameResult::Stalemate
+            };
+            return Ok(Some(result));
+        }
 
-                // check that en passant square is on proper rank given active color
-                let rank = en_passant.get_rank();
-                match rank {
-                    // White pawn just moved up by two spaces
-                    // If active color is black then en_passant rank has to be 3.
-                    Rank::Rank3 => {
-                        match self.active_color {
-                            Color::Black => {
-                                // check that the en passant square is empty
-                                if let Some(_piece) = self.board.pieces[en_passant as usize] {
-                                    return Err(
-                                        GamestateValidityCheckError::StrictEnPassantNotEmpty {
-                                            en_passant_square: en_passant,
-                                        },
-                                    );
-                                }
+        if self.halfmove_clock >= HALF_MOVE_MAX {
+            return Ok(Some(GameResult::DrawFiftyMove));
+        }
 
-                                // check that the square behind the en_passant square is empty
-                                let square_behind_index = en_passant as usize - NUM_BOARD_COLUMNS;
-                                if let Some(_piece) = self.board.pieces[square_behind_index] {
-                                    return Err(
-                                    GamestateValidityCheckError::StrictEnPassantSquareBehindNotEmpty {
-                                        square_behind: Square::try_from(square_behind_index)
-                                            .expect(
+        if self.is_dead_position() {
+            return Ok(Some(GameResult::DrawDeadPosition));
+        }
+
+        Ok(None)
+    }
+
+    /// Whether no sequence of legal moves can lead to a capture or a pawn move, so neither
+    /// side can ever be checkmated. This is hard to tell in general, so only the tractable
+    /// case is recognised: nothing but Kings and pawns are left, every pawn is blocked by
+    /// another pawn and can't capture, and neither King can walk up to an enemy pawn.
+    /// NOTE: a position with only the two Kings left counts as dead too
+    fn is_dead_position(&self) -> bool {
+        if self.en_passant.is_some() {
+            return false;
+        }
+        let piece_at = |square: Result<Square, SquareConversionError>| {
+            square
+                .ok()
+                .and_then(|square| self.board.pieces[square as usize])
+        };
+
+        for square in Square::iter() {
+            let Some(piece) = self.board.pieces[square as usize] else {
+                continue;
+            };
+            match piece.get_piece_type() {
+                PieceType::King => {}
+                PieceType::Pawn => {
+                    let forward = match piece.get_color() {
+                        Color::White => WHITE_PAWN_VERTICAL_DIRECTION,
+                        Color::Black => BLACK_PAWN_VERTICAL_DIRECTION,
+                    };
+                    let is_blocked =
+                        piece_at(square + forward).is_some_and(|ahead| ahead.is_pawn());
+                    let can_capture = piece.get_attack_directions().into_iter().any(|direction| {
+                        piece_at(square + direction).is_some_and(|target| {
+                            target.is_pawn() && target.get_color() != piece.get_color()
+                        })
+                    });
+                    if !is_blocked || can_capture {
+                        return false;
+                    }
+                }
+                _ => return false,
+            }
+        }
+
+        !self.king_can_reach_enemy_pawn(Color::White)
+            && !self.king_can_reach_enemy_pawn(Color::Black)
+    }
+
+    /// Whether the King of the color can get next to an enemy pawn. Only pawns are treated
+    /// as obstacles, the enemy King could always step out of the way.
+    fn king_can_reach_enemy_pawn(&self, color: Color) -> bool {
+        let Some(king_square) = self.board.kings_square[color as usize] else {
+            return false;
+        };
+        let enemy_pawn = match color {
+            Color::White => Piece::BlackPawn,
+            Color::Black => Piece::WhitePawn,
+        };
+        let piece_at = |square: Result<Square, SquareConversionError>| {
+            square
+                .ok()
+                .and_then(|square| self.board.pieces[square as usize])
+        };
+        let is_attacked_by_pawn = |square: Square| {
+            enemy_pawn
+                .get_attack_directions()
+                .into_iter()
+                .any(|direction| piece_at(square - direction) == Some(enemy_pawn))
+        };
+
+        let mut visited = [false; NUM_INTERNAL_BOARD_SQUARES];
+        visited[king_square as usize] = true;
+        let mut to_visit = vec![king_square];
+        while let Some(square) = to_visit.pop() {
+            for direction in Piece::WhiteKing.get_attack_directions() {
+                let Ok(neighbour) = square + direction else {
+                    continue;
+                };
+                match self.board.pieces[neighbour as usize] {
+                    Some(piece) if piece == enemy_pawn => return true,
+                    Some(piece) if piece.is_pawn() => {}
+                    _ if visited[neighbour as usize] || is_attacked_by_pawn(neighbour) => {}
+                    _ => {
+                        visited[neighbour as usize] = true;
+                        to_visit.push(neighbour);
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    /// Whether the active color has been checkmated, see status
+    pub fn is_checkmate(&mut self) -> bool {
+        matches!(self.status(), Ok(Some(GameResult::Checkmate { .. })))
+    }
+
+    /// Whether the active color is stalemated, see status
+    pub fn is_stalemate(&mut self) -> bool {
+        matches!(self.status(), Ok(Some(GameResult::Stalemate)))
+    }
+
+    /// Whether the current position has already occurred twice before, i.e. the game can be
+    /// drawn by threefold repetition. Positions are compared by PositionKey, so the side to
+    /// move, castle permissions and en passant square have to match too. Only positions since
+    /// the last capture or pawn move are checked since earlier ones can never repeat.
+    pub fn is_threefold_repetition(&self) -> bool {
+        self.repetition_count() >= 3
+    }
+
+    /// How many times the current position has occurred, counting the current occurrence, so
+    /// it's 1 for a position that has never been seen before. Like is_threefold_repetition,
+    /// only positions since the last capture or pawn move are checked.
+    pub fn repetition_count(&self) -> usize {
+        // NOTE: skip the dummy Undo for the initial state, it has the same position_key as
+        // the Undo of the first move
+        let repetitions = self
+            .history
+            .iter()
+            .skip(1)
+            .rev()
+            .take(self.halfmove_clock as usize)
+            .filter(|undo| undo.position_key == self.position_key)
+            .count();
+        repetitions + 1
+    }
+
+    /// PositionKeys of every position in the history, from the oldest to the current one
+    pub fn positions_seen(&self) -> impl Iterator<Item = PositionKey> + '_ {
+        // NOTE: each Undo holds the key of the position before its Move was made, and the
+        // dummy Undo for the initial state repeats the key of the first one
+        self.history
+            .iter()
+            .skip(1)
+            .map(|undo| undo.position_key)
+            .chain(std::iter::once(self.position_key))
+    }
+
+    /// Drop all but the last keep_last moves from the history, for long running tools that
+    /// don't need to undo all the way back to the start. Only the kept moves can be undone.
+    /// NOTE: is_threefold_repetition only sees positions that are still in the history, so
+    /// repeats of positions that were truncated away are no longer counted
+    pub fn truncate_history(&mut self, keep_last: usize) {
+        // NOTE: the dummy Undo for the initial state is kept so undo_move still knows where
+        // the history starts
+        let num_moves = self.history.len().saturating_sub(1);
+        if keep_last < num_moves {
+            self.history.drain(1..=num_moves - keep_last);
+        }
+    }
+
+    //================================= ANALYSIS ==============================
+
+    /// Replay the game from the start of the history, comparing each played move with the
+    /// engine's best move searched to depth (at least 2). A large delta marks a blunder.
+    /// Annotations are in ply order.
+    pub fn annotate_history(&self, evaluator: &dyn Evaluator, depth: usize) -> Vec<Annotation> {
+        let depth = depth.max(2);
+        let mut replay = self.history_start();
+
+        let mut annotations = Vec::with_capacity(self.history.len().saturating_sub(1));
+        // NOTE: skip the dummy Undo for the initial state, its Move was never made
+        for played in self.history.iter().skip(1).map(|undo| undo.move_) {
+            let best = search_with_evaluator(&mut replay, depth, evaluator)
+                .expect("positions from the history should be valid");
+            let best_move = best
+                .best_move
+                .expect("a move was played from this position so it has legal moves");
+            replay
+                .make_move(played)
+                .expect("moves from the history should still be legal");
+
+            // NOTE: one ply shallower since the played move already used up the first ply
+            let played_score = match played == best_move {
+                true => best.score,
+                false => {
+                    -search_with_evaluator(&mut replay, depth - 1, evaluator)
+                        .expect("positions from the history should be valid")
+                        .score
+                }
+            };
+            annotations.push(Annotation {
+                played,
+                best_move,
+                best_score: best.score,
+                played_score,
+                delta: (best.score - played_score).max(0),
+            });
+        }
+        annotations
+    }
+
+    /// A copy of this Gamestate with every move in the history undone
+    fn history_start(&self) -> Gamestate {
+        let mut start = self.clone();
+        while start.undo_move().is_ok() {}
+        start
+    }
+
+    //================================= BINARY FORMAT =========================
+
+    /// Encode the game as the FEN of the start of the history followed by the played moves
+    /// packed into u32s (see Move::to_packed), after GAME_BYTES_MAGIC and GAME_BYTES_VERSION.
+    /// Much smaller than PGN when storing many games.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let moves: Vec<u32> = self
+            .history
+            .iter()
+            .skip(1)
+            .map(|undo| undo.move_.to_packed())
+            .collect();
+
+        let mut bytes = GAME_BYTES_MAGIC.to_vec();
+        bytes.push(GAME_BYTES_VERSION);
+        self.history_start().to_fen().ser_bin(&mut bytes);
+        moves.ser_bin(&mut bytes);
+        bytes
+    }
+
+    /// Decode a game written by to_bytes, replaying every move so the history can be undone.
+    /// Each move has to be legal in the position it is played from.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Gamestate, GameBytesError> {
+        let header_len = GAME_BYTES_MAGIC.len();
+        if !bytes.starts_with(&GAME_BYTES_MAGIC) {
+            return Err(GameBytesError::InvalidMagic {
+                expected: GAME_BYTES_MAGIC,
+            });
+        }
+        let version = *bytes
+            .get(header_len)
+            .ok_or(GameBytesError::Decode { offset: header_len })?;
+        if version != GAME_BYTES_VERSION {
+            return Err(GameBytesError::UnsupportedVersion {
+                version,
+                expected: GAME_BYTES_VERSION,
+            });
+        }
+
+        let mut offset = header_len + 1;
+        Self::check_length_prefix(bytes, offset, 1)?;
+        let fen = String::de_bin(&mut offset, bytes)
+            .map_err(|err| GameBytesError::Decode { offset: err.o })?;
+        Self::check_length_prefix(bytes, offset, mem::size_of::<u32>())?;
+        let moves = Vec::<u32>::de_bin(&mut offset, bytes)
+            .map_err(|err| GameBytesError::Decode { offset: err.o })?;
+        if offset != bytes.len() {
+            return Err(GameBytesError::TrailingBytes {
+                count: bytes.len() - offset,
+            });
+        }
+
+        let mut gamestate = Gamestate::try_from(fen.as_str())?;
+        for (index, packed) in moves.into_iter().enumerate() {
+            let move_ = Move::from_packed(packed)
+                .map_err(|source| GameBytesError::InvalidMove { index, source })?;
+            gamestate
+                .make_move_checked(move_)
+                .map_err(|source| GameBytesError::IllegalMove { index, source })?;
+        }
+        Ok(gamestate)
+    }
+
+    /// The bytes are untrusted, so make sure the length prefix at offset can't claim more
+    /// items of item_size bytes than are left before nanoserde allocates for them
+    fn check_length_prefix(
+        bytes: &[u8],
+        offset: usize,
+        item_size: usize,
+    ) -> Result<(), GameBytesError> {
+        let mut items_offset = offset;
+        let length = usize::de_bin(&mut items_offset, bytes)
+            .map_err(|err| GameBytesError::Decode { offset: err.o })?;
+        let remaining = bytes.len() - items_offset;
+        if !matches!(length.checked_mul(item_size), Some(size) if size <= remaining) {
+            return Err(GameBytesError::LengthTooLong {
+                offset,
+                length,
+                remaining,
+            });
+        }
+        Ok(())
+    }
+
+    //================================= PERFT =================================
+
+    /// Count the leaf nodes of the legal move tree to the given depth
+    pub fn perft(&mut self, depth: usize) -> u64 {
+        self.perft_with(depth, &mut |_| {})
+    }
+
+    /// Same as perft, but f is called with every leaf position as it is reached, e.g. to
+    /// count leaves with some property or to collect their position keys
+    pub fn perft_with<F: FnMut(&Gamestate)>(&mut self, depth: usize, f: &mut F) -> u64 {
+        if depth == 0 {
+            f(self);
+            return 1;
+        }
+
+        let move_list = self
+            .gen_move_list()
+            .expect("perft should only be run on a valid Gamestate");
+        let mut nodes = 0;
+        for move_ in move_list.moves.into_iter().flatten() {
+            if self.make_move_unchecked(move_).is_ok() {
+                nodes += self.perft_with(depth - 1, f);
+                self.undo_move()
+                    .expect("undoing a move that was just made should never fail");
+            }
+        }
+        nodes
+    }
+
+    /// Same as perft, but node counts of subtrees are cached by PositionKey and depth so
+    /// that positions reached by transposition are only counted once
+    pub fn perft_hash(&mut self, depth: usize) -> u64 {
+        let mut perft_table = HashMap::new();
+        self.perft_hash_inner(depth, &mut perft_table)
+    }
+
+    fn perft_hash_inner(
+        &mut self,
+        depth: usize,
+        perft_table: &mut HashMap<(PositionKey, usize), u64>,
+    ) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        if let Some(&nodes) = perft_table.get(&(self.position_key, depth)) {
+            return nodes;
+        }
+
+        let move_list = self
+            .gen_move_list()
+            .expect("perft should only be run on a valid Gamestate");
+        let mut nodes = 0;
+        for move_ in move_list.moves.into_iter().flatten() {
+            if self.make_move_unchecked(move_).is_ok() {
+                nodes += self.perft_hash_inner(depth - 1, perft_table);
+                self.undo_move()
+                    .expect("undoing a move that was just made should never fail");
+            }
+        }
+
+        perft_table.insert((self.position_key, depth), nodes);
+        nodes
+    }
+
+    //=========================== BUILDING ==============================
+
+    /// Generate a hash that represents the current position via Zobrist Hashing
+    fn init_position_key(&mut self) {
+        let mut position_key: u64 = 0;
+
+        // Color (which player's turn) component
+        if self.active_color == Color::White {
+            let color_key = ZOBRIST
+                .lock()
+                .expect("Mutex holding ZOBRIST should not be poisoned")
+                .color_key;
+
+            // Note Color::Black is encoded via absence
+            position_key ^= color_key;
+        };
+
+        // Piece location component
+        for (square, piece) in self.board.iter_pieces() {
+            let piece_keys = ZOBRIST
+                .lock()
+                .expect("Mutex holding ZOBRIST should not be poisoned")
+                .piece_keys;
+
+            // for each piece present on the board find its randomly generated value in the Zobrist
+            // struct's piece_keys array and XOR with the current Gamestate's position_key
+            position_key ^= piece_keys[piece as usize][Square64::from(square) as usize];
+        }
+
+        // En Passant component
+        if let Some(square) = self.en_passant {
+            let en_passant_keys = ZOBRIST
+                .lock()
+                .expect("Mutex holding ZOBRIST should not be poisoned")
+                .en_passant_keys;
+
+            position_key ^= en_passant_keys[square.get_file() as usize];
+        }
+
+        // Castle Permissions component
+        let castle_keys = ZOBRIST
+            .lock()
+            .expect("Mutex holding ZOBRIST should not be poisoned")
+            .castle_keys;
+
+        position_key ^= castle_keys[self.castle_perm.0 as usize];
+
+        self.position_key = PositionKey(position_key);
+    }
+
+    /// Check that the gamestate is valid for the given a validity check mode
+    pub fn check_gamestate(
+        &self,
+        validity_check: ValidityCheck,
+    ) -> Result<(), GamestateValidityCheckError> {
+        if let ValidityCheck::Strict | ValidityCheck::Chess960 = validity_check {
+            // check board is valid
+            self.board.check_board(validity_check)?;
+
+            // check that halfmove clock is plausible. Reaching the 50 move rule is not an error,
+            // it's reported as a draw by status
+            if self.halfmove_clock > HALF_MOVE_STRICT_MAX {
+                return Err(GamestateValidityCheckError::StrictHalfmoveClockExceedsMax {
+                    halfmove_clock: self.halfmove_clock,
+                });
+            }
+
+            // check that fullmove count is in valid range 1..=MAX_GAME_MOVES
+            if !(1..=MAX_GAME_MOVES).contains(&self.fullmove_count) {
+                return Err(GamestateValidityCheckError::StrictFullmoveCountNotInRange {
+                    fullmove_count: self.fullmove_count,
+                });
+            }
+
+            // check that fullmove count and halfmove clock are plausible
+            // NOTE: fullmove_count starts at 1 and increments every time black moves
+            // halfmove_clock starts at 0 and increases everytime a player makes a move that does not
+            // move a pawn or capture a piece.
+            // Initial setup is active color: white, fullmove: 1, halfmove: 0
+            // so 2 * (1 - 1) + 0 = 0 which is not less than 0
+            // Now if white moves a knight out, you should have color: black, fullmove: 1, halfmove: 1
+            // so 2*(1 - 1) + 1 = 1 which is not less than 1
+            // Now say black moves a pawn but we get back color: white, fullmove: 2, halfmove: 2
+            // so 2*(2 - 1) + 0 = 2  which is not less than 2
+            // we can't catch that kind of mistake here because for all we know black moved a knight
+            // but let's say that we get back color: white, fullmove: 1, halfmove: 2
+            // in order to get halfmove: 2, white had to play a knight, then black had to play a knight
+            // as well which should have incremented fullmove. That's what's being caught here
+            if (2 * (self.fullmove_count - 1) + self.active_color as usize)
+                < self.halfmove_clock as usize
+            {
+                return Err(
+                GamestateValidityCheckError::StrictFullmoveCountLessThanHalfmoveClockDividedByTwo {
+                    fullmove_count: self.fullmove_count,
+                    halfmove_clock: self.halfmove_clock,
+                });
+            }
+
+            //====================== CASTLE PERMISSION CHECKS =================
+
+            for castle in Castle::iter() {
+                if self.castle_perm.0 & castle as u8 != 0 {
+                    self.check_castle_perm(castle, validity_check)?;
+                }
+            }
+
+            //====================== EN PASSANT CHECKS ========================
+
+            if let Some(en_passant) = self.en_passant {
+                // TODO: this seems wrong, investigate
+                // Take in a Square64 so that you can't be given an invalid square but then cast it to 120 format
+                // so that we can use it as an index properly
+                let en_passant = Square::from(en_passant);
+
+                // check that if there is an en passant square, the halfmove clock must be 0 (pawn just moved resets clock)
+                if !matches!(self.halfmove_clock, 0) {
+                    return Err(
+                        GamestateValidityCheckError::StrictEnPassantHalfmoveClockNotZero {
+                            halfmove_clock: self.halfmove_clock,
+                        },
+                    );
+                }
+
+                // check that en passant square is on proper rank given active color
+                let rank = en_passant.get_rank();
+                match rank {
+                    // White pawn just moved up by two spaces
+                    // If active color is black then en_passant rank has to be 3.
+                    Rank::Rank3 => {
+                        match self.active_color {
+                            Color::Black => {
+                                // check that the en passant square is empty
+                                if let Some(_piece) = self.board.pieces[en_passant as usize] {
+                                    return Err(
+                                        GamestateValidityCheckError::StrictEnPassantNotEmpty {
+                                            en_passant_square: en_passant,
+                                        },
+                                    );
+                                }
+
+                                // check that the square behind the en_passant square is empty
+                                let square_behind_index = en_passant as usize - NUM_BOARD_COLUMNS;
+                                if let Some(_piece) = self.board.pieces[square_behind_index] {
+                                    return Err(
+                                    GamestateValidityCheckError::StrictEnPassantSquareBehindNotEmpty {
+                                        square_behind: Square::try_from(square_behind_index)
+                                            .expect(
                                             "should never fail since we know that we are on rank 3",
                                         ),
                                     },
@@ -1497,24 +2500,227 @@ impl Gamestate {
                         });
                     }
                 }
+
+                // The checks above make sure the pawn is where a double push would have put it
+                // and that the square it came from is empty. If the active King is in check
+                // though, the push must explain it: either the pushed pawn gives the check,
+                // or the pawn uncovered a slider by leaving its starting square. Any other
+                // checker would have been giving check before the push, which is impossible.
+                // e.g. rejects 4k3/8/3N4/8/4P3/8/8/4K3 b - e3 0 1 (the Knight on d6 was
+                // already checking Black's King when White pushed e2e4)
+                let (pushed_pawn_square, starting_square) = match self.active_color {
+                    Color::White => (
+                        en_passant - NUM_BOARD_COLUMNS as i8,
+                        en_passant + NUM_BOARD_COLUMNS as i8,
+                    ),
+                    Color::Black => (
+                        en_passant + NUM_BOARD_COLUMNS as i8,
+                        en_passant - NUM_BOARD_COLUMNS as i8,
+                    ),
+                };
+                let pushed_pawn_square = pushed_pawn_square
+                    .expect("square ahead of the en passant square was checked above");
+                let starting_square =
+                    starting_square.expect("square behind the en passant square was checked above");
+                // NOTE: this runs on every gen_move_list, so only look for the checkers when
+                // the cheaper is_in_check says there are some
+                let king_square = self.board.kings_square[self.active_color as usize];
+                if let Some(king_square) = king_square.filter(|_| self.is_in_check()) {
+                    if let Some(&checker) = self.checkers().iter().find(|&&checker| {
+                        checker != pushed_pawn_square
+                            && !squares_between(checker, king_square).contains(&starting_square)
+                    }) {
+                        return Err(
+                            GamestateValidityCheckError::StrictEnPassantImpossibleCheck {
+                                en_passant_square: en_passant,
+                                checker,
+                            },
+                        );
+                    }
+                }
             }
         }
         Ok(())
     }
 
-    /// Serialize Gamestate into FEN. Does not do any validity checking
-    pub fn to_fen(&self) -> String {
-        // board
-        let mut fen = self.board.to_board_fen();
-        fen.push(' ');
+    /// Check that the King and Rook a castle permission needs are still where they could
+    /// castle from, see ValidityCheck for how Strict and Chess960 differ
+    fn check_castle_perm(
+        &self,
+        castle: Castle,
+        validity_check: ValidityCheck,
+    ) -> Result<(), GamestateValidityCheckError> {
+        let (color, back_rank, king, rook) = match castle {
+            Castle::WhiteKing | Castle::WhiteQueen => (
+                Color::White,
+                Rank::Rank1,
+                Piece::WhiteKing,
+                Piece::WhiteRook,
+            ),
+            Castle::BlackKing | Castle::BlackQueen => (
+                Color::Black,
+                Rank::Rank8,
+                Piece::BlackKing,
+                Piece::BlackRook,
+            ),
+        };
+        let king_side = matches!(castle, Castle::WhiteKing | Castle::BlackKing);
+
+        let king_square = self.board.kings_square[color as usize]
+            .filter(|king_square| king_square.get_rank() == back_rank)
+            .filter(|king_square| {
+                matches!(validity_check, ValidityCheck::Chess960)
+                    || king_square.get_file() == File::FileE
+            })
+            .ok_or(GamestateValidityCheckError::StrictCastlePermKingMoved { castle })?;
+
+        let has_rook = match validity_check {
+            ValidityCheck::Chess960 => self.board.piece_list[rook as usize]
+                .iter()
+                .filter(|rook_square| rook_square.get_rank() == back_rank)
+                .any(|rook_square| match king_side {
+                    true => rook_square.get_file() as u8 > king_square.get_file() as u8,
+                    false => (rook_square.get_file() as u8) < king_square.get_file() as u8,
+                }),
+            _ => {
+                let file = match king_side {
+                    true => File::FileH,
+                    false => File::FileA,
+                };
+                self.board.pieces[Square::from_file_and_rank(file, back_rank) as usize]
+                    == Some(rook)
+            }
+        };
+        match has_rook {
+            true => Ok(()),
+            false => Err(GamestateValidityCheckError::StrictCastlePermRookMissing { castle }),
+        }
+    }
 
-        // active_color
-        fen.push(self.active_color.into());
-        fen.push(' ');
+    /// Check that the Board's parallel arrays still describe the same pieces: piece counts,
+    /// piece lists, material scores, pawn bitboards and King squares. make_move and undo_move
+    /// assert this in debug builds so a desync is caught by the move that caused it.
+    #[cfg(debug_assertions)]
+    pub fn debug_invariants(&self) -> Result<(), String> {
+        let board = &self.board;
+        let mut piece_count = [0u8; Piece::COUNT];
+        let mut material_score = [0u32; Color::COUNT];
+        let mut pawns = [BitBoard(0); Color::COUNT];
+        for (square, piece) in board.iter_pieces() {
+            piece_count[piece as usize] += 1;
+            material_score[piece.get_color() as usize] += piece.get_value();
+            if piece.is_pawn() {
+                pawns[piece.get_color() as usize].set_bit(Square64::from(square));
+            }
+        }
 
-        // castle_perm
-        fen.push_str(self.castle_perm.to_string().as_str());
-        fen.push(' ');
+        if piece_count != board.piece_count {
+            return Err(format!(
+                "piece_count is {:?} but the board has {piece_count:?}",
+                board.piece_count
+            ));
+        }
+        if material_score != board.material_score {
+            return Err(format!(
+                "material_score is {:?} but the board adds up to {material_score:?}",
+                board.material_score
+            ));
+        }
+        if pawns != board.pawns {
+            return Err(format!(
+                "pawns are {:?} but the board has {pawns:?}",
+                board.pawns
+            ));
+        }
+
+        for (piece, squares) in Piece::all().zip(board.piece_list.iter()) {
+            if squares.len() != piece_count[piece as usize] as usize {
+                return Err(format!(
+                    "piece_list has {} {piece:?} but the board has {}",
+                    squares.len(),
+                    piece_count[piece as usize]
+                ));
+            }
+            if let Some(square) = squares
+                .iter()
+                .find(|&&square| board.pieces[square as usize] != Some(piece))
+            {
+                return Err(format!(
+                    "piece_list has a {piece:?} on empty or other square {square}"
+                ));
+            }
+        }
+
+        for (color, king) in [
+            (Color::White, Piece::WhiteKing),
+            (Color::Black, Piece::BlackKing),
+        ] {
+            let kings_square = board.kings_square[color as usize];
+            let king_squares = &board.piece_list[king as usize];
+            if king_squares.as_slice() != kings_square.as_slice() {
+                return Err(format!(
+                    "kings_square for {color} is {kings_square:?} but its Kings are on {king_squares:?}"
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Load a new position from FEN into this Gamestate. This is an optimization over
+    /// Gamestate::try_from for callers that load many positions (e.g. a UCI loop handling
+    /// position commands): the board's piece lists and the history are cleared and refilled
+    /// instead of being allocated again. The result is the same as Gamestate::try_from.
+    /// On error the Gamestate may be left partially reset and should not be used until a
+    /// reset succeeds.
+    pub fn reset_from_fen(&mut self, fen: &str) -> Result<(), GamestateBuildError> {
+        let mut fen_fields = FenFields::parse(fen)?;
+        fen_fields
+            .board_builder
+            .validity_check(ValidityCheck::Basic)
+            .build_into(&mut self.board)
+            .map_err(GamestateFenDeserializeError::from)?;
+
+        self.active_color = fen_fields.active_color;
+        self.castle_perm = fen_fields.castle_perm;
+        self.en_passant = fen_fields.en_passant;
+        self.halfmove_clock = fen_fields.halfmove_clock;
+        self.fullmove_count = fen_fields.fullmove_count;
+        self.legal_moves_cache = None;
+        self.init_position_key();
+
+        // Add dummy Undo to allow undoing back to initial state
+        self.history.clear();
+        self.history.push(Undo {
+            move_: Move::new_initial_state_dummy(),
+            castle_perm: self.castle_perm,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            position_key: self.position_key,
+        });
+
+        self.check_gamestate(ValidityCheck::Strict)?;
+        Ok(())
+    }
+
+    /// Serialize only the piece placement field of the FEN, see Board::to_board_fen
+    pub fn board_fen(&self) -> String {
+        self.board.to_board_fen()
+    }
+
+    /// Serialize Gamestate into FEN. Does not do any validity checking
+    pub fn to_fen(&self) -> String {
+        // board
+        let mut fen = self.board.to_board_fen();
+        fen.push(' ');
+
+        // active_color
+        fen.push(self.active_color.into());
+        fen.push(' ');
+
+        // castle_perm
+        fen.push_str(self.castle_perm.to_string().as_str());
+        fen.push(' ');
 
         // en_passant
         match self.en_passant {
@@ -1537,6 +2743,151 @@ impl Gamestate {
         fen
     }
 
+    /// Determine if the active color's King is currently under attack
+    pub fn is_in_check(&self) -> bool {
+        let non_active_color = self.active_color.opponent();
+
+        match self.board.kings_square[self.active_color as usize] {
+            Some(kings_square) => self.is_square_attacked(non_active_color, kings_square),
+            None => false,
+        }
+    }
+
+    /// Squares of the enemy pieces giving check to the active color's King. Two or more
+    /// means double check, where only King moves can get out of it.
+    pub fn checkers(&self) -> Vec<Square> {
+        match self.board.kings_square[self.active_color as usize] {
+            Some(kings_square) => self.attackers_of(self.active_color.opponent(), kings_square),
+            None => Vec::new(),
+        }
+    }
+
+    /// Find the squares of all the pieces of the provided color that attack the
+    /// provided square
+    fn attackers_of(&self, color: Color, square: Square) -> Vec<Square> {
+        attackers_on(&self.board.pieces, color, square)
+    }
+
+    /// Count the pieces of the provided color that defend the provided square, i.e. that
+    /// could recapture if a piece on it were taken. Works the same as counting attackers,
+    /// so pinned pieces still count and pieces lined up behind a defender (x-rays) don't.
+    pub fn defenders_count(&self, square: Square, color: Color) -> u8 {
+        self.attackers_of(color, square).len() as u8
+    }
+
+    /// Static Exchange Evaluation: how much material the side making move_ comes out ahead
+    /// (negative if behind) once all the captures and recaptures on its end square are played
+    /// out. Each side captures with its least valuable attacker and stops as soon as carrying
+    /// on would lose material. Pieces lined up behind an attacker (x-rays) join in once it has
+    /// captured. Pins and checks are ignored, so this is an estimate for move ordering.
+    pub fn see(&self, move_: Move) -> i32 {
+        let start_square = move_
+            .get_start()
+            .expect("see should only be given moves generated for this Gamestate");
+        let end_square = move_
+            .get_end()
+            .expect("see should only be given moves generated for this Gamestate");
+        let piece_moved = move_
+            .get_piece_moved()
+            .expect("see should only be given moves generated for this Gamestate");
+        let piece_promoted = move_.get_piece_promoted().ok().flatten();
+
+        let mut pieces = self.board.pieces;
+        pieces[start_square as usize] = None;
+        if move_.is_en_passant() {
+            // NOTE: the captured pawn is right behind the end square
+            let captured_square = match piece_moved.get_color() {
+                Color::White => end_square - NUM_BOARD_COLUMNS as i8,
+                Color::Black => end_square + NUM_BOARD_COLUMNS as i8,
+            };
+            if let Ok(captured_square) = captured_square {
+                pieces[captured_square as usize] = None;
+            }
+        }
+
+        // gains[i] is how far ahead the side making the ith capture is if the exchange
+        // stops right after it
+        let mut gains = vec![move_
+            .get_piece_captured()
+            .ok()
+            .flatten()
+            .map_or(0, |piece| piece.get_value() as i32)];
+        if let Some(piece_promoted) = piece_promoted {
+            gains[0] += piece_promoted.get_value() as i32 - piece_moved.get_value() as i32;
+        }
+        let mut piece_on_square = piece_promoted.unwrap_or(piece_moved);
+        let mut color = piece_moved.get_color().opponent();
+        while let Some(&attacker_square) = attackers_on(&pieces, color, end_square).first() {
+            let previous_gain = *gains.last().expect("gains starts with the first capture");
+            gains.push(piece_on_square.get_value() as i32 - previous_gain);
+            piece_on_square = pieces[attacker_square as usize]
+                .expect("attackers_on should only return occupied squares");
+            pieces[attacker_square as usize] = None;
+            color = color.opponent();
+        }
+
+        // Going backwards, each side only recaptures if that beats stopping the exchange
+        let mut gain = gains.pop().expect("gains starts with the first capture");
+        while let Some(previous_gain) = gains.pop() {
+            gain = -(-previous_gain).max(gain);
+        }
+        gain
+    }
+
+    /// Squares of the pieces of the provided color that the opponent can win material by
+    /// capturing, according to SEE, e.g. for a "your piece is hanging" hint. Kings are left
+    /// out since they can't be captured.
+    pub fn hanging_pieces(&self, color: Color) -> Vec<Square> {
+        self.board
+            .iter_pieces()
+            .filter(|(_, piece)| piece.get_color() == color && !piece.is_king())
+            .filter(|&(square, piece)| {
+                self.attackers_of(color.opponent(), square)
+                    .into_iter()
+                    .any(|attacker_square| {
+                        let attacker = self.board.pieces[attacker_square as usize]
+                            .expect("attackers_of should only return occupied squares");
+                        let capture = Move::new(
+                            attacker_square,
+                            square,
+                            Some(piece),
+                            false,
+                            false,
+                            None,
+                            false,
+                            attacker,
+                        );
+                        self.see(capture) > 0
+                    })
+            })
+            .map(|(square, _)| square)
+            .collect()
+    }
+
+    /// Render the squares attacked by the provided color as an 8x8 grid from White's side,
+    /// marking attacked squares with X and the others with -. Ranks are labelled on the left
+    /// and files along the bottom. Meant for debugging move generation.
+    pub fn attack_map(&self, color: Color) -> String {
+        let mut attack_map = String::new();
+        for rank in Rank::iter().rev() {
+            let row = File::iter()
+                .map(|file| {
+                    match self.is_square_attacked(color, Square::from_file_and_rank(file, rank)) {
+                        true => "X",
+                        false => "-",
+                    }
+                })
+                .collect::<Vec<_>>();
+            attack_map.push_str(&format!("{} {}\n", rank as u8 + 1, row.join(" ")));
+        }
+
+        let legend = File::iter()
+            .map(|file| char::from(file).to_string())
+            .collect::<Vec<_>>();
+        attack_map.push_str(&format!("  {}\n", legend.join(" ")));
+        attack_map
+    }
+
     /// Determine if the provided square is currently under attack by the
     /// provided color
     fn is_square_attacked(&self, color: Color, square: Square) -> bool {
@@ -1586,11 +2937,6 @@ impl Gamestate {
                 // of the direction offset, and early out of a direction when we hit a blocking piece
                 // and early out entirely if we find an attacking piece
                 sliding if piece.is_sliding() => {
-                    // Optimization: bishops can never attack a square that is a different color than they are
-                    if (sliding.is_bishop() && (square.get_color() != sliding.get_color())) {
-                        continue;
-                    }
-
                     for direction in directions {
                         let mut offset = direction;
                         while let Ok(next_square) = square + offset {
@@ -1628,8 +2974,80 @@ impl Gamestate {
     }
 }
 
+/// The squares strictly between two squares that share a rank, file or diagonal. Empty if
+/// the squares aren't aligned or are adjacent.
+fn squares_between(from: Square, to: Square) -> Vec<Square> {
+    let rank_diff = to.get_rank() as i8 - from.get_rank() as i8;
+    let file_diff = to.get_file() as i8 - from.get_file() as i8;
+    if rank_diff != 0 && file_diff != 0 && rank_diff.abs() != file_diff.abs() {
+        return Vec::new();
+    }
+
+    let direction = rank_diff.signum() * NUM_BOARD_COLUMNS as i8 + file_diff.signum();
+    let mut squares = Vec::new();
+    let mut offset = direction;
+    while let Ok(square) = from + offset {
+        if square == to {
+            break;
+        }
+        squares.push(square);
+        offset += direction;
+    }
+    squares
+}
+
+/// Squares of the pieces of the provided color that attack the provided square, given the
+/// pieces on the board. Attackers come from the least to the most valuable.
+fn attackers_on(
+    pieces: &[Option<Piece>; NUM_INTERNAL_BOARD_SQUARES],
+    color: Color,
+    square: Square,
+) -> Vec<Square> {
+    let pieces_to_check = match color {
+        Color::White => [
+            Piece::WhitePawn,
+            Piece::WhiteKnight,
+            Piece::WhiteBishop,
+            Piece::WhiteRook,
+            Piece::WhiteQueen,
+            Piece::WhiteKing,
+        ],
+        Color::Black => [
+            Piece::BlackPawn,
+            Piece::BlackKnight,
+            Piece::BlackBishop,
+            Piece::BlackRook,
+            Piece::BlackQueen,
+            Piece::BlackKing,
+        ],
+    };
+
+    let mut attackers = Vec::new();
+    for piece in pieces_to_check {
+        // Same approach as is_square_attacked, but keep going after the first attacker
+        for direction in piece.get_attack_directions() {
+            let direction = -direction;
+            let mut offset = direction;
+            while let Ok(next_square) = square + offset {
+                match pieces[next_square as usize] {
+                    Some(p) if p == piece => {
+                        attackers.push(next_square);
+                        break;
+                    }
+                    Some(_) => break,
+                    None if piece.is_sliding() => offset += direction,
+                    None => break,
+                }
+            }
+        }
+    }
+    attackers
+}
+
 #[cfg(test)]
 mod tests {
+    use rand::SeedableRng;
+    use rand_pcg::Lcg128Xsl64;
     use strum::IntoEnumIterator;
 
     use super::*;
@@ -1637,88 +3055,11 @@ mod tests {
         board::bitboard::BitBoard,
         error::{BoardBuildError, BoardValidityCheckError, PieceConversionError},
         file::File,
-        gamestate, position_key,
+        gamestate,
+        moves::MoveBuilder,
+        position_key,
     };
 
-    fn assert_fuzzy_eq(output: &Gamestate, expected: &Gamestate) {
-        // board.pieces
-        assert_eq!(output.board.pieces, expected.board.pieces);
-        // board.pawns
-        assert_eq!(output.board.pawns, expected.board.pawns);
-        // board.kings_square
-        assert_eq!(output.board.kings_square, expected.board.kings_square);
-        // board.piece_count
-        assert_eq!(output.board.piece_count, expected.board.piece_count);
-        // board.big_piece_count
-        assert_eq!(output.board.big_piece_count, expected.board.big_piece_count);
-        // board.major_piece_count
-        assert_eq!(
-            output.board.major_piece_count,
-            expected.board.major_piece_count
-        );
-        // board.minor_piece_count
-        assert_eq!(
-            output.board.minor_piece_count,
-            expected.board.minor_piece_count
-        );
-        // board.material_score
-        assert_eq!(output.board.material_score, expected.board.material_score);
-
-        // board.piece_list order doesn't matter
-        let mut output_piece_list_sorted = [
-            vec![],
-            vec![],
-            vec![],
-            vec![],
-            vec![],
-            vec![],
-            vec![],
-            vec![],
-            vec![],
-            vec![],
-            vec![],
-            vec![],
-        ];
-        for (index, _squares) in output.board.piece_list.iter().enumerate() {
-            output_piece_list_sorted[index] = _squares.clone();
-            output_piece_list_sorted[index].sort();
-        }
-        let mut expected_piece_list_sorted = [
-            vec![],
-            vec![],
-            vec![],
-            vec![],
-            vec![],
-            vec![],
-            vec![],
-            vec![],
-            vec![],
-            vec![],
-            vec![],
-            vec![],
-        ];
-        for (index, _squares) in expected.board.piece_list.iter().enumerate() {
-            expected_piece_list_sorted[index] = _squares.clone();
-            expected_piece_list_sorted[index].sort();
-        }
-        assert_eq!(output_piece_list_sorted, expected_piece_list_sorted);
-
-        // active_color
-        assert_eq!(output.active_color, expected.active_color);
-        // castle_perm
-        assert_eq!(output.castle_perm, expected.castle_perm);
-        // en_passant
-        assert_eq!(output.en_passant, expected.en_passant);
-        // halfmove_clock
-        assert_eq!(output.halfmove_clock, expected.halfmove_clock);
-        // fullmove_count
-        assert_eq!(output.fullmove_count, expected.fullmove_count);
-        // history
-        assert_eq!(output.history, expected.history);
-        // position_key
-        assert_eq!(output.position_key, expected.position_key);
-    }
-
     //======================== MAKE MOVES =====================================
     // MAKE/UNDO MOVES VISUAL ONLY
     #[test]
@@ -1770,6 +3111,181 @@ mod tests {
         println!("MOVE Errors: {}\n{:#?}", undo_errors.len(), move_errors);
     }
 
+    #[test]
+    fn test_gamestate_undo_move_restores_previous_state() {
+        let mut gamestate = GamestateBuilder::new_with_fen(DEFAULT_FEN)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let e2e4 = MoveBuilder::new(Square::E2, Square::E4, Piece::WhitePawn)
+            .pawn_start()
+            .build()
+            .unwrap();
+        let g8f6 = MoveBuilder::new(Square::G8, Square::F6, Piece::BlackKnight)
+            .build()
+            .unwrap();
+
+        gamestate.make_move(e2e4).unwrap();
+        gamestate.make_move(g8f6).unwrap();
+        gamestate.undo_move().unwrap();
+
+        // the en passant square and clocks come from the Undo saved before g8f6
+        let output = gamestate.to_fen();
+        let expected = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1";
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_undo_move_restores_kings_square() {
+        let fen = "4k3/8/8/8/8/8/8/4K3 w - - 0 1";
+        let mut gamestate = GamestateBuilder::new_with_fen(fen)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let e1e2 = MoveBuilder::new(Square::E1, Square::E2, Piece::WhiteKing)
+            .build()
+            .unwrap();
+
+        gamestate.make_move(e1e2).unwrap();
+        gamestate.undo_move().unwrap();
+
+        let output = gamestate.board.kings_square;
+        let expected = [Some(Square::E1), Some(Square::E8)];
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_make_undo_capture_with_en_passant_square_set() {
+        // d6 is an en passant square, but exd5 is a regular capture
+        let fen = "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2";
+        let mut gamestate = GamestateBuilder::new_with_fen(fen)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let e4d5 = MoveBuilder::new(Square::E4, Square::D5, Piece::WhitePawn)
+            .piece_captured(Some(Piece::BlackPawn))
+            .build()
+            .unwrap();
+
+        gamestate.make_move(e4d5).unwrap();
+        let output = gamestate.to_fen();
+        let expected = "rnbqkbnr/ppp1pppp/8/3P4/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2";
+        assert_eq!(output, expected);
+
+        gamestate.undo_move().unwrap();
+        let output = gamestate.to_fen();
+        let expected = fen;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_make_move_castle_without_rook() {
+        let fen = "4k3/8/8/8/8/8/8/4K3 w - - 0 1";
+        let mut gamestate = GamestateBuilder::new_with_fen(fen)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let e1g1 = MoveBuilder::new(Square::E1, Square::G1, Piece::WhiteKing)
+            .castle()
+            .build()
+            .unwrap();
+
+        let output = gamestate.make_move_unchecked(e1g1);
+        let expected = Err(MakeMoveError::MovePiece(
+            MovePieceError::NoPieceAtMoveStart {
+                start_square: Square::H1,
+            },
+        ));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_make_move_rejected_leaves_gamestate_unchanged() {
+        let fen = "8/8/8/8/8/3k4/8/4K3 w - - 0 1";
+        let mut gamestate = GamestateBuilder::new_with_fen(fen)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        // moving next to the Black King
+        let e1e2 = MoveBuilder::new(Square::E1, Square::E2, Piece::WhiteKing)
+            .build()
+            .unwrap();
+
+        let output = gamestate.make_move_unchecked(e1e2);
+        let expected = Err(MakeMoveError::MoveWouldPutMovingSideInCheck);
+        assert_eq!(output, expected);
+
+        let output = gamestate.to_fen();
+        let expected = fen;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_gen_move_list_blocked_pawn_capture_promotions() {
+        // the Pawn on b7 is blocked by the Knight on b8 but can still capture on a8
+        let fen = "rn2k3/1P6/8/8/8/8/8/4K3 w - - 0 1";
+        let gamestate = GamestateBuilder::new_with_fen(fen)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let move_list = gamestate.gen_move_list().unwrap();
+        // one capture on a8 for each promotion target, and no quiet moves
+        let output: Vec<bool> = move_list
+            .moves
+            .into_iter()
+            .flatten()
+            .filter(|move_| move_.get_start_raw() == Square::B7 as u32)
+            .map(|move_| move_.is_capture() && move_.is_promotion())
+            .collect();
+        let expected = vec![true; 4];
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_gen_legal_moves_excludes_self_check() {
+        // the Bishop on e2 is pinned to the King by the Rook on e8
+        let fen = "4r1k1/8/8/8/8/8/4B3/4K3 w - - 0 1";
+        let mut gamestate = GamestateBuilder::new_with_fen(fen)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let legal_moves = gamestate.gen_legal_moves().unwrap();
+        let output: Vec<u32> = legal_moves
+            .moves
+            .into_iter()
+            .flatten()
+            .map(|move_| move_.get_start_raw())
+            .collect();
+        let expected = vec![Square::E1 as u32; 4];
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_is_in_check() {
+        let cases = [
+            (DEFAULT_FEN, false),
+            ("4k3/8/8/8/8/8/8/4K2r w - - 0 1", true),
+            ("4k3/8/8/8/8/8/8/4KB1r w - - 0 1", false),
+            ("4k3/8/8/1B6/8/8/8/4K3 b - - 0 1", true),
+        ];
+
+        for (fen, expected) in cases {
+            let gamestate = GamestateBuilder::new_with_fen(fen)
+                .unwrap()
+                .build()
+                .unwrap();
+            let output = gamestate.is_in_check();
+            assert_eq!(output, expected, "{fen}");
+        }
+    }
+
     // MOVE PIECE
     #[test]
     fn test_gamestate_move_piece_valid() {
@@ -1799,7 +3315,21 @@ mod tests {
             .build()
             .unwrap();
 
-        assert_fuzzy_eq(&output, &expected);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_board_eq_after_captures() {
+        // the captures shuffle the piece lists compared to building the Board from its FEN
+        let mut gamestate = Gamestate::default();
+        gamestate
+            .apply_uci_moves(&[
+                "e2e4", "d7d5", "e4d5", "g8f6", "d2d4", "f6d5", "c2c4", "d5b4",
+            ])
+            .unwrap();
+        let output = gamestate.board;
+        let expected = Board::try_from("rnbqkb1r/ppp1pppp/8/8/1nPP4/8/PP3PPP/RNBQKBNR").unwrap();
+        assert_eq!(output, expected);
     }
 
     #[test]
@@ -1837,165 +3367,790 @@ mod tests {
         assert_eq!(output, expected);
     }
 
-    // ADD PIECE
+    // ADD PIECE
+    #[test]
+    fn test_gamestate_add_piece_valid() {
+        let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/R1BQKBNR w KQkq - 0 1";
+        let mut output = GamestateBuilder::new_with_fen(fen)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        // add WhiteKnight to C3
+        output.add_piece(Square::C3, Piece::WhiteKnight);
+
+        let fen_after_add = "rnbqkbnr/pppppppp/8/8/8/2N5/PPPPPPPP/R1BQKBNR w KQkq - 0 1";
+        let expected = GamestateBuilder::new_with_fen(fen_after_add)
+            .unwrap()
+            .build()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_gamestate_add_piece_invalid_square_occupied() {
+        let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/R1BQKBNR w KQkq - 0 1";
+        let mut input = GamestateBuilder::new_with_fen(fen)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        // add WhiteKnight to D2 which is occupied by WhitePawn
+        let output = input.add_piece(Square::D2, Piece::WhiteKnight);
+
+        let expected = Err(AddPieceError::AddToOccupiedSquare {
+            occupied_square: Square::D2,
+            piece_at_square: Piece::WhitePawn,
+        });
+
+        assert_eq!(output, expected);
+    }
+
+    // CLEAR PIECE
+    #[test]
+    fn test_gamestate_clear_piece_valid() {
+        let fen = "8/8/8/8/8/8/3P4/8 w - - 0 1";
+        let mut output = GamestateBuilder::new_with_fen(fen)
+            .unwrap()
+            .validity_check(ValidityCheck::Basic)
+            .build()
+            .unwrap();
+
+        let initial_position_key = output.position_key;
+
+        output.clear_piece(Square::D2);
+
+        let history = vec![Undo {
+            move_: Move::new_initial_state_dummy(),
+            castle_perm: CastlePerm(0),
+            en_passant: None,
+            halfmove_clock: 0,
+            position_key: initial_position_key,
+        }];
+
+        let expected = GamestateBuilder::new()
+            .validity_check(ValidityCheck::Basic)
+            .history(history)
+            .build()
+            .unwrap();
+
+        assert_eq!(output, expected);
+    }
+
+    // NOTE: it's important to test a non white pawn move/add/clear since you can easily mix up Piece and PieceType values
+    // and for a white pawn both convert to usize 0
+    #[test]
+    fn test_gamestate_clear_piece_non_white_pawn_valid() {
+        // Black Knight's Piece usize value is 7 but PieceType is 1
+        // output.board.piece_list[piece_type as usize] is the vec for WhiteKnight
+        // so this fen should doubly cause an issue if we mess this up
+        let fen = "N7/8/8/8/8/8/3n4/8 w - - 0 1";
+        let mut output = GamestateBuilder::new_with_fen(fen)
+            .unwrap()
+            .validity_check(ValidityCheck::Basic)
+            .build()
+            .unwrap();
+
+        let initial_position_key = output.position_key;
+
+        output.clear_piece(Square::D2);
+
+        // Initial State's held in history would be different
+        let history = vec![Undo {
+            move_: Move::new_initial_state_dummy(),
+            castle_perm: CastlePerm(0),
+            en_passant: None,
+            halfmove_clock: 0,
+            position_key: initial_position_key,
+        }];
+
+        let expected = GamestateBuilder::new_with_board(
+            BoardBuilder::new()
+                .validity_check(ValidityCheck::Basic)
+                .piece(Piece::WhiteKnight, Square64::A8)
+                .build()
+                .unwrap(),
+        )
+        .validity_check(ValidityCheck::Basic)
+        .history(history)
+        .build()
+        .unwrap();
+
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_clear_piece_start_valid() {
+        let fen = DEFAULT_FEN;
+        let mut output = GamestateBuilder::new_with_fen(fen)
+            .unwrap()
+            .validity_check(ValidityCheck::Basic)
+            .build()
+            .unwrap();
+
+        let initial_position_key = output.position_key;
+
+        output.clear_piece(Square::D2);
+
+        let history = vec![Undo {
+            move_: Move::new_initial_state_dummy(),
+            castle_perm: CastlePerm::default(),
+            en_passant: None,
+            halfmove_clock: 0,
+            position_key: initial_position_key,
+        }];
+
+        let fen_after_clear = "rnbqkbnr/pppppppp/8/8/8/8/PPP1PPPP/RNBQKBNR w KQkq - 0 1";
+        let mut expected = GamestateBuilder::new_with_fen(fen_after_clear)
+            .unwrap()
+            .validity_check(ValidityCheck::Basic)
+            .history(history)
+            .build()
+            .unwrap();
+
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_clear_piece_invalid() {
+        let fen = "8/8/8/8/8/8/3P4/8 w - - 0 1";
+        let mut input = GamestateBuilder::new_with_fen(fen)
+            .unwrap()
+            .validity_check(ValidityCheck::Basic)
+            .build()
+            .unwrap();
+
+        let output = input.clear_piece(Square::D1);
+
+        let expected = Err(ClearPieceError::NoPieceToClear {
+            empty_square: Square::D1,
+        });
+
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_make_move_checked_valid() {
+        let mut gamestate = Gamestate::default();
+        let move_ = Move::new(
+            Square::E2,
+            Square::E4,
+            None,
+            false,
+            true,
+            None,
+            false,
+            Piece::WhitePawn,
+        );
+        gamestate.make_move_checked(move_).unwrap();
+        let output = gamestate.to_fen();
+        let expected = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1";
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_make_move_checked_invalid_pinned_piece() {
+        // White Bishop on E2 is pinned to the King by the Black Rook on E8
+        let fen = "4r1k1/8/8/8/8/8/4B3/4K3 w - - 0 1";
+        let mut gamestate = Gamestate::try_from(fen).unwrap();
+        let move_ = Move::new(
+            Square::E2,
+            Square::D3,
+            None,
+            false,
+            false,
+            None,
+            false,
+            Piece::WhiteBishop,
+        );
+        let output = gamestate.make_move_checked(move_);
+        let expected = Err(MakeMoveError::IllegalMove { move_ });
+        assert_eq!(output, expected);
+        assert_eq!(gamestate.to_fen(), fen);
+    }
+
+    // NOTE: promotions go through clear_piece and add_piece so this makes sure no stale
+    // pawn bit or piece_list entry is left behind on either square
+    #[test]
+    fn test_gamestate_make_move_promotion_updates_pawns_and_pieces() {
+        let fen = "4k3/7P/8/8/8/8/8/4K3 w - - 0 1";
+        let mut gamestate = Gamestate::try_from(fen).unwrap();
+        let move_ = Move::new(
+            Square::H7,
+            Square::H8,
+            None,
+            false,
+            false,
+            Some(Piece::WhiteQueen),
+            false,
+            Piece::WhitePawn,
+        );
+        gamestate.make_move(move_).unwrap();
+
+        let pawns = gamestate.board.pawns[Color::White as usize];
+        assert!(!pawns.check_bit(Square64::H7));
+        assert!(!pawns.check_bit(Square64::H8));
+        assert_eq!(gamestate.board.pieces[Square::H7 as usize], None);
+        assert_eq!(
+            gamestate.board.pieces[Square::H8 as usize],
+            Some(Piece::WhiteQueen)
+        );
+        assert!(gamestate.board.piece_list[Piece::WhitePawn as usize].is_empty());
+        assert_eq!(
+            gamestate.board.piece_list[Piece::WhiteQueen as usize],
+            vec![Square::H8]
+        );
+
+        gamestate.undo_move().unwrap();
+        let output = gamestate.board.pawns[Color::White as usize];
+        let mut expected = BitBoard(0);
+        expected.set_bit(Square64::H7);
+        assert_eq!(output, expected);
+        assert!(gamestate.board.piece_list[Piece::WhiteQueen as usize].is_empty());
+    }
+
+    #[test]
+    fn test_gamestate_make_move_clocks_progression() {
+        let mut gamestate = Gamestate::default();
+        // (move, halfmove_clock, fullmove_count) after each move
+        let moves_and_clocks = [
+            ("e2e4", 0, 1),
+            ("g8f6", 1, 2),
+            ("b1c3", 2, 2),
+            ("b8c6", 3, 3),
+            ("c3d5", 4, 3),
+            ("f6e4", 0, 4),
+        ];
+        for (uci, halfmove_clock, fullmove_count) in moves_and_clocks {
+            gamestate.apply_uci_moves(&[uci]).unwrap();
+            let output = (gamestate.halfmove_clock, gamestate.fullmove_count);
+            let expected = (halfmove_clock, fullmove_count);
+            assert_eq!(output, expected, "after {uci}");
+        }
+
+        // undoing restores the clocks of the previous position
+        gamestate.undo_move().unwrap();
+        let output = (gamestate.halfmove_clock, gamestate.fullmove_count);
+        let expected = (4, 3);
+        assert_eq!(output, expected);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn test_gamestate_debug_invariants() {
+        let mut gamestate = Gamestate::default();
+        gamestate
+            .apply_uci_moves(&["e2e4", "d7d5", "e4d5"])
+            .unwrap();
+        let output = gamestate.debug_invariants();
+        let expected = Ok(());
+        assert_eq!(output, expected);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn test_gamestate_debug_invariants_flags_desync() {
+        // a capture that forgot to take the pawn out of the piece_list
+        let mut gamestate = Gamestate::default();
+        gamestate.board.pieces[Square::E2 as usize] = None;
+        gamestate.board.piece_count[Piece::WhitePawn as usize] -= 1;
+        let output = gamestate.debug_invariants().is_err();
+        let expected = true;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_make_move_wrong_color_to_move() {
+        let mut gamestate = Gamestate::default();
+        let move_ = Move::new(
+            Square::E7,
+            Square::E5,
+            None,
+            false,
+            true,
+            None,
+            false,
+            Piece::BlackPawn,
+        );
+        let expected = Err(MakeMoveError::WrongColorToMove {
+            expected: Color::White,
+            found: Color::Black,
+        });
+        assert_eq!(gamestate.make_move(move_), expected);
+        assert_eq!(gamestate.make_move_checked(move_), expected);
+        assert_eq!(gamestate.to_fen(), DEFAULT_FEN);
+    }
+
+    //============================== FEN AFTER ================================
+    #[test]
+    fn test_gamestate_fen_after_leaves_gamestate_unchanged() {
+        let mut gamestate = Gamestate::default();
+        let move_ = Move::new(
+            Square::E2,
+            Square::E4,
+            None,
+            false,
+            true,
+            None,
+            false,
+            Piece::WhitePawn,
+        );
+        let output = (gamestate.fen_after(move_).unwrap(), gamestate.to_fen());
+        let expected = (
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1".to_string(),
+            DEFAULT_FEN.to_string(),
+        );
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_fen_after_self_check() {
+        // the Bishop on e2 is pinned to the King by the Rook on e7
+        let fen = "4k3/4r3/8/8/8/8/4B3/4K3 w - - 0 1";
+        let mut gamestate = Gamestate::try_from(fen).unwrap();
+        let move_ = Move::new(
+            Square::E2,
+            Square::D3,
+            None,
+            false,
+            false,
+            None,
+            false,
+            Piece::WhiteBishop,
+        );
+        let output = (gamestate.fen_after(move_), gamestate.to_fen());
+        let expected = (Err(MakeMoveError::IllegalMove { move_ }), fen.to_string());
+        assert_eq!(output, expected);
+    }
+
+    //=========================== EXPLAIN MOVE ================================
+    /// explain_move for a move from start to end in the position of the FEN. The piece on
+    /// start is used as the piece moved, so the Move itself is never what gets it rejected.
+    fn explain(fen: &str, start: Square, end: Square, promoted: Option<Piece>) -> MoveLegality {
+        let mut gamestate = Gamestate::try_from(fen).unwrap();
+        let piece = gamestate.board.pieces[start as usize].unwrap_or(Piece::WhitePawn);
+        let move_ = Move::new(start, end, None, false, false, promoted, false, piece);
+        let output = gamestate.explain_move(move_).unwrap();
+        assert_eq!(
+            gamestate.to_fen(),
+            Gamestate::try_from(fen).unwrap().to_fen()
+        );
+        output
+    }
+
+    #[test]
+    fn test_gamestate_explain_move_legal() {
+        let output = (
+            explain(DEFAULT_FEN, Square::E2, Square::E4, None),
+            explain(DEFAULT_FEN, Square::G1, Square::F3, None),
+        );
+        let expected = (MoveLegality::Legal, MoveLegality::Legal);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_explain_move_wrong_turn_and_no_piece() {
+        let output = (
+            explain(DEFAULT_FEN, Square::E7, Square::E5, None),
+            explain(DEFAULT_FEN, Square::E3, Square::E4, None),
+        );
+        let expected = (MoveLegality::WrongTurn, MoveLegality::NoPieceThere);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_explain_move_blocked_and_own_piece() {
+        let output = (
+            explain(DEFAULT_FEN, Square::F1, Square::C4, None),
+            explain(DEFAULT_FEN, Square::D1, Square::D2, None),
+        );
+        let expected = (MoveLegality::PieceBlocked, MoveLegality::OwnPieceOnTarget);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_explain_move_not_a_legal_pattern() {
+        let output = (
+            explain(DEFAULT_FEN, Square::G1, Square::G3, None),
+            // diagonal pawn move with nothing to capture
+            explain(DEFAULT_FEN, Square::E2, Square::D3, None),
+        );
+        let expected = (
+            MoveLegality::NotALegalPattern,
+            MoveLegality::NotALegalPattern,
+        );
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_explain_move_pinned_piece() {
+        let fen = "4r2k/8/8/8/8/8/4B3/4K3 w - - 0 1";
+        let output = explain(fen, Square::E2, Square::D3, None);
+        let expected = MoveLegality::WouldLeaveKingInCheck;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_explain_move_promotion() {
+        let fen = "4k3/1P6/8/8/8/8/8/4K3 w - - 0 1";
+        let output = (
+            explain(fen, Square::B7, Square::B8, None),
+            explain(fen, Square::B7, Square::B8, Some(Piece::WhiteRook)),
+            explain(fen, Square::E1, Square::E2, Some(Piece::WhiteQueen)),
+        );
+        let expected = (
+            MoveLegality::InvalidPromotion,
+            MoveLegality::Legal,
+            MoveLegality::InvalidPromotion,
+        );
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_explain_move_castling() {
+        // the Rook on f8 attacks f1, and White can only castle kingside
+        let fen = "r3kr2/8/8/8/8/8/8/R3K2R w Kq - 0 1";
+        let output = (
+            explain(fen, Square::E1, Square::G1, None),
+            explain(fen, Square::E1, Square::C1, None),
+            explain(
+                "r3k2r/8/8/8/8/8/8/R3K2R w Qkq - 0 1",
+                Square::E1,
+                Square::G1,
+                None,
+            ),
+        );
+        let expected = (
+            MoveLegality::CastlingThroughCheck,
+            MoveLegality::CastlingRightLost,
+            MoveLegality::CastlingRightLost,
+        );
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_explain_move_castling_blocked() {
+        let fen = "r3k2r/8/8/8/8/8/8/RN2K2R w KQkq - 0 1";
+        let output = (
+            explain(fen, Square::E1, Square::C1, None),
+            explain(fen, Square::E1, Square::G1, None),
+        );
+        let expected = (MoveLegality::PieceBlocked, MoveLegality::Legal);
+        assert_eq!(output, expected);
+    }
+
     #[test]
-    fn test_gamestate_add_piece_valid() {
-        let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/R1BQKBNR w KQkq - 0 1";
-        let mut output = GamestateBuilder::new_with_fen(fen)
-            .unwrap()
-            .build()
-            .unwrap();
-
-        // add WhiteKnight to C3
-        output.add_piece(Square::C3, Piece::WhiteKnight);
-
-        let fen_after_add = "rnbqkbnr/pppppppp/8/8/8/2N5/PPPPPPPP/R1BQKBNR w KQkq - 0 1";
-        let expected = GamestateBuilder::new_with_fen(fen_after_add)
-            .unwrap()
-            .build()
-            .unwrap();
+    fn test_gamestate_make_move_unchecked_rejects_self_check() {
+        // the Bishop on e2 is pinned by the Rook on e8
+        let mut gamestate = Gamestate::try_from("4r2k/8/8/8/8/8/4B3/4K3 w - - 0 1").unwrap();
+        let move_ = Move::new(
+            Square::E2,
+            Square::D3,
+            None,
+            false,
+            false,
+            None,
+            false,
+            Piece::WhiteBishop,
+        );
+        let output = gamestate.make_move_unchecked(move_);
+        let expected = Err(MakeMoveError::MoveWouldPutMovingSideInCheck);
+        assert_eq!(output, expected);
+        assert_eq!(gamestate.to_fen(), "4r2k/8/8/8/8/8/4B3/4K3 w - - 0 1");
     }
 
     #[test]
-    fn test_gamestate_add_piece_invalid_square_occupied() {
-        let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/R1BQKBNR w KQkq - 0 1";
-        let mut input = GamestateBuilder::new_with_fen(fen)
-            .unwrap()
-            .build()
-            .unwrap();
-
-        // add WhiteKnight to D2 which is occupied by WhitePawn
-        let output = input.add_piece(Square::D2, Piece::WhiteKnight);
+    fn test_gamestate_en_passant_exposing_king_along_rank_white() {
+        // dxe6 would take both pawns off the 5th rank, leaving the King on a5 to the Rook on h5
+        let fen = "8/8/8/K2Pp2r/8/8/8/7k w - e6 0 1";
+        let mut gamestate = Gamestate::try_from(fen).unwrap();
+        let en_passant = Move::new(
+            Square::D5,
+            Square::E6,
+            Some(Piece::BlackPawn),
+            true,
+            false,
+            None,
+            false,
+            Piece::WhitePawn,
+        );
 
-        let expected = Err(AddPieceError::AddToOccupiedSquare {
-            occupied_square: Square::D2,
-            piece_at_square: Piece::WhitePawn,
-        });
+        let legal_moves = gamestate.gen_legal_moves().unwrap();
+        let output = legal_moves.moves.contains(&Some(en_passant));
+        let expected = false;
+        assert_eq!(output, expected);
 
+        // NOTE: make_move would already reject it as illegal before making it
+        let output = gamestate.make_move_unchecked(en_passant);
+        let expected = Err(MakeMoveError::MoveWouldPutMovingSideInCheck);
         assert_eq!(output, expected);
+        assert_eq!(gamestate.to_fen(), fen);
     }
 
-    // CLEAR PIECE
     #[test]
-    fn test_gamestate_clear_piece_valid() {
-        let fen = "8/8/8/8/8/8/3P4/8 w - - 0 1";
-        let mut output = GamestateBuilder::new_with_fen(fen)
-            .unwrap()
-            .validity_check(ValidityCheck::Basic)
-            .build()
-            .unwrap();
-
-        let initial_position_key = output.position_key;
+    fn test_gamestate_en_passant_exposing_king_along_rank_black() {
+        let fen = "8/8/8/8/k2pP2R/8/8/7K b - e3 0 1";
+        let mut gamestate = Gamestate::try_from(fen).unwrap();
+        let en_passant = Move::new(
+            Square::D4,
+            Square::E3,
+            Some(Piece::WhitePawn),
+            true,
+            false,
+            None,
+            false,
+            Piece::BlackPawn,
+        );
 
-        output.clear_piece(Square::D2);
+        let legal_moves = gamestate.gen_legal_moves().unwrap();
+        let output = legal_moves.moves.contains(&Some(en_passant));
+        let expected = false;
+        assert_eq!(output, expected);
 
-        let history = vec![Undo {
-            move_: Move::new_initial_state_dummy(),
-            castle_perm: CastlePerm(0),
-            en_passant: None,
-            halfmove_clock: 0,
-            position_key: initial_position_key,
-        }];
+        // NOTE: make_move would already reject it as illegal before making it
+        let output = gamestate.make_move_unchecked(en_passant);
+        let expected = Err(MakeMoveError::MoveWouldPutMovingSideInCheck);
+        assert_eq!(output, expected);
+        assert_eq!(gamestate.to_fen(), fen);
+    }
 
-        let expected = GamestateBuilder::new()
-            .validity_check(ValidityCheck::Basic)
-            .history(history)
-            .build()
-            .unwrap();
+    #[test]
+    fn test_gamestate_make_move_halfmove_clock_non_pawn_capture() {
+        let mut gamestate = Gamestate::try_from("4k2r/8/8/8/8/8/8/4K2R w - - 5 10").unwrap();
+        gamestate.apply_uci_moves(&["h1h8"]).unwrap();
+        let output = gamestate.halfmove_clock;
+        let expected = 0;
+        assert_eq!(output, expected);
+    }
 
+    #[test]
+    fn test_gamestate_make_move_halfmove_clock_quiet_knight_move() {
+        let mut gamestate = Gamestate::try_from("4k3/8/8/8/8/8/8/4K1N1 w - - 5 10").unwrap();
+        gamestate.apply_uci_moves(&["g1f3"]).unwrap();
+        let output = gamestate.halfmove_clock;
+        let expected = 6;
         assert_eq!(output, expected);
     }
 
-    // NOTE: it's important to test a non white pawn move/add/clear since you can easily mix up Piece and PieceType values
-    // and for a white pawn both convert to usize 0
     #[test]
-    fn test_gamestate_clear_piece_non_white_pawn_valid() {
-        // Black Knight's Piece usize value is 7 but PieceType is 1
-        // output.board.piece_list[piece_type as usize] is the vec for WhiteKnight
-        // so this fen should doubly cause an issue if we mess this up
-        let fen = "N7/8/8/8/8/8/3n4/8 w - - 0 1";
-        let mut output = GamestateBuilder::new_with_fen(fen)
-            .unwrap()
-            .validity_check(ValidityCheck::Basic)
-            .build()
-            .unwrap();
+    fn test_gamestate_make_move_halfmove_clock_saturates() {
+        let mut gamestate = Gamestate::try_from("4k3/8/8/8/8/8/8/4K1N1 w - - 5 10").unwrap();
+        gamestate.halfmove_clock = u8::MAX;
+        let move_ = Move::new(
+            Square::G1,
+            Square::F3,
+            None,
+            false,
+            false,
+            None,
+            false,
+            Piece::WhiteKnight,
+        );
+        gamestate.make_move_unchecked(move_).unwrap();
+        let output = gamestate.halfmove_clock;
+        let expected = u8::MAX;
+        assert_eq!(output, expected);
+    }
 
-        let initial_position_key = output.position_key;
+    #[test]
+    fn test_gamestate_make_move_halfmove_clock_capture_promotion() {
+        let mut gamestate = Gamestate::try_from("3r3k/4P3/8/8/8/8/8/4K3 w - - 5 10").unwrap();
+        gamestate.apply_uci_moves(&["e7d8q"]).unwrap();
+        let output = (gamestate.halfmove_clock, gamestate.to_fen());
+        let expected = (0, "3Q3k/8/8/8/8/8/8/4K3 b - - 0 10".to_owned());
+        assert_eq!(output, expected);
+    }
 
-        output.clear_piece(Square::D2);
+    #[test]
+    fn test_gamestate_make_move_capturing_rook_on_home_square_revokes_castle_perm() {
+        let mut gamestate = Gamestate::try_from("r3k3/8/8/8/8/8/7r/R3K2R b KQq - 0 1").unwrap();
+        gamestate.apply_uci_moves(&["h2h1"]).unwrap();
+        let output = (gamestate.castle_permissions(), gamestate.to_fen());
+        let expected = (
+            CastlePerm(Castle::WhiteQueen as u8 | Castle::BlackQueen as u8),
+            "r3k3/8/8/8/8/8/8/R3K2r w Qq - 0 2".to_owned(),
+        );
+        assert_eq!(output, expected);
 
-        // Initial State's held in history would be different
-        let history = vec![Undo {
-            move_: Move::new_initial_state_dummy(),
-            castle_perm: CastlePerm(0),
-            en_passant: None,
-            halfmove_clock: 0,
-            position_key: initial_position_key,
-        }];
+        // and undoing the capture gives the right back
+        gamestate.undo_move().unwrap();
+        let output = gamestate.to_fen();
+        let expected = "r3k3/8/8/8/8/8/7r/R3K2R b KQq - 0 1";
+        assert_eq!(output, expected);
+    }
 
-        let expected = GamestateBuilder::new_with_board(
-            BoardBuilder::new()
-                .validity_check(ValidityCheck::Basic)
-                .piece(Piece::WhiteKnight, Square64::A8)
-                .build()
-                .unwrap(),
-        )
-        .validity_check(ValidityCheck::Basic)
-        .history(history)
-        .build()
-        .unwrap();
+    #[test]
+    fn test_gamestate_make_move_full_capture() {
+        let mut gamestate = Gamestate::try_from("4k3/8/8/8/8/8/4q3/R3K3 w Q - 0 1").unwrap();
+        let move_ = san_to_move(&mut gamestate, "Kxe2").unwrap();
+        let output = gamestate.make_move_full(move_).unwrap();
+        let expected = MoveOutcome {
+            captured: Some(Piece::BlackQueen),
+            is_check: false,
+            is_checkmate: false,
+            san: "Kxe2".to_string(),
+        };
+        assert_eq!(output, expected);
+    }
 
+    #[test]
+    fn test_gamestate_make_move_full_checkmate() {
+        let mut gamestate = Gamestate::try_from("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let move_ = san_to_move(&mut gamestate, "Ra8").unwrap();
+        let output = gamestate.make_move_full(move_).unwrap();
+        let expected = MoveOutcome {
+            captured: None,
+            is_check: true,
+            is_checkmate: true,
+            san: "Ra8#".to_string(),
+        };
         assert_eq!(output, expected);
     }
 
     #[test]
-    fn test_gamestate_clear_piece_start_valid() {
-        let fen = DEFAULT_FEN;
-        let mut output = GamestateBuilder::new_with_fen(fen)
-            .unwrap()
-            .validity_check(ValidityCheck::Basic)
-            .build()
-            .unwrap();
+    fn test_gamestate_make_move_full_check() {
+        let mut gamestate = Gamestate::try_from("6k1/8/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let move_ = san_to_move(&mut gamestate, "Ra8").unwrap();
+        let output = gamestate.make_move_full(move_).unwrap();
+        let expected = MoveOutcome {
+            captured: None,
+            is_check: true,
+            is_checkmate: false,
+            san: "Ra8+".to_string(),
+        };
+        assert_eq!(output, expected);
+    }
 
-        let initial_position_key = output.position_key;
+    #[test]
+    fn test_gamestate_make_move_full_illegal() {
+        let mut gamestate = Gamestate::try_from("4k3/8/8/8/8/8/4q3/R3K3 w Q - 0 1").unwrap();
+        // Castling out of check
+        let move_ = Move::new(
+            Square::E1,
+            Square::C1,
+            None,
+            false,
+            false,
+            None,
+            true,
+            Piece::WhiteKing,
+        );
+        let output = gamestate.make_move_full(move_);
+        let expected = Err(MakeMoveError::IllegalMove { move_ });
+        assert_eq!(output, expected);
 
-        output.clear_piece(Square::D2);
+        // make_move goes through make_move_full, so it's rejected the same way
+        let output = (gamestate.make_move(move_), gamestate.to_fen());
+        let expected = (
+            Err(MakeMoveError::IllegalMove { move_ }),
+            "4k3/8/8/8/8/8/4q3/R3K3 w Q - 0 1".to_string(),
+        );
+        assert_eq!(output, expected);
+    }
 
-        let history = vec![Undo {
-            move_: Move::new_initial_state_dummy(),
-            castle_perm: CastlePerm::default(),
-            en_passant: None,
-            halfmove_clock: 0,
-            position_key: initial_position_key,
-        }];
+    #[test]
+    fn test_gamestate_gives_check() {
+        let cases = [
+            // direct check
+            ("6k1/8/8/8/8/8/8/R5K1 w - - 0 1", "Ra8", true),
+            // discovered check from the Rook behind the Knight
+            ("4k3/8/8/8/8/8/4N3/4R1K1 w - - 0 1", "Nc3", true),
+            // double check from the Knight and the Rook behind it
+            ("4k3/8/8/8/4N3/8/8/4R1K1 w - - 0 1", "Nf6", true),
+            // en passant capture opening the rank to the King
+            ("8/8/8/k1pP3R/8/8/8/4K3 w - c6 0 1", "dxc6", true),
+            ("6k1/8/8/8/8/8/8/R5K1 w - - 0 1", "Kf2", false),
+        ];
+        for (fen, san, expected) in cases {
+            let mut gamestate = Gamestate::try_from(fen).unwrap();
+            let move_ = san_to_move(&mut gamestate, san).unwrap();
+            let output = gamestate.gives_check(move_).unwrap();
+            assert_eq!(output, expected, "{san} in {fen}");
+        }
+    }
 
-        let fen_after_clear = "rnbqkbnr/pppppppp/8/8/8/8/PPP1PPPP/RNBQKBNR w KQkq - 0 1";
-        let mut expected = GamestateBuilder::new_with_fen(fen_after_clear)
-            .unwrap()
-            .validity_check(ValidityCheck::Basic)
-            .history(history)
-            .build()
-            .unwrap();
+    #[test]
+    fn test_gamestate_gives_check_leaves_gamestate_unchanged() {
+        let mut gamestate = Gamestate::try_from("4k3/8/8/8/8/8/4N3/4R1K1 w - - 0 1").unwrap();
+        let move_ = san_to_move(&mut gamestate, "Nc3").unwrap();
+        gamestate.gives_check(move_).unwrap();
+        let output = gamestate;
+        let expected = Gamestate::try_from("4k3/8/8/8/8/8/4N3/4R1K1 w - - 0 1").unwrap();
+        assert_eq!(output, expected);
+    }
 
-        assert_fuzzy_eq(&output, &expected);
+    #[test]
+    fn test_gamestate_apply_uci_moves_opera_game() {
+        let moves = "e2e4 e7e5 g1f3 d7d6 d2d4 c8g4 d4e5 g4f3 d1f3 d6e5 f1c4 g8f6 f3b3 d8e7 \
+            b1c3 c7c6 c1g5 b7b5 c3b5 c6b5 c4b5 b8d7 e1c1 a8d8 d1d7 d8d7 h1d1 e7e6 b5d7 f6d7 \
+            b3b8 d7b8 d1d8";
+        let moves = moves.split_whitespace().collect::<Vec<_>>();
+        let mut gamestate = Gamestate::default();
+        gamestate.apply_uci_moves(&moves).unwrap();
+
+        let output = gamestate.to_fen();
+        let expected = "1n1Rkb1r/p4ppp/4q3/4p1B1/4P3/8/PPP2PPP/2K5 b k - 1 17";
+        assert_eq!(output, expected);
     }
 
     #[test]
-    fn test_gamestate_clear_piece_invalid() {
-        let fen = "8/8/8/8/8/8/3P4/8 w - - 0 1";
-        let mut input = GamestateBuilder::new_with_fen(fen)
-            .unwrap()
-            .validity_check(ValidityCheck::Basic)
-            .build()
-            .unwrap();
+    fn test_gamestate_apply_uci_moves_invalid_token() {
+        let mut gamestate = Gamestate::default();
+        let output = gamestate.apply_uci_moves(&["e2e4", "e7e5", "e1e3"]);
+        let expected = Err(MakeMoveError::InvalidUciMove {
+            index: 2,
+            uci: "e1e3".to_string(),
+        });
+        assert_eq!(output, expected);
+    }
 
-        let output = input.clear_piece(Square::D1);
+    #[test]
+    fn test_gamestate_from_san_moves_opera_game() {
+        let moves = "e4 e5 Nf3 d6 d4 Bg4 dxe5 Bxf3 Qxf3 dxe5 Bc4 Nf6 Qb3 Qe7 Nc3 c6 Bg5 b5 \
+            Nxb5 cxb5 Bxb5+ Nbd7 O-O-O Rd8 Rxd7 Rxd7 Rd1 Qe6 Bxd7+ Nxd7 Qb8+ Nxb8 Rd8#";
+        let moves = moves.split_whitespace().collect::<Vec<_>>();
+        let gamestate = Gamestate::from_san_moves(&moves).unwrap();
+
+        let output = (gamestate.to_fen(), gamestate.history.len());
+        // history also holds the dummy Undo for the initial state
+        let expected = (
+            "1n1Rkb1r/p4ppp/4q3/4p1B1/4P3/8/PPP2PPP/2K5 b k - 1 17".to_string(),
+            moves.len() + 1,
+        );
+        assert_eq!(output, expected);
+    }
 
-        let expected = Err(ClearPieceError::NoPieceToClear {
-            empty_square: Square::D1,
+    #[test]
+    fn test_gamestate_from_san_moves_invalid_token() {
+        let output = Gamestate::from_san_moves(&["e4", "e5", "Ke3"]);
+        let expected = Err(PgnError::SanMove {
+            index: 2,
+            san: "Ke3".to_string(),
+            source: Box::new(PgnError::IllegalMove {
+                san: "Ke3".to_string(),
+            }),
         });
+        assert_eq!(output, expected);
+    }
+
+    //================================ CLONE ==================================
+    #[test]
+    fn test_gamestate_clone_is_snapshot() {
+        let mut gamestate = Gamestate::default();
+        gamestate.apply_uci_moves(&["e2e4", "c7c5"]).unwrap();
+        let fen = gamestate.to_fen();
+
+        let mut clone = gamestate.clone();
+        let output = (clone.position_key(), &clone);
+        let expected = (gamestate.position_key(), &gamestate);
+        assert_eq!(output, expected);
 
+        clone.apply_uci_moves(&["g1f3"]).unwrap();
+        clone.undo_move().unwrap();
+        clone.undo_move().unwrap();
+        let output = (gamestate.to_fen(), gamestate.history.len());
+        let expected = (fen, 3);
         assert_eq!(output, expected);
+        assert_ne!(clone, gamestate);
     }
 
     //======================== POSITION KEY ===================================
@@ -2116,6 +4271,66 @@ mod tests {
         assert_eq!(output, expected);
     }
 
+    #[test]
+    fn test_gamestate_transposition_key_ignores_move_counters() {
+        let gamestate = Gamestate::try_from(
+            "r1bqkb1r/pppp1ppp/2n2n2/4p3/4P3/2N2N2/PPPP1PPP/R1BQKB1R w KQkq - 4 4",
+        )
+        .unwrap();
+        let later_gamestate = Gamestate::try_from(
+            "r1bqkb1r/pppp1ppp/2n2n2/4p3/4P3/2N2N2/PPPP1PPP/R1BQKB1R w KQkq - 12 8",
+        )
+        .unwrap();
+        let output = later_gamestate.transposition_key();
+        let expected = gamestate.transposition_key();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_transposition_key_includes_en_passant() {
+        let gamestate =
+            Gamestate::try_from("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1")
+                .unwrap();
+        let no_en_passant =
+            Gamestate::try_from("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1")
+                .unwrap();
+        assert_ne!(
+            gamestate.transposition_key(),
+            no_en_passant.transposition_key()
+        );
+    }
+
+    #[test]
+    fn test_gamestate_position_key_en_passant_cleared_by_reply() {
+        // (starting FEN, reply that doesn't capture, en passant rank of the double push)
+        let inputs = [
+            ("4k3/pppppppp/8/8/8/8/PPPPPPPP/4K3 w - - 0 1", "e8d8", '3'),
+            ("4k3/pppppppp/8/8/8/8/PPPPPPPP/4K3 b - - 0 1", "e1d1", '6'),
+        ];
+        for (fen, reply, en_passant_rank) in inputs {
+            for file in ['a', 'b', 'c', 'd', 'e', 'f', 'g', 'h'] {
+                let mut gamestate = Gamestate::try_from(fen).unwrap();
+                let (start_rank, end_rank) = match en_passant_rank {
+                    '3' => ('2', '4'),
+                    _ => ('7', '5'),
+                };
+                let double_push = format!("{file}{start_rank}{file}{end_rank}");
+                gamestate.apply_uci_moves(&[&double_push]).unwrap();
+                assert_eq!(
+                    gamestate.en_passant().map(|square| square.to_string()),
+                    Some(format!("{}{en_passant_rank}", file.to_ascii_uppercase()))
+                );
+
+                gamestate.apply_uci_moves(&[reply]).unwrap();
+                let output = gamestate.position_key;
+                let expected = Gamestate::try_from(gamestate.to_fen().as_str())
+                    .unwrap()
+                    .position_key;
+                assert_eq!(output, expected, "{double_push} {reply}");
+            }
+        }
+    }
+
     //========================= MOVE GEN ======================================
 
     #[test]
@@ -2248,6 +4463,77 @@ mod tests {
         assert_eq!(output, expected);
     }
 
+    #[test]
+    fn test_gamestate_move_gen_castling_moves_in_check_from_knight() {
+        // Black Knight on D3 checks the King but doesn't attack any other square the
+        // King would cross (F1, G1 and D1)
+        let fen = "r3k2r/8/8/8/8/3n4/8/R3K2R w KQkq - 0 1";
+        let mut gamestate = Gamestate::try_from(fen).unwrap();
+        assert!(gamestate.is_in_check());
+
+        let mut output = MoveList::new();
+        gamestate.gen_castling_moves(Color::White, &mut output);
+        let expected = MoveList::new();
+        assert_eq!(output, expected);
+
+        let output = gamestate
+            .gen_legal_moves()
+            .unwrap()
+            .moves
+            .into_iter()
+            .flatten()
+            .any(|move_| move_.is_castle());
+        assert!(!output);
+    }
+
+    #[test]
+    fn test_gamestate_can_castle_available() {
+        let gamestate = Gamestate::try_from("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let output = [Color::White, Color::Black].map(|color| {
+            (
+                gamestate.can_castle_kingside(color),
+                gamestate.can_castle_queenside(color),
+            )
+        });
+        let expected = [(true, true), (true, true)];
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_can_castle_blocked() {
+        // Knights still on b1 and g8
+        let gamestate = Gamestate::try_from("r3k1nr/8/8/8/8/8/8/RN2K2R w KQkq - 0 1").unwrap();
+        let output = [Color::White, Color::Black].map(|color| {
+            (
+                gamestate.can_castle_kingside(color),
+                gamestate.can_castle_queenside(color),
+            )
+        });
+        let expected = [(true, false), (false, true)];
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_can_castle_attacked_path() {
+        // the Rook on f8 covers f1, which the King would cross
+        let gamestate = Gamestate::try_from("4kr2/8/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+        let output = (
+            gamestate.can_castle_kingside(Color::White),
+            gamestate.can_castle_queenside(Color::White),
+        );
+        let expected = (false, true);
+        assert_eq!(output, expected);
+
+        // the landing square counts as well
+        let gamestate = Gamestate::try_from("2r1k3/8/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+        let output = (
+            gamestate.can_castle_kingside(Color::White),
+            gamestate.can_castle_queenside(Color::White),
+        );
+        let expected = (true, false);
+        assert_eq!(output, expected);
+    }
+
     #[test]
     fn test_gamestate_move_gen_sliding_rooks() {
         let fen = "8/8/2p5/8/1pR1P3/8/8/8 w - - 0 1";
@@ -3530,26 +5816,788 @@ mod tests {
             false,
             None,
             false,
-            piece_moved,
-        ));
+            piece_moved,
+        ));
+
+        let output_count = output.count;
+        let expected_count = expected.count;
+
+        println!("OUTPUT:\n{}", output);
+        println!("\n\n\nEXPECTED:\n{}", expected);
+
+        assert_eq!(output_count, expected_count);
+
+        // Order doesn't need to match exactly right now since the order is
+        // tricky to make intuitive
+        let mut output = output.moves.into_iter().flatten().collect::<Vec<Move>>(); // get rid of Nones
+        let mut expected = expected.moves.into_iter().flatten().collect::<Vec<Move>>();
+        output.sort();
+        expected.sort();
+
+        assert_eq!(expected_count, output.len());
+
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_move_gen_white_pawn_quiet_and_tactical() {
+        let fen = "rnbqkb1r/pp1p1pPp/8/2p1pP2/1P1P4/3P3P/P1P1P3/RNBQKBNR w KQkq e6 0 1";
+        let gamestate = GamestateBuilder::new_with_fen(fen)
+            .unwrap()
+            .validity_check(ValidityCheck::Basic)
+            .build()
+            .unwrap();
+        let mut move_list = MoveList::new();
+        gamestate.gen_pawn_moves(Color::White, &mut move_list);
+
+        let (quiet, tactical): (Vec<Move>, Vec<Move>) = move_list
+            .moves
+            .into_iter()
+            .flatten()
+            .partition(|move_| move_.is_quiet());
+        let mut output = (
+            quiet.iter().map(|move_| move_.to_uci()).collect::<Vec<_>>(),
+            tactical
+                .iter()
+                .map(|move_| move_.to_uci())
+                .collect::<Vec<_>>(),
+        );
+        output.0.sort();
+        output.1.sort();
+        let output = (
+            output.0.iter().map(String::as_str).collect::<Vec<_>>(),
+            output.1.iter().map(String::as_str).collect::<Vec<_>>(),
+        );
+        // promotions are tactical even without a capture (g7g8)
+        let expected = (
+            vec![
+                "a2a3", "a2a4", "b4b5", "c2c3", "c2c4", "d4d5", "e2e3", "e2e4", "f5f6", "h3h4",
+            ],
+            vec![
+                "b4c5", "d4c5", "d4e5", "f5e6", "g7f8b", "g7f8n", "g7f8q", "g7f8r", "g7g8b",
+                "g7g8n", "g7g8q", "g7g8r", "g7h8b", "g7h8n", "g7h8q", "g7h8r",
+            ],
+        );
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_move_gen_quiet_and_tactical_split_kiwipete() {
+        let mut gamestate = Gamestate::try_from(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        )
+        .unwrap();
+        let legal_moves = gamestate.gen_legal_moves().unwrap();
+        let legal_moves = legal_moves.moves.into_iter().flatten().collect::<Vec<_>>();
+        let output = (
+            legal_moves.iter().filter(|move_| move_.is_quiet()).count(),
+            legal_moves
+                .iter()
+                .filter(|move_| move_.is_capture())
+                .count(),
+            legal_moves.iter().filter(|move_| move_.is_castle()).count(),
+        );
+        // 48 legal moves: 8 captures, 2 castles and everything else quiet
+        let expected = (38, 8, 2);
+        assert_eq!(output, expected);
+    }
+
+    /// Keep only the moves from the list that don't leave the mover's King in check
+    fn legal_subset(gamestate: &mut Gamestate, move_list: MoveList) -> Vec<Move> {
+        move_list
+            .moves
+            .into_iter()
+            .flatten()
+            .filter(|&move_| {
+                let is_legal = gamestate.make_move(move_).is_ok();
+                if is_legal {
+                    gamestate.undo_move().unwrap();
+                }
+                is_legal
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_gamestate_gen_captures_kiwipete() {
+        let mut gamestate = Gamestate::try_from(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        )
+        .unwrap();
+        let mut move_list = MoveList::new();
+        gamestate.gen_captures(Color::White, &mut move_list);
+        assert!(move_list.moves.iter().flatten().all(Move::is_capture));
+
+        let output = legal_subset(&mut gamestate, move_list).len();
+        let expected = 8;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_gen_evasions_matches_legal_moves() {
+        let inputs = [
+            // knight check, King can also capture the knight
+            "4k3/8/8/8/8/8/3n4/R3K3 w Q - 0 1",
+            // rook check that can be blocked or captured
+            "4r1k1/8/8/8/8/8/3B4/R3K3 w Q - 0 1",
+            // double check
+            "4k3/8/8/8/8/8/3nr3/4K1R1 w - - 0 1",
+            // en passant capture of the checking pawn
+            "8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1",
+        ];
+        for input in inputs {
+            let mut gamestate = Gamestate::try_from(input).unwrap();
+            let active_color = gamestate.active_color;
+            let mut move_list = MoveList::new();
+            gamestate.gen_evasions(active_color, &mut move_list);
+
+            let output = legal_subset(&mut gamestate, move_list);
+            let expected = gamestate
+                .gen_legal_moves()
+                .unwrap()
+                .moves
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>();
+            assert_eq!(output, expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_gamestate_gen_evasions_double_check_only_king_moves() {
+        let gamestate = Gamestate::try_from("4k3/8/8/8/8/8/3nr3/4K1R1 w - - 0 1").unwrap();
+        let mut move_list = MoveList::new();
+        gamestate.gen_evasions(Color::White, &mut move_list);
+        let output = move_list
+            .moves
+            .into_iter()
+            .flatten()
+            .all(|move_| move_.get_piece_moved() == Ok(Piece::WhiteKing));
+        assert!(output);
+    }
+
+    #[test]
+    fn test_gamestate_legal_move_count() {
+        let inputs = [
+            (DEFAULT_FEN, 20),
+            (
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                48,
+            ),
+            // stalemate
+            ("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", 0),
+        ];
+        for (input, expected) in inputs {
+            let mut gamestate = Gamestate::try_from(input).unwrap();
+            let output = gamestate.legal_move_count().unwrap();
+            assert_eq!(output, expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_gamestate_has_legal_moves() {
+        let inputs = [
+            (DEFAULT_FEN, true),
+            // fool's mate
+            (
+                "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3",
+                false,
+            ),
+            // stalemate
+            ("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", false),
+        ];
+        for (input, expected) in inputs {
+            let mut gamestate = Gamestate::try_from(input).unwrap();
+            let output = gamestate.has_legal_moves().unwrap();
+            assert_eq!(output, expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_gamestate_cached_legal_moves_invalidated_by_make_and_undo() {
+        let mut gamestate = Gamestate::default();
+        let mut fresh = Gamestate::default();
+
+        let output = gamestate.cached_legal_moves().unwrap();
+        let expected = fresh.gen_legal_moves().unwrap();
+        assert_eq!(output, &expected);
+
+        gamestate.apply_uci_moves(&["e2e4"]).unwrap();
+        fresh.apply_uci_moves(&["e2e4"]).unwrap();
+        let output = gamestate.cached_legal_moves().unwrap();
+        let expected = fresh.gen_legal_moves().unwrap();
+        assert_eq!(output, &expected);
+
+        gamestate.undo_move().unwrap();
+        fresh.undo_move().unwrap();
+        let output = gamestate.cached_legal_moves().unwrap();
+        let expected = fresh.gen_legal_moves().unwrap();
+        assert_eq!(output, &expected);
+    }
+
+    #[test]
+    fn test_gamestate_cached_legal_moves_ignored_by_eq() {
+        let mut gamestate = Gamestate::default();
+        gamestate.cached_legal_moves().unwrap();
+        let output = gamestate;
+        let expected = Gamestate::default();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_legal_moves_from() {
+        let mut gamestate = Gamestate::default();
+        let mut output: Vec<Square> = gamestate
+            .legal_moves_from(Square::E2)
+            .unwrap()
+            .iter()
+            .map(|move_| move_.get_end().unwrap())
+            .collect();
+        output.sort();
+        let expected = vec![Square::E3, Square::E4];
+        assert_eq!(output, expected);
+        assert!(gamestate.legal_moves_from(Square::E4).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_gamestate_legal_moves_from_pinned_piece() {
+        let mut gamestate = Gamestate::try_from("4r2k/8/8/8/8/8/4B3/4K3 w - - 0 1").unwrap();
+        assert!(gamestate.legal_moves_from(Square::E2).unwrap().is_empty());
+        let output = gamestate.legal_moves_from(Square::E1).unwrap().len();
+        let expected = 4;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_grouped_legal_moves() {
+        let mut gamestate = Gamestate::default();
+        let grouped = gamestate.grouped_legal_moves().unwrap();
+        let mut output: Vec<(Square, usize)> = grouped
+            .iter()
+            .map(|(square, moves)| (*square, moves.len()))
+            .collect();
+        output.sort();
+        let mut expected: Vec<(Square, usize)> = [
+            Square::A2,
+            Square::B2,
+            Square::C2,
+            Square::D2,
+            Square::E2,
+            Square::F2,
+            Square::G2,
+            Square::H2,
+        ]
+        .into_iter()
+        .map(|square| (square, 2))
+        .chain([(Square::B1, 2), (Square::G1, 2)])
+        .collect();
+        expected.sort();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_grouped_legal_moves_castling_under_king() {
+        let mut gamestate = Gamestate::try_from("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        let grouped = gamestate.grouped_legal_moves().unwrap();
+        let output = grouped[&Square::E1]
+            .iter()
+            .filter(|move_| move_.is_castle())
+            .count();
+        let expected = 1;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_random_legal_move() {
+        let mut rng = Lcg128Xsl64::seed_from_u64(FEN_FUZZ_SEED);
+        let mut gamestate = Gamestate::default();
+        let legal_moves = gamestate.gen_legal_moves().unwrap();
+        let legal_moves = legal_moves.moves.into_iter().flatten().collect::<Vec<_>>();
+        assert_eq!(legal_moves.len(), 20);
+
+        for _ in 0..10 {
+            let output = gamestate.random_legal_move(&mut rng).unwrap();
+            assert!(legal_moves.contains(&output), "{output}");
+        }
+    }
+
+    #[test]
+    fn test_gamestate_random_legal_move_checkmate() {
+        let mut rng = Lcg128Xsl64::seed_from_u64(FEN_FUZZ_SEED);
+        // fool's mate
+        let mut gamestate =
+            Gamestate::try_from("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3")
+                .unwrap();
+        let output = gamestate.random_legal_move(&mut rng);
+        let expected = None;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_legal_moves_uci() {
+        let mut gamestate = Gamestate::default();
+        let legal_moves = gamestate.legal_moves_uci().unwrap();
+        let output = (
+            legal_moves.len(),
+            ["e2e4", "g1f3", "b1c3"]
+                .iter()
+                .all(|uci| legal_moves.contains(&uci.to_string())),
+        );
+        let expected = (20, true);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_checkers_not_in_check() {
+        let gamestate = Gamestate::default();
+        let output = gamestate.checkers();
+        let expected: Vec<Square> = vec![];
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_checkers_single_check() {
+        let gamestate = Gamestate::try_from("4k3/8/8/1B6/8/8/8/4K3 b - - 0 1").unwrap();
+        let output = gamestate.checkers();
+        let expected = vec![Square::B5];
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_checkers_double_check() {
+        // Nd6+ uncovered the Rook on e1
+        let mut gamestate = Gamestate::try_from("4k3/8/8/8/4N3/8/8/4RK2 w - - 0 1").unwrap();
+        gamestate.apply_uci_moves(&["e4d6"]).unwrap();
+        let mut output = gamestate.checkers();
+        output.sort();
+        let expected = vec![Square::E1, Square::D6];
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_hanging_pieces() {
+        // the Bishop on b5 is attacked by the Rook and undefended, while taking the Knight on
+        // e5 would cost the Rook to the d6 pawn
+        let gamestate = Gamestate::try_from("4k3/8/3p4/1b2n3/8/8/8/1R2R1K1 b - - 0 1").unwrap();
+        let output = (
+            gamestate.hanging_pieces(Color::Black),
+            gamestate.hanging_pieces(Color::White),
+        );
+        let expected = (vec![Square::B5], vec![]);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_defenders_count() {
+        // pawn on e4 defended by the knight on d2 and the rook on e1, attacked by the knight on f6
+        let gamestate = Gamestate::try_from("7k/8/5n2/8/4P3/8/3N4/4R2K w - - 0 1").unwrap();
+        let output = (
+            gamestate.defenders_count(Square::E4, Color::White),
+            gamestate.defenders_count(Square::E4, Color::Black),
+        );
+        let expected = (2, 1);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_mobility_centralized_queen() {
+        let centralized = Gamestate::try_from("7k/8/8/8/3Q4/8/8/K7 w - - 0 1").unwrap();
+        let cornered = Gamestate::try_from("7k/8/8/8/8/8/8/K6Q w - - 0 1").unwrap();
+        let output = (
+            centralized.mobility(Color::White),
+            cornered.mobility(Color::White),
+        );
+        let expected = (26, 20);
+        assert_eq!(output, expected);
+    }
+
+    //=============================== SEE =====================================
+    fn legal_move(gamestate: &mut Gamestate, start: Square, end: Square) -> Move {
+        gamestate
+            .gen_legal_moves()
+            .unwrap()
+            .moves
+            .into_iter()
+            .flatten()
+            .find(|move_| move_.get_start() == Ok(start) && move_.get_end() == Ok(end))
+            .expect("move should be legal in the test position")
+    }
+
+    #[test]
+    fn test_gamestate_see_defended_pawn_capture() {
+        // the queen takes a pawn and is taken back by the d6 pawn
+        let mut gamestate = Gamestate::try_from("4k3/8/3p4/4p3/8/8/8/4QK2 w - - 0 1").unwrap();
+        let move_ = legal_move(&mut gamestate, Square::E1, Square::E5);
+        let output = gamestate.see(move_);
+        let expected = 100 - 1_000;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_see_free_piece_capture() {
+        let mut gamestate = Gamestate::try_from("4k3/8/8/4n3/8/8/8/4RK2 w - - 0 1").unwrap();
+        let move_ = legal_move(&mut gamestate, Square::E1, Square::E5);
+        let output = gamestate.see(move_);
+        let expected = 325;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_see_counts_x_rays() {
+        // after Rxe5 Rxe5 the rook on e1 recaptures, so White wins a pawn for nothing
+        let mut gamestate = Gamestate::try_from("4k3/4r3/8/4p3/8/8/4R3/4RK2 w - - 0 1").unwrap();
+        let move_ = legal_move(&mut gamestate, Square::E2, Square::E5);
+        let output = gamestate.see(move_);
+        let expected = 100;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_see_en_passant_opens_file() {
+        // taking the d5 pawn en passant lets the Rook on d1 recapture on d6, so Black won't
+        let mut gamestate = Gamestate::try_from("3rk3/8/8/3pP3/8/8/8/3RK3 w - d6 0 2").unwrap();
+        let move_ = legal_move(&mut gamestate, Square::E5, Square::D6);
+        let output = gamestate.see(move_);
+        let expected = Piece::BlackPawn.get_value() as i32;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_see_quiet_move_onto_attacked_square() {
+        // the pawn on e7 keeps the Black King out of check
+        let mut gamestate = Gamestate::try_from("4k3/4p3/3p4/8/8/8/8/4QK2 w - - 0 1").unwrap();
+        let move_ = legal_move(&mut gamestate, Square::E1, Square::E5);
+        let output = gamestate.see(move_);
+        let expected = -1_000;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_is_threefold_repetition() {
+        let knight_shuffle = ["g1f3", "g8f6", "f3g1", "f6g8"];
+        let mut gamestate = Gamestate::default();
+
+        gamestate.apply_uci_moves(&knight_shuffle).unwrap();
+        let output = gamestate.is_threefold_repetition();
+        let expected = false;
+        assert_eq!(output, expected);
+
+        gamestate.apply_uci_moves(&knight_shuffle).unwrap();
+        let output = gamestate.is_threefold_repetition();
+        let expected = true;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_repetition_count() {
+        let mut gamestate = Gamestate::default();
+        let mut output = vec![gamestate.repetition_count()];
+        for uci_move in [
+            "g1f3", "g8f6", "f3g1", "f6g8", "g1f3", "g8f6", "f3g1", "f6g8",
+        ] {
+            gamestate.apply_uci_moves(&[uci_move]).unwrap();
+            output.push(gamestate.repetition_count());
+        }
+        let expected = vec![1, 1, 1, 1, 2, 2, 2, 2, 3];
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_positions_seen() {
+        let mut gamestate = Gamestate::default();
+        let start = gamestate.position_key();
+        gamestate.apply_uci_moves(&["g1f3"]).unwrap();
+        let after_nf3 = gamestate.position_key();
+        gamestate.apply_uci_moves(&["g8f6"]).unwrap();
+
+        let output: Vec<PositionKey> = gamestate.positions_seen().collect();
+        let expected = vec![start, after_nf3, gamestate.position_key()];
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_is_threefold_repetition_reset_by_pawn_move() {
+        let mut gamestate = Gamestate::default();
+        gamestate
+            .apply_uci_moves(&["g1f3", "g8f6", "f3g1", "f6g8", "e2e4", "e7e5"])
+            .unwrap();
+        gamestate
+            .apply_uci_moves(&["g1f3", "g8f6", "f3g1", "f6g8"])
+            .unwrap();
+        let output = gamestate.is_threefold_repetition();
+        let expected = false;
+        assert_eq!(output, expected);
+    }
+
+    //============================== ANALYSIS =================================
+    #[test]
+    fn test_gamestate_annotate_history_flags_blunder() {
+        // Scholar's mate, Black's Nf6 allows Qxf7#
+        let mut gamestate = Gamestate::default();
+        gamestate
+            .apply_uci_moves(&["e2e4", "e7e5", "d1h5", "b8c6", "f1c4", "g8f6", "h5f7"])
+            .unwrap();
+        let annotations = gamestate.annotate_history(&crate::evaluation::TaperedEvaluator, 3);
+        assert_eq!(annotations.len(), 7);
+
+        let blunder = annotations
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, annotation)| annotation.delta)
+            .unwrap();
+        let output = (blunder.0, blunder.1.played.to_uci());
+        let expected = (5, "g8f6".to_owned());
+        assert_eq!(output, expected);
+
+        // the mating move is the engine's choice too
+        let mate = annotations[6];
+        let output = (mate.played == mate.best_move, mate.delta);
+        let expected = (true, 0);
+        assert_eq!(output, expected);
+    }
+
+    //============================ DEAD POSITION ==============================
+    #[test]
+    fn test_gamestate_status_dead_position_locked_pawns() {
+        // the pawn chains lock the Kings into their own halves of the board
+        let mut gamestate =
+            Gamestate::try_from("8/4k3/8/p1p1p1p1/P1P1P1P1/8/3K4/8 w - - 0 1").unwrap();
+        let output = gamestate.status();
+        let expected = Ok(Some(GameResult::DrawDeadPosition));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_status_dead_position_king_can_reach_pawn() {
+        // the g and h files are open, so the White King can walk around to the Black pawns
+        let mut gamestate = Gamestate::try_from("8/4k3/8/p1p1p3/P1P1P3/8/3K4/8 w - - 0 1").unwrap();
+        let output = gamestate.status();
+        let expected = Ok(None);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_status_dead_position_pawn_can_capture() {
+        let mut gamestate =
+            Gamestate::try_from("8/4k3/8/p1p1pp2/P1P1P1P1/8/3K4/8 w - - 0 1").unwrap();
+        let output = gamestate.status();
+        let expected = Ok(None);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_status_dead_position_other_pieces() {
+        let mut gamestate =
+            Gamestate::try_from("8/4k3/8/p1p1p1p1/P1P1P1P1/8/3K4/7N w - - 0 1").unwrap();
+        let output = gamestate.status();
+        let expected = Ok(None);
+        assert_eq!(output, expected);
+    }
+
+    //============================ BINARY FORMAT ==============================
+    #[test]
+    fn test_gamestate_bytes_round_trip() {
+        // covers en passant, promotion and castling
+        let moves = [
+            "e2e4", "d7d5", "e4e5", "f7f5", "e5f6", "e7e6", "f6g7", "e8e7", "g7h8q", "b8c6",
+            "g1f3", "d8d6", "f1b5", "c8d7", "e1g1",
+        ];
+        let mut gamestate = Gamestate::default();
+        gamestate.apply_uci_moves(&moves).unwrap();
+
+        let bytes = gamestate.to_bytes();
+        let mut decoded = Gamestate::from_bytes(&bytes).unwrap();
+        let output = (decoded.to_fen(), decoded.to_bytes());
+        let expected = (gamestate.to_fen(), bytes);
+        assert_eq!(output, expected);
+
+        // the whole history came along
+        while decoded.undo_move().is_ok() {}
+        let output = decoded.to_fen();
+        let expected = DEFAULT_FEN;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_from_bytes_invalid_header() {
+        let mut bytes = Gamestate::default().to_bytes();
+        bytes[GAME_BYTES_MAGIC.len()] = GAME_BYTES_VERSION + 1;
+        let output = Gamestate::from_bytes(&bytes);
+        let expected = Err(GameBytesError::UnsupportedVersion {
+            version: GAME_BYTES_VERSION + 1,
+            expected: GAME_BYTES_VERSION,
+        });
+        assert_eq!(output, expected);
+
+        bytes[0] = b'X';
+        let output = Gamestate::from_bytes(&bytes);
+        let expected = Err(GameBytesError::InvalidMagic {
+            expected: GAME_BYTES_MAGIC,
+        });
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_from_bytes_fen_length_too_long() {
+        let mut bytes = Gamestate::default().to_bytes();
+        let fen_offset = GAME_BYTES_MAGIC.len() + 1;
+        bytes[fen_offset..fen_offset + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        let output = Gamestate::from_bytes(&bytes);
+        let expected = Err(GameBytesError::LengthTooLong {
+            offset: fen_offset,
+            length: usize::MAX,
+            remaining: bytes.len() - fen_offset - 8,
+        });
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_from_bytes_moves_length_too_long() {
+        let mut gamestate = Gamestate::default();
+        gamestate.apply_uci_moves(&["e2e4"]).unwrap();
+        let mut bytes = gamestate.to_bytes();
+        // one move is left, but the prefix claims 2^40 of them
+        let num_moves_offset = bytes.len() - 4 - 8;
+        bytes[num_moves_offset..num_moves_offset + 8].copy_from_slice(&(1_u64 << 40).to_le_bytes());
+        let output = Gamestate::from_bytes(&bytes);
+        let expected = Err(GameBytesError::LengthTooLong {
+            offset: num_moves_offset,
+            length: 1 << 40,
+            remaining: 4,
+        });
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_from_bytes_illegal_move() {
+        let mut gamestate = Gamestate::default();
+        gamestate.apply_uci_moves(&["e2e4"]).unwrap();
+        let mut bytes = gamestate.to_bytes();
+        // Nb8-d7 lands on Black's own pawn
+        let illegal_move = Move::new(
+            Square::B8,
+            Square::D7,
+            None,
+            false,
+            false,
+            None,
+            false,
+            Piece::BlackKnight,
+        );
+        let num_moves_offset = bytes.len() - 4 - 8;
+        bytes[num_moves_offset] = 2;
+        bytes.extend_from_slice(&illegal_move.to_packed().to_le_bytes());
+
+        let output = Gamestate::from_bytes(&bytes);
+        let expected = Err(GameBytesError::IllegalMove {
+            index: 1,
+            source: MakeMoveError::IllegalMove {
+                move_: illegal_move,
+            },
+        });
+        assert_eq!(output, expected);
+    }
 
-        let output_count = output.count;
-        let expected_count = expected.count;
+    //========================= TRUNCATE HISTORY ==============================
+    #[test]
+    fn test_gamestate_truncate_history_boundary() {
+        let mut gamestate = Gamestate::default();
+        gamestate
+            .apply_uci_moves(&["e2e4", "e7e5", "g1f3", "b8c6"])
+            .unwrap();
+        gamestate.truncate_history(2);
 
-        println!("OUTPUT:\n{}", output);
-        println!("\n\n\nEXPECTED:\n{}", expected);
+        let output = [gamestate.undo_move().is_ok(), gamestate.undo_move().is_ok()];
+        let expected = [true, true];
+        assert_eq!(output, expected);
 
-        assert_eq!(output_count, expected_count);
+        let output = gamestate.undo_move();
+        let expected = Err(UndoMoveError::NoMoveToUndo);
+        assert_eq!(output, expected);
 
-        // Order doesn't need to match exactly right now since the order is
-        // tricky to make intuitive
-        let mut output = output.moves.into_iter().flatten().collect::<Vec<Move>>(); // get rid of Nones
-        let mut expected = expected.moves.into_iter().flatten().collect::<Vec<Move>>();
-        output.sort();
-        expected.sort();
+        let output = gamestate.to_fen();
+        let expected = "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2";
+        assert_eq!(output, expected);
+    }
 
-        assert_eq!(expected_count, output.len());
+    #[test]
+    fn test_gamestate_truncate_history_keep_more_than_played() {
+        let mut gamestate = Gamestate::default();
+        gamestate.apply_uci_moves(&["e2e4", "e7e5"]).unwrap();
+        let expected = gamestate.history.clone();
+        gamestate.truncate_history(5);
+        let output = gamestate.history;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_truncate_history_forgets_repetitions() {
+        let knight_shuffle = ["g1f3", "g8f6", "f3g1", "f6g8"];
+        let mut gamestate = Gamestate::default();
+        gamestate.apply_uci_moves(&knight_shuffle).unwrap();
+        gamestate.apply_uci_moves(&knight_shuffle).unwrap();
+
+        // only the second shuffle is kept, so the starting position is only seen once before
+        gamestate.truncate_history(4);
+        let output = gamestate.is_threefold_repetition();
+        let expected = false;
+        assert_eq!(output, expected);
+    }
+
+    //============================ PERFT ======================================
+    #[test]
+    fn test_gamestate_perft_hash_matches_perft() {
+        let mut gamestate = Gamestate::default();
+        for depth in 1..=4 {
+            let output = gamestate.perft_hash(depth);
+            let expected = gamestate.perft(depth);
+            assert_eq!(output, expected);
+        }
+    }
+
+    #[test]
+    fn test_gamestate_perft_hash_matches_perft_depth_5_endgame() {
+        // with only the Kings and a pawn depth 5 is cheap enough to always run, the starting
+        // position is only checked at depth 5 by the ignored test below
+        let mut gamestate = Gamestate::try_from("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1").unwrap();
+        let output = gamestate.perft_hash(5);
+        let expected = gamestate.perft(5);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    #[ignore = "takes minutes in debug builds, run with --release -- --ignored"]
+    fn test_gamestate_perft_hash_matches_perft_depth_5() {
+        let mut gamestate = Gamestate::default();
+        let output = gamestate.perft_hash(5);
+        let expected = gamestate.perft(5);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_perft_with_counts_checks() {
+        let mut gamestate = Gamestate::default();
+        let mut leaves = 0;
+        let mut checks = 0;
+        let nodes = gamestate.perft_with(3, &mut |leaf| {
+            leaves += 1;
+            if leaf.is_in_check() {
+                checks += 1;
+            }
+        });
+
+        let output = (nodes, leaves, checks);
+        let expected = (8_902, 8_902, 12);
+        assert_eq!(output, expected);
+    }
 
+    #[test]
+    fn test_gamestate_perft_starting_position() {
+        let mut gamestate = Gamestate::default();
+        let output = (1..=3)
+            .map(|depth| gamestate.perft(depth))
+            .collect::<Vec<_>>();
+        let expected = vec![20, 400, 8902];
         assert_eq!(output, expected);
     }
 
@@ -3571,6 +6619,107 @@ mod tests {
         assert_eq!(gamestate_0, gamestate_1);
     }
 
+    //============================== FROM PARTS ===============================
+    #[test]
+    fn test_gamestate_from_parts_matches_builder() {
+        let board = Board::try_from("r3k2r/8/8/3pP3/8/8/8/R3K2R").unwrap();
+        let output = Gamestate::from_parts(
+            board.clone(),
+            Color::White,
+            CastlePerm(0b_1111),
+            Some(Square64::D6),
+            0,
+            12,
+        );
+        let expected = GamestateBuilder::new_with_board(board)
+            .castle_perm(CastlePerm(0b_1111))
+            .en_passant(Some(Square64::D6))
+            .fullmove_count(12)
+            .build();
+        assert_eq!(output, expected);
+        assert_eq!(
+            output.unwrap().to_fen(),
+            "r3k2r/8/8/3pP3/8/8/8/R3K2R w KQkq d6 0 12"
+        );
+    }
+
+    #[test]
+    fn test_gamestate_from_parts_runs_strict_check() {
+        let board = BoardBuilder::new()
+            .validity_check(ValidityCheck::Basic)
+            .piece(Piece::WhiteQueen, Square64::E4)
+            .build()
+            .unwrap();
+        let output = Gamestate::from_parts(board, Color::White, CastlePerm(0), None, 0, 1).is_err();
+        assert!(output);
+    }
+
+    //=========================== RESET FROM FEN ==============================
+    #[test]
+    fn test_gamestate_reset_from_fen_matches_try_from() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut gamestate = Gamestate::default();
+        gamestate
+            .apply_uci_moves(&["e2e4", "e7e5", "g1f3", "b8c6"])
+            .unwrap();
+
+        gamestate.reset_from_fen(fen).unwrap();
+        let output = gamestate;
+        let expected = Gamestate::try_from(fen).unwrap();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_reset_from_fen_invalid() {
+        let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1";
+        let mut gamestate = Gamestate::default();
+        let output = gamestate.reset_from_fen(fen);
+        let expected = Gamestate::try_from(fen).map(|_| ());
+        assert_eq!(output, expected);
+    }
+
+    //=========================== GETTERS =====================================
+    #[test]
+    fn test_gamestate_getters() {
+        let gamestate =
+            Gamestate::try_from("rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w Kq c6 0 2")
+                .unwrap();
+
+        assert_eq!(gamestate.active_color(), Color::White);
+        assert_eq!(gamestate.castle_permissions(), CastlePerm(0b_1001));
+        assert_eq!(gamestate.en_passant(), Some(Square64::C6));
+        assert_eq!(gamestate.halfmove_clock(), 0);
+        assert_eq!(gamestate.fullmove_count(), 2);
+        assert_eq!(
+            gamestate.board().to_board_fen(),
+            "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR"
+        );
+    }
+
+    #[test]
+    fn test_gamestate_last_move_and_king_square() {
+        let mut gamestate = Gamestate::default();
+        assert_eq!(gamestate.last_move(), None);
+        assert_eq!(gamestate.king_square(Color::White), Some(Square::E1));
+
+        gamestate
+            .apply_uci_moves(&["e2e4", "e7e5", "e1e2"])
+            .unwrap();
+        let last_squares = |gamestate: &Gamestate| {
+            gamestate
+                .last_move()
+                .map(|move_| (move_.get_start().unwrap(), move_.get_end().unwrap()))
+        };
+        assert_eq!(last_squares(&gamestate), Some((Square::E1, Square::E2)));
+        assert_eq!(gamestate.king_square(Color::White), Some(Square::E2));
+        assert_eq!(gamestate.king_square(Color::Black), Some(Square::E8));
+
+        gamestate.undo_move().unwrap();
+        let output = last_squares(&gamestate);
+        let expected = Some((Square::E7, Square::E5));
+        assert_eq!(output, expected);
+    }
+
     //=========================== FEN parsing tests ===========================
     // Full FEN parsing
     #[test]
@@ -3669,6 +6818,7 @@ mod tests {
             halfmove_clock,
             fullmove_count,
             history,
+            legal_moves_cache: None,
             position_key,
         });
 
@@ -3797,6 +6947,7 @@ mod tests {
             halfmove_clock,
             fullmove_count,
             history,
+            legal_moves_cache: None,
             position_key,
         };
 
@@ -3824,6 +6975,35 @@ mod tests {
         assert_eq!(output, expected);
     }
 
+    #[test]
+    fn test_attack_map_queen_no_blockers() {
+        let board = BoardBuilder::new()
+            .validity_check(ValidityCheck::Basic)
+            .piece(Piece::WhiteQueen, Square64::E4)
+            .piece(Piece::BlackQueen, Square64::D7)
+            .build()
+            .unwrap();
+        let gamestate = GamestateBuilder::new_with_board(board)
+            .validity_check(ValidityCheck::Basic)
+            .build()
+            .unwrap();
+
+        // same squares as test_square_attacked_queen_no_blockers
+        let output = gamestate.attack_map(Color::White);
+        let expected = "\
+8 X - - - X - - -
+7 - X - - X - - X
+6 - - X - X - X -
+5 - - - X X X - -
+4 X X X X - X X X
+3 - - - X X X - -
+2 - - X - X - X -
+1 - X - - X - - X
+  A B C D E F G H
+";
+        assert_eq!(output, expected);
+    }
+
     #[test]
     fn test_square_attacked_queen_with_blocker() {
         // const FEN_1: &str = "8/3q4/8/8/4Q3/8/2P5/8 w - - 0 2";
@@ -3906,6 +7086,7 @@ mod tests {
             halfmove_clock,
             fullmove_count,
             history,
+            legal_moves_cache: None,
             position_key,
         };
 
@@ -3994,25 +7175,8 @@ mod tests {
         //     ]
         // };
         let active_color = Color::White;
-        let castle_perm = CastlePerm::try_from(0).unwrap();
-        let en_passant = None;
-        let halfmove_clock = 0;
-        let fullmove_count = 1;
-        let history = Vec::new();
-
-        // NOTE: should use the builder
-        let position_key = PositionKey(0);
-
-        let gamestate = Gamestate {
-            board,
-            active_color,
-            castle_perm,
-            en_passant,
-            halfmove_clock,
-            fullmove_count,
-            history,
-            position_key,
-        };
+        let gamestate =
+            Gamestate::from_parts(board, active_color, CastlePerm(0), None, 0, 1).unwrap();
 
         let mut output = [[false; File::COUNT]; Rank::COUNT];
         for rank in Rank::iter() {
@@ -4023,16 +7187,19 @@ mod tests {
             }
         }
 
+        // NOTE: the Bishop attacks all of its diagonals. is_square_attacked used to skip
+        // Bishops whose piece color didn't match the square color (instead of comparing the
+        // Bishop's square color), which hid every attack of this White Bishop on a dark square
         #[rustfmt::skip]
         let expected = [
-            [false, false, false, false, false, false, false, false],
-            [false, false, false, false, false, false, true,  true],
+            [true,  false, false, false, true,  false, false, false],
+            [false, true,  false, true,  false, false, true,  true],
             [false, false, false, false, false, false, true,  false],
-            [false, false, false, false, false, false, true,  true],
-            [false, false, false, false, false, false, false, false],
-            [false, false, false, false, false, false, false, false],
-            [false, false, false, false, false, false, false, false],
-            [false, false, false, false, false, false, false, false],
+            [false, true,  false, true,  false, false, true,  true],
+            [true,  false, false, false, true,  false, false, false],
+            [false, false, false, false, false, true,  false, false],
+            [false, false, false, false, false, false, true,  false],
+            [false, false, false, false, false, false, false, true],
         ];
 
         assert_eq!(output, expected);
@@ -4051,17 +7218,7 @@ mod tests {
             println!("FEN: {}", fen);
             println!("Board:\n{}", gamestate.board);
             println!("All squares attacked by {}:", gamestate.active_color);
-            for rank in Rank::iter().rev() {
-                for file in File::iter() {
-                    let square = Square::from_file_and_rank(file, rank);
-                    match gamestate.is_square_attacked(gamestate.active_color, square) {
-                        true => print!("X"),
-                        false => print!("-"),
-                    }
-                }
-                println!()
-            }
-            println!()
+            println!("{}", gamestate.attack_map(gamestate.active_color));
         }
     }
 
@@ -4192,6 +7349,51 @@ mod tests {
     }
 
     //=================================== Serialization to FEN ================
+    #[test]
+    fn test_gamestate_fen_round_trip_exact() {
+        let inputs = [
+            DEFAULT_FEN,
+            // castling subsets
+            "r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R w Kq - 0 1",
+            "r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R b Qk - 3 7",
+            "r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R w KQ - 0 1",
+            "r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R b kq - 0 1",
+            "r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R w - - 0 1",
+            // en passant for both colors
+            "rnbqkbnr/pppp1ppp/8/8/3Pp3/8/PPP1PPPP/RNBQKBNR b KQkq d3 0 2",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            // high move counts
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 99 120",
+            "4k3/8/8/8/8/8/8/4K2R b K - 42 512",
+            // Kiwipete
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            // promotion race
+            "8/P6k/8/8/8/8/p6K/8 b - - 0 60",
+        ];
+        for input in inputs {
+            let output = Gamestate::try_from(input).unwrap().to_fen();
+            let expected = input;
+            assert_eq!(output, expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_gamestate_board_fen() {
+        let gamestate =
+            Gamestate::try_from("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3")
+                .unwrap();
+        let output = gamestate.board_fen();
+        let expected = "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR";
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_starting_position_fen() {
+        let output = Gamestate::starting_position().to_fen();
+        let expected = DEFAULT_FEN;
+        assert_eq!(output, expected);
+    }
+
     #[test]
     fn test_gamestate_serialization_en_passant_opening() {
         let expected = "rnbqkbnr/pppp1pp1/7p/3Pp3/8/8/PPP1PPPP/RNBQKBNR w KQkq e6 0 3";
@@ -4267,6 +7469,26 @@ mod tests {
         assert_eq!(output, expected);
     }
 
+    #[test]
+    fn test_gamestate_try_from_invalid_fen_too_long() {
+        let input = format!("{} {}", DEFAULT_FEN, "1".repeat(10_000));
+        let output = Gamestate::try_from(input.as_str());
+        let expected = Err(GamestateBuildError::GamestateFenDeserialize(
+            GamestateFenDeserializeError::TooLong {
+                length: input.len(),
+            },
+        ));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_try_from_valid_fen_padded_to_max_len() {
+        let input = format!("{:<width$}", DEFAULT_FEN, width = MAX_FEN_LEN);
+        let output = Gamestate::try_from(input.as_str());
+        let expected = Ok(Gamestate::default());
+        assert_eq!(output, expected);
+    }
+
     #[test]
     fn test_gamestate_try_from_invalid_fen_spaces_in_board_section() {
         let invalid_board_section = "rnbqkbnr/pppppppp/";
@@ -4367,6 +7589,101 @@ mod tests {
         assert_eq!(output, expected);
     }
 
+    #[test]
+    fn test_gamestate_try_from_invalid_castle_perm_rook_missing() {
+        let input = "r3k2r/8/8/8/8/8/8/R3K3 w KQkq - 0 1";
+        let output = Gamestate::try_from(input);
+        let expected = Err(GamestateBuildError::GamestateValidityCheck(
+            GamestateValidityCheckError::StrictCastlePermRookMissing {
+                castle: Castle::WhiteKing,
+            },
+        ));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_chess960_start_fails_strict() {
+        let input = "bqnbrkrn/pppppppp/8/8/8/8/PPPPPPPP/BQNBRKRN w KQkq - 0 1";
+        let output = Gamestate::try_from(input);
+        let expected = Err(GamestateBuildError::GamestateValidityCheck(
+            GamestateValidityCheckError::StrictCastlePermKingMoved {
+                castle: Castle::WhiteKing,
+            },
+        ));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_chess960_start_passes_chess960() {
+        let input = "bqnbrkrn/pppppppp/8/8/8/8/PPPPPPPP/BQNBRKRN w KQkq - 0 1";
+        let output = GamestateBuilder::new_with_fen(input)
+            .unwrap()
+            .validity_check(ValidityCheck::Chess960)
+            .build()
+            .map(|gamestate| gamestate.to_fen());
+        let expected = Ok(input.to_owned());
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_chess960_rook_on_wrong_side() {
+        // the only White Rook is on the Queen side of the King
+        let input = "4k3/8/8/8/8/8/8/1R3KN1 w K - 0 1";
+        let output = GamestateBuilder::new_with_fen(input)
+            .unwrap()
+            .validity_check(ValidityCheck::Chess960)
+            .build();
+        let expected = Err(GamestateBuildError::GamestateValidityCheck(
+            GamestateValidityCheckError::StrictCastlePermRookMissing {
+                castle: Castle::WhiteKing,
+            },
+        ));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_chess960_keeps_board_checks() {
+        let input = "bqnbrkrn/pppppppp/8/8/8/8/PPPPPPPP/BQNBRKRK w - - 0 1";
+        let output = GamestateBuilder::new_with_fen(input)
+            .unwrap()
+            .validity_check(ValidityCheck::Chess960)
+            .build();
+        let expected = Err(GamestateBuildError::GamestateValidityCheck(
+            GamestateValidityCheckError::BoardValidityCheck(
+                BoardValidityCheckError::StrictOneBlackKingOneWhiteKing {
+                    num_white_kings: 2,
+                    num_black_kings: 1,
+                },
+            ),
+        ));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_try_from_invalid_en_passant_check_not_from_push() {
+        // the Knight on d6 was checking Black's King before e2e4 was played
+        let input = "4k3/8/3N4/8/4P3/8/8/4K3 b - e3 0 1";
+        let output = Gamestate::try_from(input);
+        let expected = Err(GamestateBuildError::GamestateValidityCheck(
+            GamestateValidityCheckError::StrictEnPassantImpossibleCheck {
+                en_passant_square: Square::E3,
+                checker: Square::D6,
+            },
+        ));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_try_from_valid_en_passant_check_from_push() {
+        // e2e4 uncovered the Bishop on d1's check on h5
+        let discovered = Gamestate::try_from("8/8/8/7k/4P3/8/8/3BK3 b - e3 0 1");
+        // d7d5 gave check with the pawn itself
+        let direct = Gamestate::try_from("8/8/8/3p4/4K3/8/8/4k3 w - d6 0 2");
+        let output = (discovered.is_ok(), direct.is_ok());
+        let expected = (true, true);
+        assert_eq!(output, expected);
+    }
+
     // Pawn has to be in front of en passant square
     #[test]
     fn test_gamestate_try_from_invalid_en_passant_no_pawn_in_front() {
@@ -4398,8 +7715,8 @@ mod tests {
     // Halfmove and Fullmove
     #[test]
     fn test_gamestate_try_from_invalid_halfmove_exceeds_max() {
-        let halfmove: u8 = 100;
-        let input = "rnbqkbnr/pppp1pp1/7p/3Pp3/8/8/PPP1PPPP/RNBQKBNR w KQkq - 100 1024";
+        let halfmove: u8 = 151;
+        let input = "rnbqkbnr/pppp1pp1/7p/3Pp3/8/8/PPP1PPPP/RNBQKBNR w KQkq - 151 1024";
         let output = Gamestate::try_from(input);
         let expected = Err(GamestateBuildError::GamestateValidityCheck(
             GamestateValidityCheckError::StrictHalfmoveClockExceedsMax {
@@ -4409,6 +7726,50 @@ mod tests {
         assert_eq!(output, expected);
     }
 
+    #[test]
+    fn test_gamestate_try_from_valid_halfmove_fifty_move_draw() {
+        let input = "rnbqkbnr/pppp1pp1/7p/3Pp3/8/8/PPP1PPPP/RNBQKBNR w KQkq - 100 1024";
+        let mut gamestate = Gamestate::try_from(input).unwrap();
+        let output = gamestate.status();
+        let expected = Ok(Some(GameResult::DrawFiftyMove));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_status_checkmate_takes_precedence_over_fifty_move_rule() {
+        let input = "7k/6Q1/6K1/8/8/8/8/8 b - - 100 80";
+        let mut gamestate = Gamestate::try_from(input).unwrap();
+        let output = gamestate.status();
+        let expected = Ok(Some(GameResult::Checkmate {
+            winner: Color::White,
+        }));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_is_checkmate_back_rank() {
+        let mut gamestate = Gamestate::try_from("3R2k1/5ppp/8/8/8/8/8/6K1 b - - 1 1").unwrap();
+        let output = (gamestate.is_checkmate(), gamestate.is_stalemate());
+        let expected = (true, false);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_is_stalemate() {
+        let mut gamestate = Gamestate::try_from("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        let output = (gamestate.is_checkmate(), gamestate.is_stalemate());
+        let expected = (false, true);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_gamestate_is_checkmate_starting_position() {
+        let mut gamestate = Gamestate::default();
+        let output = (gamestate.is_checkmate(), gamestate.is_stalemate());
+        let expected = (false, false);
+        assert_eq!(output, expected);
+    }
+
     #[test]
     fn test_gamestate_try_from_invalid_fullmove_exceeds_max() {
         let fullmove: usize = 1025;
@@ -4512,4 +7873,85 @@ mod tests {
         ));
         assert_eq!(output, expected);
     }
+
+    //=========================== FEN ROUND TRIP FUZZ ===========================
+    const FEN_FUZZ_SEED: u64 = 0x5eed_f3e1;
+    const FEN_FUZZ_GAMES_PER_SEED_FEN: usize = 8;
+    const FEN_FUZZ_MAX_PLIES: usize = 60;
+    const FEN_FUZZ_SEED_FENS: [&str; 5] = [
+        DEFAULT_FEN,
+        // Kiwipete
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        // En passant available
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    ];
+
+    /// Everything except history, which a Gamestate built from a FEN can't know about
+    fn assert_fen_round_trip_eq(output: &Gamestate, expected: &Gamestate, context: &str) {
+        let sorted_piece_list = |gamestate: &Gamestate| {
+            let mut piece_list = gamestate.board.piece_list.clone();
+            for squares in piece_list.iter_mut() {
+                squares.sort();
+            }
+            piece_list
+        };
+
+        assert_eq!(output.board.pieces, expected.board.pieces, "{context}");
+        assert_eq!(output.board.pawns, expected.board.pawns, "{context}");
+        assert_eq!(
+            output.board.kings_square, expected.board.kings_square,
+            "{context}"
+        );
+        assert_eq!(
+            output.board.piece_count, expected.board.piece_count,
+            "{context}"
+        );
+        assert_eq!(
+            output.board.material_score, expected.board.material_score,
+            "{context}"
+        );
+        assert_eq!(
+            sorted_piece_list(output),
+            sorted_piece_list(expected),
+            "{context}"
+        );
+        assert_eq!(output.active_color, expected.active_color, "{context}");
+        assert_eq!(output.castle_perm, expected.castle_perm, "{context}");
+        assert_eq!(output.en_passant, expected.en_passant, "{context}");
+        assert_eq!(output.halfmove_clock, expected.halfmove_clock, "{context}");
+        assert_eq!(output.fullmove_count, expected.fullmove_count, "{context}");
+        assert_eq!(output.position_key, expected.position_key, "{context}");
+    }
+
+    #[test]
+    fn test_gamestate_fen_round_trip_fuzz() {
+        let mut rng = Lcg128Xsl64::seed_from_u64(FEN_FUZZ_SEED);
+
+        for seed_fen in FEN_FUZZ_SEED_FENS {
+            for _ in 0..FEN_FUZZ_GAMES_PER_SEED_FEN {
+                let mut gamestate = Gamestate::try_from(seed_fen).unwrap();
+                let mut moves_played = vec![];
+
+                for _ in 0..FEN_FUZZ_MAX_PLIES {
+                    let move_ = match gamestate.random_legal_move(&mut rng) {
+                        Some(move_) => move_,
+                        None => break,
+                    };
+                    gamestate.make_move(move_).unwrap();
+                    moves_played.push(move_.to_uci());
+
+                    let fen = gamestate.to_fen();
+                    let context = format!(
+                        "FEN {fen} reached from {seed_fen} with moves: {}",
+                        moves_played.join(" ")
+                    );
+                    let output = Gamestate::try_from(fen.as_str())
+                        .unwrap_or_else(|err| panic!("{context} failed to parse: {err}"));
+                    assert_fen_round_trip_eq(&output, &gamestate, &context);
+                }
+            }
+        }
+    }
 }
@@ -8,12 +8,17 @@ mod macros;
 pub mod board;
 pub mod castle_perm;
 pub mod color;
+pub mod epd;
 pub mod error;
+pub mod evaluation;
 pub mod file;
 pub mod gamestate;
 pub mod moves;
+pub mod opening_book;
+pub mod pgn;
 pub mod piece;
 pub mod position_key;
 pub mod rank;
+pub mod search;
 pub mod square;
 pub mod zobrist;
@@ -1,4 +1,4 @@
-use std::fmt;
+use std::{cmp::Ordering, fmt};
 
 use crate::{
     board::NUM_BOARD_COLUMNS,
@@ -158,7 +158,7 @@ impl MoveBuilder {
         };
 
         match self.validity_check {
-            ValidityCheck::Strict => Ok(*move_.check_move_strict()?),
+            ValidityCheck::Strict | ValidityCheck::Chess960 => Ok(*move_.check_move_strict()?),
             // TODO: for now do redundant checks and then after we have a good perft setup we will refactor
             ValidityCheck::Move => Ok(*move_.check_move_strict()?),
             ValidityCheck::Basic => Ok(*move_.check_move()?),
@@ -187,7 +187,7 @@ impl MoveBuilder {
 /// IMPORTANT: 000 0000 indicates Square 0 in theory (in practice we should avoid this with the type system) and not absence
 ///            0000 indicates absence for Pieces. 0001 indicated White Pawn
 /// NOTE: The number of pieces can fit in 4 bits while the number of 120 squares can fit in 7 bits
-#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct Move {
     move_: u32,
     score: u16,
@@ -707,6 +707,12 @@ impl Move {
         (self.move_ & MOVE_IS_PROMOTED_MASK) != 0
     }
 
+    /// Neither a capture (en passant included), a promotion nor a castle. Every Move is
+    /// either quiet or tactical.
+    pub fn is_quiet(&self) -> bool {
+        !(self.is_capture() || self.is_promotion() || self.is_castle())
+    }
+
     pub fn is_initial_state_dummy(&self) -> bool {
         self.move_ == INITIAL_STATE_DUMMY_MOVE
     }
@@ -715,9 +721,64 @@ impl Move {
         self.score
     }
 
+    /// The bit-packed representation without the score, e.g. for storing games compactly
+    pub fn to_packed(&self) -> u32 {
+        self.move_
+    }
+
+    /// Inverse of to_packed. The bits are checked with check_move since they usually come
+    /// from outside the engine.
+    pub fn from_packed(move_: u32) -> Result<Self, MoveValidityError> {
+        let move_ = Move { move_, score: 0 };
+        move_.check_move()?;
+        Ok(move_)
+    }
+
     // pub fn from_uci(uci: &str) -> Self {
     //     todo!()
     // }
+
+    /// Long algebraic notation as used by UCI, e.g. "e2e4" or "e7e8q"
+    pub fn to_uci(&self) -> String {
+        let start = self.get_start().expect("start should always be valid");
+        let end = self.get_end().expect("end should always be valid");
+        let mut uci = format!("{}{}", start.to_algebraic(), end.to_algebraic());
+
+        if let Some(piece) = self
+            .get_piece_promoted()
+            .expect("piece_promoted should always be valid")
+        {
+            uci.push(char::from(piece).to_ascii_lowercase());
+        }
+
+        uci
+    }
+}
+
+/// Moves are ordered by start square, then end square, then promoted piece (no promotion
+/// first, then in Piece order), so sorted move lists read predictably whatever the bit
+/// layout is. The remaining bits and the score only break ties to keep the order
+/// consistent with Eq.
+/// NOTE: this says nothing about how good a move is, search orders moves by their score
+impl Ord for Move {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let key = |move_: &Move| {
+            (
+                move_.get_start_raw(),
+                move_.get_end_raw(),
+                move_.get_piece_promoted_raw(),
+                move_.move_,
+                move_.score,
+            )
+        };
+        key(self).cmp(&key(other))
+    }
+}
+
+impl PartialOrd for Move {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
 impl fmt::Display for Move {
@@ -1300,6 +1361,93 @@ mod tests {
         assert!(output.is_ok());
     }
 
+    //================================ PACKED =================================
+    #[test]
+    fn test_move_from_packed_round_trip() {
+        let move_ = Move::new(
+            Square::G7,
+            Square::H8,
+            Some(Piece::BlackRook),
+            false,
+            false,
+            Some(Piece::WhiteQueen),
+            false,
+            Piece::WhitePawn,
+        );
+        let output = Move::from_packed(move_.to_packed());
+        let expected = Ok(move_);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_move_from_packed_invalid() {
+        let output = Move::from_packed(u32::MAX);
+        let expected = Err(MoveValidityError::MoveDeserialize(
+            MoveDeserializeError::Start {
+                start: 127,
+                move_: u32::MAX,
+            },
+        ));
+        assert_eq!(output, expected);
+    }
+
+    //================================ ORDERING ===============================
+    #[test]
+    fn test_move_ord_start_end_then_promotion() {
+        let pawn_move = |start, end, promoted| {
+            Move::new(
+                start,
+                end,
+                None,
+                false,
+                false,
+                promoted,
+                false,
+                Piece::WhitePawn,
+            )
+        };
+        let mut output = [
+            pawn_move(Square::B7, Square::B8, Some(Piece::WhiteQueen)),
+            pawn_move(Square::H2, Square::H3, None),
+            pawn_move(Square::B7, Square::B8, Some(Piece::WhiteKnight)),
+            pawn_move(Square::A2, Square::A4, None),
+            pawn_move(Square::A7, Square::A8, Some(Piece::WhiteRook)),
+            pawn_move(Square::A2, Square::A3, None),
+        ];
+        output.sort();
+        let output = output.iter().map(Move::to_uci).collect::<Vec<_>>();
+        let expected = vec!["a2a3", "a2a4", "h2h3", "a7a8r", "b7b8n", "b7b8q"];
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_move_ord_ignores_bit_layout() {
+        // piece_moved sits in the highest bits but only breaks ties
+        let rook_move = Move::new(
+            Square::A1,
+            Square::A8,
+            None,
+            false,
+            false,
+            None,
+            false,
+            Piece::WhiteRook,
+        );
+        let knight_move = Move::new(
+            Square::B1,
+            Square::C3,
+            None,
+            false,
+            false,
+            None,
+            false,
+            Piece::WhiteKnight,
+        );
+        let output = rook_move.cmp(&knight_move);
+        let expected = Ordering::Less;
+        assert_eq!(output, expected);
+    }
+
     //================================ DISPLAY ================================
     // TODO: these display tests rely heavily on Gamestate functionality
     // should write some decoupled tests
@@ -1463,6 +1611,40 @@ mod tests {
         assert_eq!(output, expected)
     }
 
+    #[test]
+    fn test_to_uci() {
+        let move_ = Move::new(
+            Square::E2,
+            Square::E4,
+            None,
+            false,
+            true,
+            None,
+            false,
+            Piece::WhitePawn,
+        );
+        let output = move_.to_uci();
+        let expected = "e2e4";
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_to_uci_promotion() {
+        let move_ = Move::new(
+            Square::B2,
+            Square::A1,
+            Some(Piece::WhiteRook),
+            false,
+            false,
+            Some(Piece::BlackKnight),
+            false,
+            Piece::BlackPawn,
+        );
+        let output = move_.to_uci();
+        let expected = "b2a1n";
+        assert_eq!(output, expected);
+    }
+
     // #[test]
     // fn test_from_uci() {
     //     let ref_string = "e2e4";
@@ -0,0 +1,169 @@
+use rand::{thread_rng, Rng};
+use std::{collections::HashMap, fs, path::Path};
+
+use crate::{
+    error::OpeningBookError,
+    gamestate::Gamestate,
+    moves::Move,
+    pgn::{import_pgn, PgnGame},
+    position_key::PositionKey,
+};
+
+// CONSTANTS:
+/// By default only the first few moves of each game are considered opening theory
+pub const DEFAULT_BOOK_PLIES: usize = 16;
+
+/// Moves seen in known games, keyed on the PositionKey of the position they were
+/// played from. Each move is weighted by how often it was played. Since Zobrist keys
+/// are generated from a fixed seed the entries stay valid across runs.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct OpeningBook {
+    entries: HashMap<PositionKey, Vec<(Move, u16)>>,
+}
+
+impl OpeningBook {
+    pub fn new() -> Self {
+        OpeningBook::default()
+    }
+
+    /// Build a book from every .pgn file in the directory, using the first max_plies
+    /// moves of each game
+    pub fn from_pgn_dir(dir: &Path, max_plies: usize) -> Result<Self, OpeningBookError> {
+        let read_dir_error = |err: std::io::Error| OpeningBookError::ReadDir {
+            path: dir.display().to_string(),
+            message: err.to_string(),
+        };
+
+        let mut paths = fs::read_dir(dir)
+            .map_err(read_dir_error)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(read_dir_error)?;
+        paths.retain(|path| path.extension().is_some_and(|ext| ext == "pgn"));
+        // NOTE: sort so that building the same directory always gives the same book
+        paths.sort();
+
+        let mut book = OpeningBook::new();
+        for path in paths {
+            let pgn = fs::read_to_string(&path).map_err(|err| OpeningBookError::ReadFile {
+                path: path.display().to_string(),
+                message: err.to_string(),
+            })?;
+            book.add_pgn(&pgn, max_plies)?;
+        }
+
+        Ok(book)
+    }
+
+    /// Add every game in a PGN string to the book
+    pub fn add_pgn(&mut self, pgn: &str, max_plies: usize) -> Result<(), OpeningBookError> {
+        for game in import_pgn(pgn)? {
+            self.add_game(&game, max_plies)?;
+        }
+        Ok(())
+    }
+
+    pub fn add_game(&mut self, game: &PgnGame, max_plies: usize) -> Result<(), OpeningBookError> {
+        let mut gamestate = Gamestate::try_from(game.start_fen.as_str())?;
+
+        for &move_ in game.moves.iter().take(max_plies) {
+            let moves = self.entries.entry(gamestate.position_key()).or_default();
+            match moves.iter_mut().find(|(book_move, _)| *book_move == move_) {
+                Some((_, weight)) => *weight = weight.saturating_add(1),
+                None => moves.push((move_, 1)),
+            }
+            gamestate.make_move(move_)?;
+        }
+
+        Ok(())
+    }
+
+    /// Weighted book moves for the position, if it is in the book
+    pub fn lookup(&self, key: PositionKey) -> Option<Vec<(Move, u16)>> {
+        self.entries.get(&key).cloned()
+    }
+
+    /// Pick a book move for the Gamestate at random, proportionally to its weight.
+    /// Book moves are checked against the legal moves of the position in case of a
+    /// PositionKey collision.
+    pub fn choose_move(&self, gamestate: &mut Gamestate) -> Option<Move> {
+        let legal_moves = gamestate.gen_legal_moves().ok()?;
+        let book_moves = self
+            .lookup(gamestate.position_key())?
+            .into_iter()
+            .filter(|(move_, _)| legal_moves.moves.contains(&Some(*move_)))
+            .collect::<Vec<_>>();
+
+        let total_weight = book_moves
+            .iter()
+            .map(|&(_, weight)| weight as u32)
+            .sum::<u32>();
+        if total_weight == 0 {
+            return None;
+        }
+
+        let mut pick = thread_rng().gen_range(0..total_weight);
+        for (move_, weight) in book_moves {
+            if pick < weight as u32 {
+                return Some(move_);
+            }
+            pick -= weight as u32;
+        }
+        None
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pgn::san_to_move;
+
+    const PGN: &str = "[Event \"A\"]\n\n1. e4 e5 2. Nf3 Nc6 1-0\n\n[Event \"B\"]\n\n1. e4 c5 0-1\n\n[Event \"C\"]\n\n1. d4 d5 *\n";
+
+    #[test]
+    fn test_lookup_weights_moves_by_frequency() {
+        let mut book = OpeningBook::new();
+        book.add_pgn(PGN, DEFAULT_BOOK_PLIES).unwrap();
+
+        let mut gamestate = Gamestate::default();
+        let e4 = san_to_move(&mut gamestate, "e4").unwrap();
+        let d4 = san_to_move(&mut gamestate, "d4").unwrap();
+
+        let output = book.lookup(gamestate.position_key());
+        let expected = Some(vec![(e4, 2), (d4, 1)]);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_lookup_respects_max_plies() {
+        let mut book = OpeningBook::new();
+        book.add_pgn(PGN, 1).unwrap();
+
+        let mut gamestate = Gamestate::default();
+        let e4 = san_to_move(&mut gamestate, "e4").unwrap();
+        gamestate.make_move(e4).unwrap();
+
+        let output = book.lookup(gamestate.position_key());
+        let expected = None;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_choose_move_is_a_book_move() {
+        let mut book = OpeningBook::new();
+        book.add_pgn(PGN, DEFAULT_BOOK_PLIES).unwrap();
+
+        let mut gamestate = Gamestate::default();
+        let output = book.choose_move(&mut gamestate).unwrap();
+        let book_moves = book.lookup(gamestate.position_key()).unwrap();
+        assert!(book_moves.iter().any(|&(move_, _)| move_ == output));
+    }
+}
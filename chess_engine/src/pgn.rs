@@ -0,0 +1,472 @@
+use crate::{
+    error::{MakeMoveError, PgnError},
+    gamestate::{Gamestate, DEFAULT_FEN},
+    moves::Move,
+    piece::{Piece, PieceType},
+    square::Square,
+};
+
+// CONSTANTS:
+const GAME_RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+/// A single game read from PGN. Only the mainline is kept, comments and variations
+/// are skipped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    /// FEN of the position the game started from (taken from the FEN tag if there is one)
+    pub start_fen: String,
+    pub moves: Vec<Move>,
+}
+
+impl PgnGame {
+    pub fn get_tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Import every game in a PGN string, resolving each SAN move against the legal
+/// moves of the position it was played in
+pub fn import_pgn(pgn: &str) -> Result<Vec<PgnGame>, PgnError> {
+    split_games(pgn)
+        .into_iter()
+        .map(|(tag_section, movetext)| import_game(&tag_section, &movetext))
+        .collect()
+}
+
+/// Split a PGN database into (tag section, movetext) pairs, one per game
+fn split_games(pgn: &str) -> Vec<(String, String)> {
+    let mut games = Vec::new();
+    let mut tag_section = String::new();
+    let mut movetext = String::new();
+
+    for line in pgn.lines().map(str::trim) {
+        if line.starts_with('[') {
+            // A tag after movetext means a new game has started
+            if !movetext.trim().is_empty() {
+                games.push((tag_section, movetext));
+                tag_section = String::new();
+                movetext = String::new();
+            }
+            tag_section.push_str(line);
+            tag_section.push('\n');
+        } else if !line.starts_with('%') {
+            movetext.push_str(line);
+            movetext.push('\n');
+        }
+    }
+
+    if !tag_section.is_empty() || !movetext.trim().is_empty() {
+        games.push((tag_section, movetext));
+    }
+
+    games
+}
+
+fn import_game(tag_section: &str, movetext: &str) -> Result<PgnGame, PgnError> {
+    let tags = tag_section
+        .lines()
+        .map(parse_tag)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let start_fen = tags
+        .iter()
+        .find(|(tag, _)| tag == "FEN")
+        .map(|(_, fen)| fen.clone())
+        .unwrap_or_else(|| DEFAULT_FEN.to_string());
+
+    let mut gamestate = Gamestate::try_from(start_fen.as_str())?;
+    let mut moves = Vec::new();
+    for san in tokenize_movetext(movetext) {
+        let move_ = san_to_move(&mut gamestate, &san)?;
+        gamestate.make_move(move_)?;
+        moves.push(move_);
+    }
+
+    Ok(PgnGame {
+        tags,
+        start_fen,
+        moves,
+    })
+}
+
+/// Parse a tag pair like [White "Kasparov, Garry"]
+fn parse_tag(line: &str) -> Result<(String, String), PgnError> {
+    let invalid_tag = || PgnError::InvalidTag {
+        tag: line.to_string(),
+    };
+
+    let inner = line
+        .strip_prefix('[')
+        .and_then(|line| line.strip_suffix(']'))
+        .ok_or_else(invalid_tag)?;
+    let (name, value) = inner.split_once(' ').ok_or_else(invalid_tag)?;
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .ok_or_else(invalid_tag)?;
+
+    Ok((name.to_string(), value.replace("\\\"", "\"")))
+}
+
+/// Reduce movetext to its SAN tokens, dropping move numbers, comments, variations,
+/// annotation glyphs and the game result
+fn tokenize_movetext(movetext: &str) -> Vec<String> {
+    let mut mainline = String::new();
+    let mut variation_depth = 0;
+    let mut chars = movetext.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => {
+                chars.by_ref().find(|&c| c == '}');
+                mainline.push(' ');
+            }
+            ';' => {
+                chars.by_ref().find(|&c| c == '\n');
+                mainline.push(' ');
+            }
+            '(' => variation_depth += 1,
+            ')' => {
+                variation_depth -= 1;
+                mainline.push(' ');
+            }
+            _ if variation_depth > 0 => {}
+            _ => mainline.push(c),
+        }
+    }
+
+    mainline
+        .split_whitespace()
+        .filter(|token| !GAME_RESULTS.contains(token))
+        .map(strip_move_number)
+        .filter(|token| !token.is_empty() && !token.starts_with('$'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Strip a move number, which may be attached to the move like "1.e4" or "12...Nf6". Only
+/// digits followed by a dot count as one, so zero style castling like "0-0" is left alone.
+fn strip_move_number(token: &str) -> &str {
+    let after_digits = token.trim_start_matches(|c: char| c.is_ascii_digit());
+    match after_digits.strip_prefix('.') {
+        Some(after_dots) => after_dots.trim_start_matches('.'),
+        None => token,
+    }
+}
+
+/// Find the legal move in the current position that the SAN string describes
+pub fn san_to_move(gamestate: &mut Gamestate, san: &str) -> Result<Move, PgnError> {
+    let invalid_san = || PgnError::InvalidSan {
+        san: san.to_string(),
+    };
+    // NOTE: the slicing below assumes one byte per character
+    if !san.is_ascii() {
+        return Err(invalid_san());
+    }
+
+    let stripped = san.trim_end_matches(['+', '#', '!', '?']);
+    let legal_moves = gamestate.gen_legal_moves()?;
+    let legal_moves = legal_moves.moves.into_iter().flatten();
+
+    let candidates = match stripped {
+        "O-O" | "0-0" | "O-O-O" | "0-0-0" => {
+            let kingside = stripped.len() == 3;
+            legal_moves
+                .filter(|move_| {
+                    move_.is_castle()
+                        && move_.get_end().map(|end| end.to_string().starts_with('G'))
+                            == Ok(kingside)
+                })
+                .collect::<Vec<_>>()
+        }
+        _ => {
+            let (piece_type, rest) = match stripped.chars().next() {
+                Some('N') => (PieceType::Knight, &stripped[1..]),
+                Some('B') => (PieceType::Bishop, &stripped[1..]),
+                Some('R') => (PieceType::Rook, &stripped[1..]),
+                Some('Q') => (PieceType::Queen, &stripped[1..]),
+                Some('K') => (PieceType::King, &stripped[1..]),
+                Some(_) => (PieceType::Pawn, stripped),
+                None => return Err(invalid_san()),
+            };
+
+            let (rest, promotion) = match rest.char_indices().last() {
+                Some((index, c)) if "NBRQ".contains(c) => {
+                    let promoted = Piece::try_from(c).map_err(|_| invalid_san())?;
+                    (
+                        rest[..index].trim_end_matches('='),
+                        Some(promoted.get_piece_type()),
+                    )
+                }
+                _ => (rest, None),
+            };
+
+            if rest.len() < 2 {
+                return Err(invalid_san());
+            }
+            let (disambiguation, end) = rest.split_at(rest.len() - 2);
+            let end = Square::from_algebraic(end).map_err(|_| invalid_san())?;
+            let disambiguation = disambiguation.replace('x', "").to_uppercase();
+
+            legal_moves
+                .filter(|move_| {
+                    let moved = move_
+                        .get_piece_moved()
+                        .expect("generated moves should always have a valid moved piece");
+                    let promoted = move_
+                        .get_piece_promoted()
+                        .expect("generated moves should always have a valid promoted piece")
+                        .map(|piece| piece.get_piece_type());
+                    let start = move_
+                        .get_start()
+                        .expect("generated moves should always have a valid start")
+                        .to_string();
+
+                    moved.get_piece_type() == piece_type
+                        && move_.get_end() == Ok(end)
+                        && promoted == promotion
+                        && disambiguation.chars().all(|c| start.contains(c))
+                })
+                .collect::<Vec<_>>()
+        }
+    };
+
+    match candidates.as_slice() {
+        [move_] => Ok(*move_),
+        [] => Err(PgnError::IllegalMove {
+            san: san.to_string(),
+        }),
+        _ => Err(PgnError::AmbiguousMove {
+            san: san.to_string(),
+        }),
+    }
+}
+
+/// Write a legal move of the current position in SAN, including + or # when it gives
+/// check or checkmate. The Gamestate is left as it was.
+pub fn move_to_san(gamestate: &mut Gamestate, move_: Move) -> Result<String, MakeMoveError> {
+    let legal_moves = gamestate.gen_legal_moves()?;
+    if !legal_moves.moves.contains(&Some(move_)) {
+        return Err(MakeMoveError::IllegalMove { move_ });
+    }
+
+    let start = move_.get_start()?;
+    let end = move_.get_end()?;
+    let piece_moved = move_.get_piece_moved()?;
+
+    let mut san = if move_.is_castle() {
+        match end.to_string().starts_with('G') {
+            true => "O-O".to_string(),
+            false => "O-O-O".to_string(),
+        }
+    } else {
+        let mut san = String::new();
+        if piece_moved.is_pawn() {
+            if move_.is_capture() {
+                san.push_str(&start.to_algebraic()[..1]);
+            }
+        } else {
+            san.push(char::from(piece_moved).to_ascii_uppercase());
+
+            // Only disambiguate between pieces of the same kind that can reach the same square
+            let others = legal_moves
+                .moves
+                .into_iter()
+                .flatten()
+                .filter(|other| {
+                    other.get_piece_moved() == Ok(piece_moved)
+                        && other.get_end() == Ok(end)
+                        && other.get_start() != Ok(start)
+                })
+                .filter_map(|other| other.get_start().ok())
+                .collect::<Vec<_>>();
+            if !others.is_empty() {
+                let same_file = others
+                    .iter()
+                    .any(|other| other.get_file() == start.get_file());
+                let same_rank = others
+                    .iter()
+                    .any(|other| other.get_rank() == start.get_rank());
+                match (same_file, same_rank) {
+                    (false, _) => san.push_str(&start.to_algebraic()[..1]),
+                    (true, false) => san.push_str(&start.to_algebraic()[1..]),
+                    (true, true) => san.push_str(&start.to_algebraic()),
+                }
+            }
+        }
+
+        if move_.is_capture() {
+            san.push('x');
+        }
+        san.push_str(&end.to_algebraic());
+        if let Some(promoted) = move_.get_piece_promoted()? {
+            san.push('=');
+            san.push(char::from(promoted).to_ascii_uppercase());
+        }
+        san
+    };
+
+    if gamestate.gives_check(move_)? {
+        gamestate.make_move_unchecked(move_)?;
+        let has_legal_moves = gamestate.has_legal_moves();
+        gamestate
+            .undo_move()
+            .expect("undoing a move that was just made should never fail");
+        match has_legal_moves? {
+            true => san.push('+'),
+            false => san.push('#'),
+        }
+    }
+
+    Ok(san)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_pgn_replays_mainline() {
+        let pgn = r#"[Event "Casual Game"]
+[White "Anderssen, Adolf"]
+[Black "Kieseritzky, Lionel"]
+[Result "1-0"]
+
+1. e4 e5 2. f4 exf4 {King's Gambit Accepted} 3. Bc4 Qh4+ (3... Nf6) 4. Kf1 b5 5. Bxb5
+Nf6 6. Nf3 Qh6 7. d3 Nh5 8. Nh4 Qg5 9. Nf5 c6 10. g4 Nf6 11. Rg1 cxb5 12. h4 Qg6
+13. h5 Qg5 14. Qf3 Ng8 15. Bxf4 Qf6 16. Nc3 Bc5 17. Nd5 Qxb2 18. Bd6 Bxg1 19. e5
+Qxa1+ 20. Ke2 Na6 21. Nxg7+ Kd8 22. Qf6+ Nxf6 23. Be7# 1-0
+"#;
+        let games = import_pgn(pgn).unwrap();
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].get_tag("White"), Some("Anderssen, Adolf"));
+        assert_eq!(games[0].moves.len(), 45);
+
+        let mut gamestate = Gamestate::default();
+        for &move_ in &games[0].moves {
+            gamestate.make_move(move_).unwrap();
+        }
+        let output = gamestate.to_fen();
+        let expected = "r1bk3r/p2pBpNp/n4n2/1p1NP2P/6P1/3P4/P1P1K3/q5b1 b - - 1 23";
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_import_pgn_multiple_games() {
+        let pgn = "[Event \"A\"]\n\n1. e4 e5 1-0\n\n[Event \"B\"]\n\n1. d4 d5 2. c4 *\n";
+        let output = import_pgn(pgn)
+            .unwrap()
+            .into_iter()
+            .map(|game| game.moves.len())
+            .collect::<Vec<_>>();
+        let expected = vec![2, 3];
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_import_pgn_zero_castling() {
+        // with the move numbers both apart from and attached to the castling moves
+        let pgn = "1. e4 d5 2. Nf3 Nc6 3. Bc4 Bg4 4.0-0 Qd7 5. d3 5...0-0-0 *";
+        let output = import_pgn(pgn).unwrap()[0]
+            .moves
+            .iter()
+            .map(|move_| move_.to_uci())
+            .collect::<Vec<_>>();
+        let expected = vec![
+            "e2e4", "d7d5", "g1f3", "b8c6", "f1c4", "c8g4", "e1g1", "d8d7", "d2d3", "e8c8",
+        ];
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_san_to_move_disambiguation_and_promotion() {
+        let mut gamestate = Gamestate::try_from("4k3/1P6/8/8/8/8/4K3/R6R w - - 0 1").unwrap();
+        let output = san_to_move(&mut gamestate, "b8=Q+").unwrap();
+        let expected = Move::new(
+            Square::B7,
+            Square::B8,
+            None,
+            false,
+            false,
+            Some(Piece::WhiteQueen),
+            false,
+            Piece::WhitePawn,
+        );
+        assert_eq!(output, expected);
+
+        let output = san_to_move(&mut gamestate, "Rd1");
+        let expected = Err(PgnError::AmbiguousMove {
+            san: "Rd1".to_string(),
+        });
+        assert_eq!(output, expected);
+
+        let output = san_to_move(&mut gamestate, "Rad1").unwrap().get_start();
+        let expected = Ok(Square::A1);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_san_to_move_castling() {
+        let mut gamestate = Gamestate::try_from("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let output = san_to_move(&mut gamestate, "O-O").unwrap().get_end();
+        let expected = Ok(Square::G1);
+        assert_eq!(output, expected);
+
+        let output = san_to_move(&mut gamestate, "O-O-O").unwrap().get_end();
+        let expected = Ok(Square::C1);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_move_to_san_round_trip() {
+        let inputs = [
+            ("4k3/1P6/8/8/8/8/4K3/R6R w - - 0 1", "b8=Q+"),
+            ("4k3/1P6/8/8/8/8/4K3/R6R w - - 0 1", "Rad1"),
+            ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "O-O-O"),
+            ("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "Ra8#"),
+            ("7k/8/8/8/R7/8/8/R5K1 w - - 0 1", "R4a2"),
+            ("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "exd6"),
+        ];
+        for (fen, san) in inputs {
+            let mut gamestate = Gamestate::try_from(fen).unwrap();
+            let move_ = san_to_move(&mut gamestate, san).unwrap();
+            let output = move_to_san(&mut gamestate, move_).unwrap();
+            let expected = san;
+            assert_eq!(output, expected);
+            assert_eq!(gamestate.to_fen(), fen);
+        }
+    }
+
+    #[test]
+    fn test_san_to_move_illegal() {
+        let mut gamestate = Gamestate::default();
+        let output = san_to_move(&mut gamestate, "e5");
+        let expected = Err(PgnError::IllegalMove {
+            san: "e5".to_string(),
+        });
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_san_to_move_non_ascii() {
+        let mut gamestate = Gamestate::default();
+        let output = (
+            san_to_move(&mut gamestate, "Ké1"),
+            san_to_move(&mut gamestate, "é1"),
+        );
+        let expected = (
+            Err(PgnError::InvalidSan {
+                san: "Ké1".to_string(),
+            }),
+            Err(PgnError::InvalidSan {
+                san: "é1".to_string(),
+            }),
+        );
+        assert_eq!(output, expected);
+    }
+}
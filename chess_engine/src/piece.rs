@@ -2,8 +2,8 @@ use crate::{color::Color, error::PieceConversionError};
 
 use std::fmt::{self, write};
 
-use strum::EnumCount;
-use strum_macros::EnumCount as EnumCountMacro;
+use strum::{EnumCount, IntoEnumIterator};
+use strum_macros::{EnumCount as EnumCountMacro, EnumIter};
 
 // CONSTANTS:
 const PIECE_BIG: [bool; Piece::COUNT] = [
@@ -100,6 +100,18 @@ const PIECE_KING: [bool; Piece::COUNT] = [
     false, false, false, false, false, true, false, false, false, false, false, true,
 ];
 
+/// Allows us to know if a piece slides along diagonals (Bishops and Queens)
+const PIECE_DIAGONAL_SLIDER: [bool; Piece::COUNT] = [
+    // wp  wn     wb    wr     wq    wk     bp     bn     bb    br     bq    bk
+    false, false, true, false, true, false, false, false, true, false, true, false,
+];
+
+/// Allows us to know if a piece slides along ranks and files (Rooks and Queens)
+const PIECE_ORTHOGONAL_SLIDER: [bool; Piece::COUNT] = [
+    // wp  wn     wb     wr    wq    wk     bp     bn     bb     br    bq    bk
+    false, false, false, true, true, false, false, false, false, true, true, false,
+];
+
 /// For regular chess these are the max number of pieces that you could imagineably get per type
 const MAX_NUM_PIECES_ALLOWED: [u8; Piece::COUNT] = [
     //wp wn wb wr  wq wk bp bn  bb  br  bq bk
@@ -209,7 +221,7 @@ pub enum PieceType {
     King,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, EnumCountMacro)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, EnumCountMacro, EnumIter)]
 pub enum Piece {
     WhitePawn,
     WhiteKnight,
@@ -226,6 +238,11 @@ pub enum Piece {
 }
 
 impl Piece {
+    /// Every Piece, White's first, in the same order as the arrays indexed by Piece
+    pub fn all() -> impl Iterator<Item = Piece> {
+        Piece::iter()
+    }
+
     pub fn is_big(&self) -> bool {
         PIECE_BIG[*self as usize]
     }
@@ -269,6 +286,14 @@ impl Piece {
     pub fn is_king(&self) -> bool {
         PIECE_KING[*self as usize]
     }
+    /// Moves any number of squares along diagonals, i.e. in the BISHOP_DIRECTIONS
+    pub fn is_diagonal_slider(&self) -> bool {
+        PIECE_DIAGONAL_SLIDER[*self as usize]
+    }
+    /// Moves any number of squares along ranks and files, i.e. in the ROOK_DIRECTIONS
+    pub fn is_orthogonal_slider(&self) -> bool {
+        PIECE_ORTHOGONAL_SLIDER[*self as usize]
+    }
     pub fn get_piece_type(&self) -> PieceType {
         PIECE_TYPE[*self as usize]
     }
@@ -455,6 +480,74 @@ mod test {
         assert_eq!(output, expected);
     }
 
+    #[test]
+    fn test_piece_is_knight_all_pieces() {
+        let output: Vec<bool> = Piece::all().map(|piece| piece.is_knight()).collect();
+        #[rustfmt::skip]
+        let expected = vec![
+            false, true, false, false, false, false,
+            false, true, false, false, false, false,
+        ];
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_piece_is_rook_all_pieces() {
+        let output: Vec<bool> = Piece::all().map(|piece| piece.is_rook()).collect();
+        #[rustfmt::skip]
+        let expected = vec![
+            false, false, false, true, false, false,
+            false, false, false, true, false, false,
+        ];
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_piece_is_queen_all_pieces() {
+        let output: Vec<bool> = Piece::all().map(|piece| piece.is_queen()).collect();
+        #[rustfmt::skip]
+        let expected = vec![
+            false, false, false, false, true, false,
+            false, false, false, false, true, false,
+        ];
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_piece_is_diagonal_slider_all_pieces() {
+        let output: Vec<bool> = Piece::all()
+            .map(|piece| piece.is_diagonal_slider())
+            .collect();
+        #[rustfmt::skip]
+        let expected = vec![
+            false, false, true, false, true, false,
+            false, false, true, false, true, false,
+        ];
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_piece_is_orthogonal_slider_all_pieces() {
+        let output: Vec<bool> = Piece::all()
+            .map(|piece| piece.is_orthogonal_slider())
+            .collect();
+        #[rustfmt::skip]
+        let expected = vec![
+            false, false, false, true, true, false,
+            false, false, false, true, true, false,
+        ];
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_piece_sliders_are_diagonal_or_orthogonal() {
+        let output: Vec<bool> = Piece::all()
+            .map(|piece| piece.is_diagonal_slider() || piece.is_orthogonal_slider())
+            .collect();
+        let expected: Vec<bool> = Piece::all().map(|piece| piece.is_sliding()).collect();
+        assert_eq!(output, expected);
+    }
+
     #[test]
     fn test_piece_get_value() {
         let input = Piece::WhitePawn;
@@ -504,4 +597,12 @@ mod test {
         let expected = "♜".to_owned();
         assert_eq!(output, expected);
     }
+
+    //================================= ALL ===================================
+    #[test]
+    fn test_piece_all() {
+        let output = (Piece::all().count(), Piece::all().nth(6));
+        let expected = (12, Some(Piece::BlackPawn));
+        assert_eq!(output, expected);
+    }
 }
@@ -10,7 +10,7 @@ use std::fmt;
 // TODO: make builder for PositionKey to allow building it up in pieces if performance allows it
 
 /// Holds the Zobrist hashed key for the current Gamestate
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub struct PositionKey(pub u64);
 
 impl PositionKey {
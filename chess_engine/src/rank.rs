@@ -1,5 +1,6 @@
 use crate::{
-    board::{NUM_EXTERNAL_BOARD_SQUARES, NUM_INTERNAL_BOARD_SQUARES},
+    board::{bitboard::BitBoard, NUM_EXTERNAL_BOARD_SQUARES, NUM_INTERNAL_BOARD_SQUARES},
+    color::Color,
     error::RankConversionError,
 };
 use strum::{EnumCount, IntoEnumIterator};
@@ -45,6 +46,23 @@ pub enum Rank {
     Rank8,
 }
 
+impl Rank {
+    /// This rank as seen from the provided color's side of the board, so Rank1 is always that
+    /// color's back rank and a pawn's rank says how far it has advanced for either color
+    pub fn relative_to(&self, color: Color) -> Rank {
+        match color {
+            Color::White => *self,
+            Color::Black => Rank::try_from(Rank::COUNT - 1 - *self as usize)
+                .expect("mirrored rank should be in range 0..=7"),
+        }
+    }
+
+    /// Every square on this rank
+    pub fn mask(&self) -> BitBoard {
+        BitBoard::rank_mask(*self)
+    }
+}
+
 impl TryFrom<usize> for Rank {
     type Error = RankConversionError;
     fn try_from(value: usize) -> Result<Self, Self::Error> {
@@ -63,3 +81,26 @@ impl TryFrom<usize> for Rank {
 //         result
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rank_relative_to() {
+        let output = (
+            Rank::Rank2.relative_to(Color::White),
+            Rank::Rank2.relative_to(Color::Black),
+            Rank::Rank8.relative_to(Color::Black),
+        );
+        let expected = (Rank::Rank2, Rank::Rank7, Rank::Rank1);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_rank_mask() {
+        let output = Rank::Rank8.mask();
+        let expected = BitBoard(0xFF_00_00_00_00_00_00_00);
+        assert_eq!(output, expected);
+    }
+}
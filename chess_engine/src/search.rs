@@ -0,0 +1,775 @@
+use rand::{thread_rng, Rng, RngCore};
+use std::{
+    cmp::Reverse,
+    time::{Duration, Instant},
+};
+use strum::EnumCount;
+
+use crate::{
+    error::MoveGenError,
+    evaluation::{Evaluator, TaperedEvaluator},
+    gamestate::{Gamestate, ValidityCheck, HALF_MOVE_MAX},
+    moves::{Move, MoveList},
+    opening_book::OpeningBook,
+    square::Square64,
+};
+
+// CONSTANTS:
+/// Larger than any score the search can produce
+const INFINITY: i32 = 1_000_000;
+/// Score for checkmating at the root. Mates further away are scored closer to 0, see mate_in
+pub const MATE_SCORE: i32 = 100_000;
+const DRAW_SCORE: i32 = 0;
+/// Upper bound on how deep iterative deepening will go when searching on a time budget
+pub const MAX_SEARCH_DEPTH: usize = 64;
+/// How many nodes to visit between checks of the clock. Must be a power of 2
+const NODES_BETWEEN_TIME_CHECKS: u64 = 1024;
+/// Number of quiet moves remembered per ply by the killer heuristic
+const NUM_KILLERS: usize = 2;
+/// Once a history score passes this the whole table is halved, so long searches can't
+/// overflow it and older cutoffs slowly count for less
+const HISTORY_MAX: i32 = 1 << 20;
+
+/// Score for delivering checkmate ply half moves from the root. Shorter mates score higher so
+/// the search prefers them. Being mated is the negation.
+pub fn mate_in(ply: usize) -> i32 {
+    MATE_SCORE - ply as i32
+}
+
+/// Whether the score comes from a forced checkmate (for either side) rather than the evaluator
+pub fn is_mate_score(score: i32) -> bool {
+    score.abs() > MATE_SCORE - MAX_SEARCH_DEPTH as i32
+}
+
+/// Outcome of a search from the root position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchResult {
+    /// None if the side to move has no legal moves
+    pub best_move: Option<Move>,
+    /// Score in centipawns relative to the side to move
+    pub score: i32,
+    /// Deepest fully completed iteration
+    pub depth: usize,
+    pub nodes: u64,
+}
+
+/// Groups moves are ordered in, searched from the last to the first. Captures that lose
+/// material according to SEE go behind the quiet moves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum MoveClass {
+    LosingCapture,
+    Quiet,
+    Killer,
+    Tactical,
+}
+
+/// What the search learns about quiet moves while it runs, used to order them after the
+/// captures and promotions
+struct MoveOrdering {
+    /// Quiet moves that recently caused a beta cutoff at each ply, most recent first
+    killers: [[Option<Move>; NUM_KILLERS]; MAX_SEARCH_DEPTH],
+    /// Indexed by [start][end], bumped whenever a quiet move causes a beta cutoff. Deeper
+    /// cutoffs weigh more since they prune larger subtrees.
+    history: [[i32; Square64::COUNT]; Square64::COUNT],
+    /// Off only to compare against ordering by MVV-LVA alone
+    enabled: bool,
+}
+
+impl MoveOrdering {
+    fn new() -> Self {
+        MoveOrdering {
+            killers: [[None; NUM_KILLERS]; MAX_SEARCH_DEPTH],
+            history: [[0; Square64::COUNT]; Square64::COUNT],
+            enabled: true,
+        }
+    }
+
+    fn history_index(move_: Move) -> (usize, usize) {
+        let start = move_
+            .get_start()
+            .expect("generated moves should always have a valid start square");
+        let end = move_
+            .get_end()
+            .expect("generated moves should always have a valid end square");
+        (Square64::from(start) as usize, Square64::from(end) as usize)
+    }
+
+    fn record_cutoff(&mut self, move_: Move, ply: usize, depth: usize) {
+        if !self.enabled || !move_.is_quiet() {
+            return;
+        }
+        if let Some(killers) = self.killers.get_mut(ply) {
+            if killers[0] != Some(move_) {
+                killers.rotate_right(1);
+                killers[0] = Some(move_);
+            }
+        }
+        let (start, end) = Self::history_index(move_);
+        let score = &mut self.history[start][end];
+        *score = score.saturating_add((depth * depth) as i32);
+        if *score > HISTORY_MAX {
+            for score in self.history.iter_mut().flatten() {
+                *score /= 2;
+            }
+        }
+    }
+
+    /// Killers come first (the most recent one ahead), then the rest by history score
+    fn quiet_key(&self, move_: Move, ply: usize) -> (MoveClass, i32) {
+        let killers = self.killers.get(ply).copied().unwrap_or_default();
+        match killers.iter().position(|&killer| killer == Some(move_)) {
+            Some(slot) => (MoveClass::Killer, -(slot as i32)),
+            None => {
+                let (start, end) = Self::history_index(move_);
+                (MoveClass::Quiet, self.history[start][end])
+            }
+        }
+    }
+}
+
+struct Searcher<'a> {
+    nodes: u64,
+    deadline: Option<Instant>,
+    stopped: bool,
+    /// Root moves scoring within this many centipawns of the best move are picked from at random
+    jitter_cp: i32,
+    /// Picks between the near-best root moves. Only used when jitter_cp isn't 0
+    rng: &'a mut dyn RngCore,
+    evaluator: &'a dyn Evaluator,
+    ordering: MoveOrdering,
+}
+
+impl<'a> Searcher<'a> {
+    fn new(
+        deadline: Option<Instant>,
+        jitter_cp: i32,
+        rng: &'a mut dyn RngCore,
+        evaluator: &'a dyn Evaluator,
+    ) -> Self {
+        Searcher {
+            nodes: 0,
+            deadline,
+            stopped: false,
+            jitter_cp,
+            rng,
+            evaluator,
+            ordering: MoveOrdering::new(),
+        }
+    }
+
+    fn check_time(&mut self) {
+        if self.nodes & (NODES_BETWEEN_TIME_CHECKS - 1) == 0 {
+            if let Some(deadline) = self.deadline {
+                if Instant::now() >= deadline {
+                    self.stopped = true;
+                }
+            }
+        }
+    }
+
+    /// Search every root move to the given depth. Returns None if the search was
+    /// stopped before a single root move could be fully searched.
+    fn search_root(
+        &mut self,
+        gamestate: &mut Gamestate,
+        depth: usize,
+        previous_best: Option<Move>,
+    ) -> Result<Option<(Option<Move>, i32)>, MoveGenError> {
+        let mut moves = ordered_moves(gamestate, false, Some((&self.ordering, 0)))?;
+
+        if moves.is_empty() {
+            let score = match gamestate.is_in_check() {
+                true => -mate_in(0),
+                false => DRAW_SCORE,
+            };
+            return Ok(Some((None, score)));
+        }
+
+        // Search the best move of the previous iteration first
+        if let Some(previous_best) = previous_best {
+            if let Some(index) = moves.iter().position(|&m| m == previous_best) {
+                let move_ = moves.remove(index);
+                moves.insert(0, move_);
+            }
+        }
+
+        let beta = INFINITY;
+        let mut best_score = -INFINITY;
+        let mut candidates = Vec::new();
+
+        for move_ in moves {
+            // Widen the window by the jitter so that near-best moves get exact scores
+            let alpha = best_score.saturating_sub(self.jitter_cp).max(-INFINITY);
+
+            gamestate
+                .make_move_unchecked(move_)
+                .expect("move came from the legal move generator");
+            let score = -self.negamax(gamestate, depth - 1, 1, -beta, -alpha)?;
+            gamestate
+                .undo_move()
+                .expect("undoing a move that was just made should never fail");
+
+            if self.stopped {
+                break;
+            }
+
+            if score > alpha || candidates.is_empty() {
+                candidates.push((move_, score));
+            }
+            best_score = best_score.max(score);
+        }
+
+        if candidates.is_empty() {
+            return Ok(None);
+        }
+
+        let (best_move, score) = match self.jitter_cp {
+            0 => *candidates
+                .iter()
+                .find(|&&(_, score)| score == best_score)
+                .expect("best_score came from one of the candidates"),
+            _ => {
+                let near_best = candidates
+                    .into_iter()
+                    .filter(|&(_, score)| score >= best_score - self.jitter_cp)
+                    .collect::<Vec<_>>();
+                near_best[self.rng.gen_range(0..near_best.len())]
+            }
+        };
+
+        Ok(Some((Some(best_move), score)))
+    }
+
+    fn negamax(
+        &mut self,
+        gamestate: &mut Gamestate,
+        depth: usize,
+        ply: usize,
+        mut alpha: i32,
+        beta: i32,
+    ) -> Result<i32, MoveGenError> {
+        self.nodes += 1;
+        self.check_time();
+        if self.stopped {
+            return Ok(DRAW_SCORE);
+        }
+
+        if gamestate.halfmove_clock() >= HALF_MOVE_MAX {
+            return Ok(DRAW_SCORE);
+        }
+
+        if depth == 0 {
+            return self.quiescence(gamestate, alpha, beta);
+        }
+
+        let moves = ordered_moves(gamestate, false, Some((&self.ordering, ply)))?;
+        if moves.is_empty() {
+            return match gamestate.is_in_check() {
+                true => Ok(-mate_in(ply)),
+                false => Ok(DRAW_SCORE),
+            };
+        }
+
+        for move_ in moves {
+            gamestate
+                .make_move_unchecked(move_)
+                .expect("move came from the legal move generator");
+            let score = -self.negamax(gamestate, depth - 1, ply + 1, -beta, -alpha)?;
+            gamestate
+                .undo_move()
+                .expect("undoing a move that was just made should never fail");
+
+            if self.stopped {
+                return Ok(DRAW_SCORE);
+            }
+
+            if score >= beta {
+                self.ordering.record_cutoff(move_, ply, depth);
+                return Ok(beta);
+            }
+            if score > alpha {
+                alpha = score;
+            }
+        }
+
+        Ok(alpha)
+    }
+
+    /// Only look at captures so that we don't stop searching in the middle of an exchange
+    fn quiescence(
+        &mut self,
+        gamestate: &mut Gamestate,
+        mut alpha: i32,
+        beta: i32,
+    ) -> Result<i32, MoveGenError> {
+        self.nodes += 1;
+        self.check_time();
+        if self.stopped {
+            return Ok(DRAW_SCORE);
+        }
+
+        let stand_pat = self.evaluator.evaluate(gamestate);
+        if stand_pat >= beta {
+            return Ok(beta);
+        }
+        if stand_pat > alpha {
+            alpha = stand_pat;
+        }
+
+        for move_ in ordered_moves(gamestate, true, None)? {
+            gamestate
+                .make_move_unchecked(move_)
+                .expect("move came from the legal move generator");
+            let score = -self.quiescence(gamestate, -beta, -alpha)?;
+            gamestate
+                .undo_move()
+                .expect("undoing a move that was just made should never fail");
+
+            if self.stopped {
+                return Ok(DRAW_SCORE);
+            }
+
+            if score >= beta {
+                return Ok(beta);
+            }
+            if score > alpha {
+                alpha = score;
+            }
+        }
+
+        Ok(alpha)
+    }
+}
+
+/// Score a move for ordering purposes (Most Valuable Victim - Least Valuable Attacker).
+/// Promotions and captures are searched before quiet moves.
+fn mvv_lva(move_: Move) -> i32 {
+    if move_.is_quiet() {
+        return 0;
+    }
+
+    let mut score = 0;
+
+    if let Ok(Some(captured)) = move_.get_piece_captured() {
+        let attacker = move_
+            .get_piece_moved()
+            .expect("generated moves should always have a valid moved piece");
+        score += 10 * (captured.get_piece_type() as i32 + 1) - attacker.get_piece_type() as i32;
+    }
+    if let Ok(Some(promoted)) = move_.get_piece_promoted() {
+        score += promoted.get_piece_type() as i32;
+    }
+
+    score
+}
+
+/// Legal moves sorted so that the most promising ones are searched first. Captures that lose
+/// material according to SEE are searched last, or left out entirely when only looking at
+/// captures (unless in check, where every capture might be needed to get out of it).
+/// Quiet moves are ordered by the killer and history heuristics when quiet_ordering is given
+/// along with the current ply.
+fn ordered_moves(
+    gamestate: &mut Gamestate,
+    captures_only: bool,
+    quiet_ordering: Option<(&MoveOrdering, usize)>,
+) -> Result<Vec<Move>, MoveGenError> {
+    // NOTE: generating only evasions/captures up front saves making and undoing moves that
+    // would be thrown away anyway
+    let active_color = gamestate.active_color();
+    let in_check = gamestate.is_in_check();
+    let pseudo_legal_moves = if in_check {
+        gamestate.check_gamestate(ValidityCheck::Strict)?;
+        let mut move_list = MoveList::new();
+        gamestate.gen_evasions(active_color, &mut move_list);
+        move_list
+    } else if captures_only {
+        gamestate.check_gamestate(ValidityCheck::Strict)?;
+        let mut move_list = MoveList::new();
+        gamestate.gen_captures(active_color, &mut move_list);
+        move_list
+    } else {
+        gamestate.gen_move_list()?
+    };
+
+    let mut moves = Vec::new();
+    for move_ in pseudo_legal_moves.moves.into_iter().flatten() {
+        if captures_only && (!move_.is_capture() || (!in_check && gamestate.see(move_) < 0)) {
+            continue;
+        }
+        if gamestate.make_move_unchecked(move_).is_ok() {
+            gamestate
+                .undo_move()
+                .expect("undoing a move that was just made should never fail");
+            moves.push(move_);
+        }
+    }
+
+    // NOTE: sort_by_cached_key is stable so generation order is kept among equal scores
+    moves.sort_by_cached_key(|&move_| {
+        let key = match move_.is_quiet() {
+            true => quiet_ordering.map_or((MoveClass::Quiet, 0), |(ordering, ply)| {
+                ordering.quiet_key(move_, ply)
+            }),
+            false if move_.is_capture() && gamestate.see(move_) < 0 => {
+                (MoveClass::LosingCapture, mvv_lva(move_))
+            }
+            false => (MoveClass::Tactical, mvv_lva(move_)),
+        };
+        Reverse(key)
+    });
+    Ok(moves)
+}
+
+/// Search to a fixed depth and return the best move found
+pub fn search(gamestate: &mut Gamestate, depth: usize) -> Result<SearchResult, MoveGenError> {
+    search_with_evaluator(gamestate, depth, &TaperedEvaluator)
+}
+
+/// Same as search but scores positions with the provided Evaluator
+pub fn search_with_evaluator(
+    gamestate: &mut Gamestate,
+    depth: usize,
+    evaluator: &dyn Evaluator,
+) -> Result<SearchResult, MoveGenError> {
+    let depth = depth.max(1);
+    let mut rng = thread_rng();
+    let mut searcher = Searcher::new(None, 0, &mut rng, evaluator);
+    let (best_move, score) = searcher
+        .search_root(gamestate, depth, None)?
+        .expect("search without a deadline always completes");
+
+    Ok(SearchResult {
+        best_move,
+        score,
+        depth,
+        nodes: searcher.nodes,
+    })
+}
+
+/// Iterative deepening search that stops once the time budget is used up. Always
+/// completes at least a depth 1 search so a legal move is returned when one exists.
+pub fn search_timed(
+    gamestate: &mut Gamestate,
+    budget: Duration,
+) -> Result<SearchResult, MoveGenError> {
+    iterative_deepening(gamestate, budget, 0, &mut thread_rng(), &TaperedEvaluator)
+}
+
+/// Same as search_timed but scores positions with the provided Evaluator
+pub fn search_timed_with_evaluator(
+    gamestate: &mut Gamestate,
+    budget: Duration,
+    evaluator: &dyn Evaluator,
+) -> Result<SearchResult, MoveGenError> {
+    iterative_deepening(gamestate, budget, 0, &mut thread_rng(), evaluator)
+}
+
+/// Same as search_timed except that the move played is picked at random from the root
+/// moves scoring within jitter_cp centipawns of the best one, so that the engine doesn't
+/// play the identical game every time. Jitter deliberately weakens play, so use
+/// search_timed (or a jitter_cp of 0) when analysing positions.
+pub fn search_with_randomness(
+    gamestate: &mut Gamestate,
+    budget: Duration,
+    jitter_cp: i32,
+) -> Result<SearchResult, MoveGenError> {
+    iterative_deepening(
+        gamestate,
+        budget,
+        jitter_cp.max(0),
+        &mut thread_rng(),
+        &TaperedEvaluator,
+    )
+}
+
+/// Play a move from the opening book if the position is in it, otherwise fall back
+/// to search_with_randomness. A book move is reported with a depth of 0.
+pub fn search_with_book(
+    gamestate: &mut Gamestate,
+    book: &OpeningBook,
+    budget: Duration,
+    jitter_cp: i32,
+) -> Result<SearchResult, MoveGenError> {
+    match book.choose_move(gamestate) {
+        Some(book_move) => Ok(SearchResult {
+            best_move: Some(book_move),
+            score: DRAW_SCORE,
+            depth: 0,
+            nodes: 0,
+        }),
+        None => search_with_randomness(gamestate, budget, jitter_cp),
+    }
+}
+
+fn iterative_deepening(
+    gamestate: &mut Gamestate,
+    budget: Duration,
+    jitter_cp: i32,
+    rng: &mut dyn RngCore,
+    evaluator: &dyn Evaluator,
+) -> Result<SearchResult, MoveGenError> {
+    let start = Instant::now();
+    let mut searcher = Searcher::new(None, jitter_cp, rng, evaluator);
+    let (best_move, score) = searcher
+        .search_root(gamestate, 1, None)?
+        .expect("search without a deadline always completes");
+    let mut result = SearchResult {
+        best_move,
+        score,
+        depth: 1,
+        nodes: searcher.nodes,
+    };
+    let mut searcher = Searcher::new(Some(start + budget), jitter_cp, rng, evaluator);
+
+    for depth in 2..=MAX_SEARCH_DEPTH {
+        // A deeper iteration takes several times longer than the last one, so don't
+        // start one we are unlikely to finish
+        if result.best_move.is_none() || start.elapsed() >= budget / 2 {
+            break;
+        }
+
+        match searcher.search_root(gamestate, depth, result.best_move)? {
+            Some((best_move, score)) if !searcher.stopped => {
+                result = SearchResult {
+                    best_move,
+                    score,
+                    depth,
+                    nodes: result.nodes + searcher.nodes,
+                };
+                searcher.nodes = 0;
+            }
+            _ => break,
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        evaluation::MaterialEvaluator, gamestate::DEFAULT_FEN, piece::Piece, square::Square,
+    };
+    use rand::SeedableRng;
+    use rand_pcg::Lcg128Xsl64;
+
+    #[test]
+    fn test_search_finds_mate_in_one() {
+        // Back rank mate: Ra1-a8#
+        let mut gamestate = Gamestate::try_from("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let output = search(&mut gamestate, 2).unwrap().best_move;
+        let expected = Some(Move::new(
+            Square::A1,
+            Square::A8,
+            None,
+            false,
+            false,
+            None,
+            false,
+            Piece::WhiteRook,
+        ));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_search_prefers_faster_mate() {
+        // Rh1-h8 mates right away, the search must not settle for a slower mate
+        let mut gamestate = Gamestate::try_from("2k5/8/2K5/8/8/8/8/7R w - - 0 1").unwrap();
+        let result = search(&mut gamestate, 4).unwrap();
+        let output = (result.best_move.map(|move_| move_.to_uci()), result.score);
+        let expected = (Some("h1h8".to_owned()), mate_in(1));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_search_scores_mate_in_two() {
+        // Kc6-b6 and Rh1-h8 next move
+        let mut gamestate = Gamestate::try_from("k7/8/2K5/8/8/8/8/7R w - - 0 1").unwrap();
+        let output = search(&mut gamestate, 4).unwrap().score;
+        let expected = mate_in(3);
+        assert_eq!(output, expected);
+        assert!(mate_in(1) > output);
+    }
+
+    #[test]
+    fn test_search_mated_score() {
+        // Black has been back rank mated
+        let mut gamestate = Gamestate::try_from("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1").unwrap();
+        let output = search(&mut gamestate, 2).unwrap().score;
+        let expected = -mate_in(0);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_search_is_mate_score() {
+        let output = [mate_in(3), -mate_in(5), 900, -900, DRAW_SCORE].map(is_mate_score);
+        let expected = [true, true, false, false, false];
+        assert_eq!(output, expected);
+    }
+
+    /// Root score and node count of a fixed depth search, with or without the killer and
+    /// history heuristics
+    fn search_nodes(fen: &str, depth: usize, ordering_enabled: bool) -> (i32, u64) {
+        let mut gamestate = Gamestate::try_from(fen).unwrap();
+        let mut rng = thread_rng();
+        let mut searcher = Searcher::new(None, 0, &mut rng, &TaperedEvaluator);
+        searcher.ordering.enabled = ordering_enabled;
+        let (_, score) = searcher
+            .search_root(&mut gamestate, depth, None)
+            .unwrap()
+            .unwrap();
+        (score, searcher.nodes)
+    }
+
+    #[test]
+    fn test_search_killer_and_history_reduce_nodes() {
+        // Rd1-d8 mates, and the same quiet moves keep refuting Black's replies across the tree
+        let fen = "6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1";
+        let (score, nodes) = search_nodes(fen, 4, true);
+        let (mvv_lva_score, mvv_lva_nodes) = search_nodes(fen, 4, false);
+        assert_eq!(score, mvv_lva_score);
+        assert!(nodes < mvv_lva_nodes);
+    }
+
+    #[test]
+    fn test_search_history_stays_below_max() {
+        let mut ordering = MoveOrdering::new();
+        let move_ = Move::new(
+            Square::G1,
+            Square::F3,
+            None,
+            false,
+            false,
+            None,
+            false,
+            Piece::WhiteKnight,
+        );
+        for _ in 0..1000 {
+            ordering.record_cutoff(move_, 0, MAX_SEARCH_DEPTH);
+        }
+        let (start, end) = MoveOrdering::history_index(move_);
+        let output = ordering.history[start][end];
+        assert!(0 < output && output <= HISTORY_MAX, "{output}");
+    }
+
+    #[test]
+    fn test_search_takes_hanging_queen() {
+        let mut gamestate = Gamestate::try_from("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1").unwrap();
+        let output = search(&mut gamestate, 2).unwrap().best_move;
+        let expected = Some(Move::new(
+            Square::D2,
+            Square::D5,
+            Some(Piece::BlackQueen),
+            false,
+            false,
+            None,
+            false,
+            Piece::WhiteRook,
+        ));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_search_with_evaluator_uses_its_scores() {
+        // Only material counts, so after winning the Queen White is up exactly a Rook
+        let mut gamestate = Gamestate::try_from("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1").unwrap();
+        let output = search_with_evaluator(&mut gamestate, 2, &MaterialEvaluator)
+            .unwrap()
+            .score;
+        let expected = Piece::WhiteRook.get_value() as i32;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_search_no_legal_moves() {
+        // Black is stalemated
+        let mut gamestate = Gamestate::try_from("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1").unwrap();
+        let output = search(&mut gamestate, 3).unwrap();
+        assert_eq!(output.best_move, None);
+        assert_eq!(output.score, DRAW_SCORE);
+    }
+
+    #[test]
+    fn test_search_timed_returns_legal_move() {
+        let mut gamestate = Gamestate::default();
+        let output = search_timed(&mut gamestate, Duration::from_millis(50))
+            .unwrap()
+            .best_move
+            .unwrap();
+        let legal_moves = gamestate.gen_legal_moves().unwrap();
+        assert!(legal_moves.moves.into_iter().flatten().any(|m| m == output));
+    }
+
+    /// Best moves of repeated fixed depth searches that pick between near-best root moves with
+    /// an rng seeded from seed
+    fn jittered_best_moves(fen: &str, depth: usize, jitter_cp: i32, seed: u64) -> Vec<Move> {
+        let mut gamestate = Gamestate::try_from(fen).unwrap();
+        let mut rng = Lcg128Xsl64::seed_from_u64(seed);
+        (0..10)
+            .map(|_| {
+                Searcher::new(None, jitter_cp, &mut rng, &TaperedEvaluator)
+                    .search_root(&mut gamestate, depth, None)
+                    .unwrap()
+                    .unwrap()
+                    .0
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_search_with_randomness_varies_between_equal_moves() {
+        // Every first move is within the jitter window of the best one
+        let moves = jittered_best_moves(DEFAULT_FEN, 2, 1000, 7);
+        assert!(moves.iter().any(|&move_| move_ != moves[0]));
+
+        let output = jittered_best_moves(DEFAULT_FEN, 2, 1000, 7);
+        let expected = moves;
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_search_with_randomness_still_takes_hanging_queen() {
+        let mut gamestate = Gamestate::try_from("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1").unwrap();
+        let output = search_with_randomness(&mut gamestate, Duration::from_millis(20), 20)
+            .unwrap()
+            .best_move;
+        let expected = Some(Move::new(
+            Square::D2,
+            Square::D5,
+            Some(Piece::BlackQueen),
+            false,
+            false,
+            None,
+            false,
+            Piece::WhiteRook,
+        ));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_search_with_book_plays_book_move() {
+        let mut book = OpeningBook::new();
+        book.add_pgn("1. a3 a6 *", 2).unwrap();
+
+        let mut gamestate = Gamestate::default();
+        let output = search_with_book(&mut gamestate, &book, Duration::from_millis(20), 0).unwrap();
+        let expected = SearchResult {
+            best_move: Some(Move::new(
+                Square::A2,
+                Square::A3,
+                None,
+                false,
+                false,
+                None,
+                false,
+                Piece::WhitePawn,
+            )),
+            score: DRAW_SCORE,
+            depth: 0,
+            nodes: 0,
+        };
+        assert_eq!(output, expected);
+    }
+}
@@ -106,6 +106,11 @@ impl TryFrom<usize> for Square64 {
 }
 
 impl Square64 {
+    /// Every Square64 from A1 to H8, going along each Rank before moving up to the next
+    pub fn all() -> impl Iterator<Item = Square64> {
+        Square64::iter()
+    }
+
     pub fn from_file_and_rank(file: File, rank: Rank) -> Self {
         let index_64 = (file as u8) + (rank as u8) * 8;
         index_64.try_into().expect(
@@ -137,6 +142,21 @@ impl Square64 {
         }
     }
 
+    /// Parse a list of squares like "e2 e4, D7". Squares can be separated by whitespace
+    /// and/or commas and are case insensitive
+    pub fn parse_squares(s: &str) -> Result<Vec<Square64>, SquareConversionError> {
+        s.split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|token| !token.is_empty())
+            .map(|token| {
+                Square64::from_str(&token.to_uppercase()).map_err(|_| {
+                    SquareConversionError::FromListToken {
+                        token: token.to_string(),
+                    }
+                })
+            })
+            .collect()
+    }
+
     /// Get the chess/kings distance between two squares
     pub fn get_chebyshev_distance(square_1: Square64, square_2: Square64) -> u8 {
         // https://www.youtube.com/watch?v=bfV4XhpzpBE&t=178s
@@ -160,7 +180,9 @@ impl Square64 {
     }
 }
 
-#[derive(Display, Debug, Clone, Copy, PartialEq, Eq, EnumIter, EnumString, EnumCountMacro, PartialOrd, Ord)]
+/// Square on the internal 10x12 board. Display and TryFrom<&str> use uppercase names
+/// (e.g. "E4"), use from_algebraic/to_algebraic for lowercase FEN/UCI style names.
+#[derive(Display, Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, EnumString, EnumCountMacro, PartialOrd, Ord)]
 #[rustfmt::skip]
 #[strum(use_phf)]
 pub enum Square {
@@ -236,6 +258,16 @@ impl TryFrom<usize> for Square {
 }
 
 impl Square {
+    /// Parse a square name in algebraic notation, e.g. "e4". Case insensitive
+    pub fn from_algebraic(s: &str) -> Result<Self, SquareConversionError> {
+        Ok(Square::from_str(&s.to_uppercase())?)
+    }
+
+    /// Lowercase square name in algebraic notation as used in FEN, SAN and UCI, e.g. "e4"
+    pub fn to_algebraic(&self) -> String {
+        self.to_string().to_lowercase()
+    }
+
     pub fn from_file_and_rank(file: File, rank: Rank) -> Self {
         let index_120 = (21 + (file as u8) + (10 * (rank as u8)));
         index_120.try_into().expect(
@@ -294,6 +326,18 @@ impl Square {
 mod tests {
     use super::*;
 
+    //================================= ALL ===================================
+    #[test]
+    fn test_square64_all() {
+        let output = (
+            Square64::all().count(),
+            Square64::all().next(),
+            Square64::all().last(),
+        );
+        let expected = (64, Some(Square64::A1), Some(Square64::H8));
+        assert_eq!(output, expected);
+    }
+
     //============================== Chebychev Distance ============================
     #[test]
     fn test_board_chebychev_distance_visual() {
@@ -473,6 +517,40 @@ mod tests {
         assert_eq!(output, expected);
     }
 
+    #[test]
+    fn test_square_120_algebraic_round_trip() {
+        for square in Square::iter() {
+            let output = Square::from_algebraic(&square.to_algebraic());
+            let expected = Ok(square);
+            assert_eq!(output, expected);
+        }
+    }
+
+    #[test]
+    fn test_square_120_from_algebraic_case_insensitive() {
+        let output = (Square::from_algebraic("d2"), Square::from_algebraic("D2"));
+        let expected = (Ok(Square::D2), Ok(Square::D2));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_square_120_from_algebraic_off_board() {
+        for input in ["i1", "a0", "a9", "", "e44"] {
+            let output = Square::from_algebraic(input);
+            let expected = Err(SquareConversionError::FromStr(
+                strum::ParseError::VariantNotFound,
+            ));
+            assert_eq!(output, expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_square_120_to_algebraic() {
+        let output = Square::D2.to_algebraic();
+        let expected = "d2".to_owned();
+        assert_eq!(output, expected);
+    }
+
     #[test]
     fn test_square_64_try_from_str_valid() {
         let input = "D2";
@@ -501,6 +579,30 @@ mod tests {
         assert_eq!(output, expected);
     }
 
+    #[test]
+    fn test_square_64_parse_squares_mixed_case_and_separators() {
+        let input = "e2 E4,d7 ,  h8\ta1";
+        let output = Square64::parse_squares(input);
+        let expected = Ok(vec![
+            Square64::E2,
+            Square64::E4,
+            Square64::D7,
+            Square64::H8,
+            Square64::A1,
+        ]);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_square_64_parse_squares_invalid_token() {
+        let input = "e2 e9 d7";
+        let output = Square64::parse_squares(input);
+        let expected = Err(SquareConversionError::FromListToken {
+            token: "e9".to_string(),
+        });
+        assert_eq!(output, expected);
+    }
+
     #[test]
     fn test_square_64_to_string() {
         let input = Square64::D2;
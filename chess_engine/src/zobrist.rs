@@ -1,6 +1,8 @@
 use crate::{
-    board::NUM_EXTERNAL_BOARD_SQUARES, castle_perm::NUM_CASTLE_PERM, file::File, piece::Piece,
+    board::NUM_EXTERNAL_BOARD_SQUARES, castle_perm::NUM_CASTLE_PERM, error::ZobristKeysError,
+    file::File, piece::Piece,
 };
+use nanoserde::{DeBin, SerBin};
 use strum::EnumCount;
 
 use rand::prelude::*;
@@ -81,6 +83,78 @@ impl Zobrist {
     }
 }
 
+/// The random tables of a Zobrist in a form that can be saved and loaded again (it's
+/// SerBin/DeBin) or filled in from another tool's keys, e.g. to match the keys of an
+/// external opening book. piece_keys holds the 64 keys of each Piece one after the other.
+#[derive(Debug, Clone, PartialEq, Eq, SerBin, DeBin)]
+pub struct ZobristKeys {
+    pub color_key: u64,
+    pub piece_keys: Vec<u64>,
+    pub en_passant_keys: Vec<u64>,
+    pub castle_keys: Vec<u64>,
+}
+
+impl Zobrist {
+    pub fn export_keys(&self) -> ZobristKeys {
+        ZobristKeys {
+            color_key: self.color_key,
+            piece_keys: self.piece_keys.concat(),
+            en_passant_keys: self.en_passant_keys.to_vec(),
+            castle_keys: self.castle_keys.to_vec(),
+        }
+    }
+
+    /// Fails if any of the tables doesn't have exactly as many keys as Zobrist needs
+    pub fn from_keys(keys: &ZobristKeys) -> Result<Self, ZobristKeysError> {
+        let wrong_num_keys = |table, expected, found| ZobristKeysError::WrongNumKeys {
+            table,
+            expected,
+            found,
+        };
+
+        let mut piece_keys = [[0u64; NUM_EXTERNAL_BOARD_SQUARES]; Piece::COUNT];
+        if keys.piece_keys.len() != Piece::COUNT * NUM_EXTERNAL_BOARD_SQUARES {
+            return Err(wrong_num_keys(
+                "piece",
+                Piece::COUNT * NUM_EXTERNAL_BOARD_SQUARES,
+                keys.piece_keys.len(),
+            ));
+        }
+        for (square_array, chunk) in piece_keys
+            .iter_mut()
+            .zip(keys.piece_keys.chunks(NUM_EXTERNAL_BOARD_SQUARES))
+        {
+            square_array.copy_from_slice(chunk);
+        }
+        let en_passant_keys =
+            keys.en_passant_keys.as_slice().try_into().map_err(|_| {
+                wrong_num_keys("en passant", File::COUNT, keys.en_passant_keys.len())
+            })?;
+        let castle_keys = keys
+            .castle_keys
+            .as_slice()
+            .try_into()
+            .map_err(|_| wrong_num_keys("castle", NUM_CASTLE_PERM, keys.castle_keys.len()))?;
+
+        Ok(Zobrist {
+            color_key: keys.color_key,
+            piece_keys,
+            en_passant_keys,
+            castle_keys,
+        })
+    }
+
+    /// Make these the keys every Gamestate hashes its position with from now on, in place of
+    /// the default ones from ZOBRIST_SEED.
+    /// NOTE: Gamestates that already exist keep position keys made with the old keys, so they
+    /// have to be rebuilt (e.g. with reset_from_fen) before being compared with new ones.
+    pub fn install(self) {
+        *ZOBRIST
+            .lock()
+            .expect("Mutex holding ZOBRIST should not be poisoned") = self;
+    }
+}
+
 impl Default for Zobrist {
     fn default() -> Self {
         Self::new()
@@ -90,6 +164,44 @@ impl Default for Zobrist {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::gamestate::Gamestate;
+
+    #[test]
+    fn test_zobrist_keys_round_trip() {
+        let zobrist = Zobrist::default();
+        let keys = zobrist.export_keys();
+        let bytes = keys.serialize_bin();
+        let output = Zobrist::from_keys(&ZobristKeys::deserialize_bin(&bytes).unwrap());
+        let expected = Ok(zobrist);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_zobrist_install_imported_keys_keeps_position_keys() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let expected = Gamestate::try_from(fen).unwrap().position_key();
+
+        // NOTE: the imported keys are the ones already installed, so other tests running at
+        // the same time are not affected
+        let keys = ZOBRIST.lock().unwrap().export_keys();
+        Zobrist::from_keys(&keys).unwrap().install();
+        let output = Gamestate::try_from(fen).unwrap().position_key();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_zobrist_from_keys_wrong_num_keys() {
+        let mut keys = Zobrist::default().export_keys();
+        keys.castle_keys.pop();
+        let output = Zobrist::from_keys(&keys);
+        let expected = Err(ZobristKeysError::WrongNumKeys {
+            table: "castle",
+            expected: NUM_CASTLE_PERM,
+            found: NUM_CASTLE_PERM - 1,
+        });
+        assert_eq!(output, expected);
+    }
+
     #[test]
     fn test_zobrist_visual() {
         let color_key = ZOBRIST.lock().unwrap().color_key;
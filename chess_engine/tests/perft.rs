@@ -76,7 +76,7 @@ fn perft(gamestate: &mut Gamestate, depth: usize, leaf_count: &mut u64) -> Resul
         // Recursive Case
         let move_list = gamestate.gen_move_list()?;
         for move_ in move_list.moves.into_iter().flatten() {
-            if gamestate.make_move(move_).is_ok() {
+            if gamestate.make_move_unchecked(move_).is_ok() {
                 perft(gamestate, depth - 1, leaf_count)?;
                 gamestate.undo_move()?;
             }
@@ -96,7 +96,7 @@ fn divided_perft(gamestate: &mut Gamestate, depth: usize) -> Result<u64, PerftEr
 
     let move_list = gamestate.gen_move_list()?;
     for (move_idx, move_) in move_list.moves.into_iter().flatten().enumerate() {
-        if gamestate.make_move(move_).is_ok() {
+        if gamestate.make_move_unchecked(move_).is_ok() {
             let total_count = leaf_count;
 
             perft(gamestate, depth - 1, &mut leaf_count)?;
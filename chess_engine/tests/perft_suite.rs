@@ -0,0 +1,38 @@
+use chess_engine::gamestate::Gamestate;
+
+const PERFT_SUITE: &str = include_str!("data/perft_depth_3.txt");
+const PERFT_SUITE_DEPTH: usize = 3;
+
+/// Parse the suite into (FEN, expected node count) pairs, skipping comments and blank lines
+fn parse_suite(suite: &str) -> Vec<(&str, u64)> {
+    suite
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let (fen, node_count) = line
+                .split_once(';')
+                .unwrap_or_else(|| panic!("missing node count in line: {line}"));
+            let node_count = node_count
+                .trim()
+                .strip_prefix("D3 ")
+                .and_then(|node_count| node_count.parse().ok())
+                .unwrap_or_else(|| panic!("invalid node count in line: {line}"));
+            (fen.trim(), node_count)
+        })
+        .collect()
+}
+
+#[test]
+fn test_perft_suite_depth_3() {
+    let suite = parse_suite(PERFT_SUITE);
+    assert!(!suite.is_empty());
+
+    for (fen, expected) in suite {
+        let mut gamestate = Gamestate::try_from(fen).unwrap();
+        let output = gamestate.perft(PERFT_SUITE_DEPTH);
+        assert_eq!(output, expected, "{fen}");
+        // perft has to leave the position as it found it
+        assert_eq!(gamestate.to_fen(), fen);
+    }
+}
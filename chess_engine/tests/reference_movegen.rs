@@ -0,0 +1,294 @@
+use chess_engine::{
+    castle_perm::Castle,
+    color::Color,
+    gamestate::Gamestate,
+    moves::Move,
+    piece::{Piece, PieceType, BLACK_PAWN_PROMOTION_TARGETS, WHITE_PAWN_PROMOTION_TARGETS},
+    square::{Square, Square64},
+};
+use rand::SeedableRng;
+use rand_pcg::Lcg128Xsl64;
+use strum::IntoEnumIterator;
+
+/// Positions the random games start from, picked for castling, en passant and promotions
+const SEED_FENS: [&str; 6] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+];
+const GAMES_PER_FEN: usize = 2;
+const PLIES_PER_GAME: usize = 40;
+
+// The reference generator below deliberately shares nothing with the engine's move generation
+// except make_move, which is only used to reject moves that leave the King in check. Squares
+// are handled as (file, rank) pairs from 0 to 7 instead of the engine's padded board.
+
+fn coords(square: Square64) -> (i8, i8) {
+    (square.get_file() as i8, square.get_rank() as i8)
+}
+
+fn square_at(file: i8, rank: i8) -> Square64 {
+    Square64::try_from((file + rank * 8) as usize).expect("coordinates should be on the board")
+}
+
+fn piece_at(gamestate: &Gamestate, square: Square64) -> Option<Piece> {
+    gamestate.board().pieces[Square::from(square) as usize]
+}
+
+/// Whether every square strictly between start and end is empty. start and end have to share
+/// a rank, file or diagonal.
+fn is_path_clear(gamestate: &Gamestate, start: Square64, end: Square64) -> bool {
+    let (start_file, start_rank) = coords(start);
+    let (end_file, end_rank) = coords(end);
+    let file_step = (end_file - start_file).signum();
+    let rank_step = (end_rank - start_rank).signum();
+
+    let (mut file, mut rank) = (start_file + file_step, start_rank + rank_step);
+    while (file, rank) != (end_file, end_rank) {
+        if piece_at(gamestate, square_at(file, rank)).is_some() {
+            return false;
+        }
+        file += file_step;
+        rank += rank_step;
+    }
+    true
+}
+
+/// Whether the move is made without leaving the mover's King in check
+/// NOTE: make_move checks against gen_legal_moves, the very thing being tested
+fn is_legal(gamestate: &mut Gamestate, move_: Move) -> bool {
+    match gamestate.make_move_unchecked(move_) {
+        Ok(()) => {
+            gamestate.undo_move().unwrap();
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+fn castle_candidate(gamestate: &mut Gamestate, start: Square64, end: Square64) -> Option<Move> {
+    let king = piece_at(gamestate, start)?;
+    let color = king.get_color();
+    let ((start_file, start_rank), (end_file, end_rank)) = (coords(start), coords(end));
+    let home_rank = match color {
+        Color::White => 0,
+        Color::Black => 7,
+    };
+    if (start_file, start_rank) != (4, home_rank) || end_rank != home_rank {
+        return None;
+    }
+
+    let (castle, rook_file) = match (color, end_file) {
+        (Color::White, 6) => (Castle::WhiteKing, 7),
+        (Color::White, 2) => (Castle::WhiteQueen, 0),
+        (Color::Black, 6) => (Castle::BlackKing, 7),
+        (Color::Black, 2) => (Castle::BlackQueen, 0),
+        _ => return None,
+    };
+    let rook_square = square_at(rook_file, home_rank);
+    let has_rook = piece_at(gamestate, rook_square).is_some_and(|piece| {
+        piece.get_piece_type() == PieceType::Rook && piece.get_color() == color
+    });
+    if gamestate.castle_permissions().0 & castle as u8 == 0
+        || !has_rook
+        || !is_path_clear(gamestate, start, rook_square)
+        || gamestate.is_in_check()
+    {
+        return None;
+    }
+
+    // the King can't pass through an attacked square either
+    let passed_square = square_at((start_file + end_file) / 2, home_rank);
+    let step = Move::new(
+        start.into(),
+        passed_square.into(),
+        None,
+        false,
+        false,
+        None,
+        false,
+        king,
+    );
+    if !is_legal(gamestate, step) {
+        return None;
+    }
+    Some(Move::new(
+        start.into(),
+        end.into(),
+        None,
+        false,
+        false,
+        None,
+        true,
+        king,
+    ))
+}
+
+fn pawn_candidates(gamestate: &Gamestate, start: Square64, end: Square64) -> Vec<Move> {
+    let pawn = piece_at(gamestate, start).expect("start should hold the pawn");
+    let captured = piece_at(gamestate, end);
+    let ((start_file, start_rank), (end_file, end_rank)) = (coords(start), coords(end));
+    let (forward, start_rank_expected, promotion_rank, promotion_targets) = match pawn.get_color() {
+        Color::White => (1, 1, 7, WHITE_PAWN_PROMOTION_TARGETS),
+        Color::Black => (-1, 6, 0, BLACK_PAWN_PROMOTION_TARGETS),
+    };
+    let (file_delta, rank_delta) = (end_file - start_file, end_rank - start_rank);
+
+    let mut en_passant = false;
+    let mut pawn_start = false;
+    let mut captured_piece = None;
+    match (file_delta.abs(), rank_delta) {
+        (0, rank_delta) if rank_delta == forward && captured.is_none() => {}
+        (0, rank_delta)
+            if rank_delta == 2 * forward
+                && start_rank == start_rank_expected
+                && captured.is_none()
+                && is_path_clear(gamestate, start, end) =>
+        {
+            pawn_start = true;
+        }
+        (1, rank_delta) if rank_delta == forward && captured.is_some() => {
+            captured_piece = captured;
+        }
+        (1, rank_delta) if rank_delta == forward && gamestate.en_passant() == Some(end) => {
+            en_passant = true;
+            captured_piece = Some(match pawn.get_color() {
+                Color::White => Piece::BlackPawn,
+                Color::Black => Piece::WhitePawn,
+            });
+        }
+        _ => return vec![],
+    }
+
+    let new_move = |piece_promoted| {
+        Move::new(
+            start.into(),
+            end.into(),
+            captured_piece,
+            en_passant,
+            pawn_start,
+            piece_promoted,
+            false,
+            pawn,
+        )
+    };
+    match end_rank == promotion_rank {
+        true => promotion_targets
+            .into_iter()
+            .map(|piece| new_move(Some(piece)))
+            .collect(),
+        false => vec![new_move(None)],
+    }
+}
+
+/// Every move that follows how the piece on start moves, ignoring whether the King is left
+/// in check
+fn candidate_moves(gamestate: &mut Gamestate, start: Square64, end: Square64) -> Vec<Move> {
+    let Some(piece) = piece_at(gamestate, start) else {
+        return vec![];
+    };
+    let captured = piece_at(gamestate, end);
+    if captured.is_some_and(|captured| captured.get_color() == piece.get_color()) {
+        return vec![];
+    }
+
+    let ((start_file, start_rank), (end_file, end_rank)) = (coords(start), coords(end));
+    let (file_distance, rank_distance) =
+        ((end_file - start_file).abs(), (end_rank - start_rank).abs());
+    let is_diagonal = file_distance == rank_distance;
+    let is_orthogonal = file_distance == 0 || rank_distance == 0;
+    let follows_rules = match piece.get_piece_type() {
+        PieceType::Pawn => return pawn_candidates(gamestate, start, end),
+        PieceType::King if file_distance == 2 && rank_distance == 0 => {
+            return castle_candidate(gamestate, start, end)
+                .into_iter()
+                .collect();
+        }
+        PieceType::King => file_distance.max(rank_distance) == 1,
+        PieceType::Knight => {
+            (file_distance, rank_distance) == (1, 2) || (file_distance, rank_distance) == (2, 1)
+        }
+        PieceType::Bishop => is_diagonal && is_path_clear(gamestate, start, end),
+        PieceType::Rook => is_orthogonal && is_path_clear(gamestate, start, end),
+        PieceType::Queen => (is_diagonal || is_orthogonal) && is_path_clear(gamestate, start, end),
+    };
+
+    match follows_rules {
+        true => vec![Move::new(
+            start.into(),
+            end.into(),
+            captured,
+            false,
+            false,
+            None,
+            false,
+            piece,
+        )],
+        false => vec![],
+    }
+}
+
+/// Try every piece of the active color on every square of the board
+fn reference_legal_moves(gamestate: &mut Gamestate) -> Vec<Move> {
+    let mut legal_moves = vec![];
+    for start in Square64::iter() {
+        let is_active = piece_at(gamestate, start)
+            .is_some_and(|piece| piece.get_color() == gamestate.active_color());
+        if !is_active {
+            continue;
+        }
+        for end in Square64::iter().filter(|end| *end != start) {
+            for move_ in candidate_moves(gamestate, start, end) {
+                if is_legal(gamestate, move_) {
+                    legal_moves.push(move_);
+                }
+            }
+        }
+    }
+    legal_moves.sort();
+    legal_moves
+}
+
+fn fast_legal_moves(gamestate: &mut Gamestate) -> Vec<Move> {
+    let mut legal_moves: Vec<Move> = gamestate
+        .gen_legal_moves()
+        .unwrap()
+        .moves
+        .into_iter()
+        .flatten()
+        .collect();
+    legal_moves.sort();
+    legal_moves
+}
+
+fn to_uci(moves: &[Move]) -> Vec<String> {
+    moves.iter().map(Move::to_uci).collect()
+}
+
+#[test]
+fn test_gen_legal_moves_matches_reference() {
+    let mut rng = Lcg128Xsl64::seed_from_u64(0x5eed);
+    let mut positions_checked = 0;
+
+    for fen in SEED_FENS {
+        for _ in 0..GAMES_PER_FEN {
+            let mut gamestate = Gamestate::try_from(fen).unwrap();
+            for _ in 0..PLIES_PER_GAME {
+                let output = fast_legal_moves(&mut gamestate);
+                let expected = reference_legal_moves(&mut gamestate);
+                assert_eq!(to_uci(&output), to_uci(&expected), "{}", gamestate.to_fen());
+                assert_eq!(output, expected, "{}", gamestate.to_fen());
+                positions_checked += 1;
+
+                match gamestate.random_legal_move(&mut rng) {
+                    Some(move_) => gamestate.make_move(move_).unwrap(),
+                    None => break,
+                }
+            }
+        }
+    }
+    assert!(positions_checked > SEED_FENS.len());
+}
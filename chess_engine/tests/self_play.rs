@@ -0,0 +1,113 @@
+use chess_engine::{
+    color::Color,
+    evaluation::{Evaluator, MaterialEvaluator, TaperedEvaluator},
+    gamestate::{GameResult, Gamestate, MAX_GAME_MOVES},
+    search::search_with_evaluator,
+};
+
+/// Shallow enough that full games stay quick in debug builds
+const SELF_PLAY_DEPTH: usize = 2;
+/// Length of the game played on every test run, the full games are ignored by default
+const SHORT_GAME_PLIES: usize = 16;
+
+/// Play a game from the starting position, each side searching with its own Evaluator,
+/// until status reports a result, MAX_GAME_MOVES full moves have been played or max_plies
+/// moves have been made. Returns None if a cap was reached first.
+/// Every move is also undone and replayed to check that make_move and undo_move agree.
+fn play_game(
+    white: &dyn Evaluator,
+    black: &dyn Evaluator,
+    depth: usize,
+    max_plies: usize,
+) -> Option<GameResult> {
+    let mut gamestate = Gamestate::starting_position();
+    let mut moves_played = vec![];
+
+    let result = loop {
+        if let Some(result) = gamestate.status().unwrap() {
+            break Some(result);
+        }
+        if gamestate.fullmove_count() >= MAX_GAME_MOVES || moves_played.len() >= max_plies {
+            break None;
+        }
+
+        let evaluator = match gamestate.active_color() {
+            Color::White => white,
+            Color::Black => black,
+        };
+        let best_move = search_with_evaluator(&mut gamestate, depth, evaluator)
+            .unwrap()
+            .best_move
+            .unwrap_or_else(|| {
+                panic!(
+                    "search found no move in an unfinished game: {}",
+                    gamestate.to_fen()
+                )
+            });
+
+        let fen_before = gamestate.to_fen();
+        gamestate.make_move(best_move).unwrap();
+        let fen_after = gamestate.to_fen();
+        gamestate.undo_move().unwrap();
+        assert_eq!(
+            gamestate.to_fen(),
+            fen_before,
+            "undoing {} changed the position",
+            best_move.to_uci()
+        );
+        gamestate.make_move(best_move).unwrap();
+        assert_eq!(gamestate.to_fen(), fen_after);
+        moves_played.push(best_move.to_uci());
+    };
+
+    // the declared result has to match what the final position says on its own
+    let output = Gamestate::try_from(gamestate.to_fen().as_str())
+        .unwrap()
+        .status()
+        .unwrap();
+    assert_eq!(output, result, "game: {}", moves_played.join(" "));
+    result
+}
+
+#[test]
+fn test_self_play_short_game() {
+    play_game(
+        &TaperedEvaluator,
+        &MaterialEvaluator,
+        SELF_PLAY_DEPTH,
+        SHORT_GAME_PLIES,
+    );
+}
+
+#[test]
+#[ignore = "plays a full game, run with -- --ignored"]
+fn test_self_play_tapered_vs_material() {
+    play_game(
+        &TaperedEvaluator,
+        &MaterialEvaluator,
+        SELF_PLAY_DEPTH,
+        usize::MAX,
+    );
+}
+
+#[test]
+#[ignore = "plays a full game, run with -- --ignored"]
+fn test_self_play_material_vs_tapered() {
+    play_game(
+        &MaterialEvaluator,
+        &TaperedEvaluator,
+        SELF_PLAY_DEPTH,
+        usize::MAX,
+    );
+}
+
+#[test]
+#[ignore = "plays a full game, run with -- --ignored"]
+fn test_self_play_tapered_vs_tapered() {
+    play_game(
+        &TaperedEvaluator,
+        &TaperedEvaluator,
+        SELF_PLAY_DEPTH,
+        usize::MAX,
+    );
+}
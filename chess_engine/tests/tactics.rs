@@ -0,0 +1,27 @@
+use chess_engine::{epd::parse_epd_file, search::search};
+
+const TACTICS_EPD: &str = include_str!("tactics.epd");
+/// Deep enough to see a mate in 2: 3 plies to deliver it and 1 more to find that the
+/// mated side has no moves
+const SEARCH_DEPTH: usize = 4;
+
+#[test]
+fn test_tactics() {
+    let records = parse_epd_file(TACTICS_EPD).unwrap();
+    assert!(!records.is_empty());
+
+    for record in records {
+        let mut gamestate = record.gamestate().unwrap();
+        let best_move = search(&mut gamestate, SEARCH_DEPTH)
+            .unwrap()
+            .best_move
+            .unwrap();
+
+        assert!(
+            record.is_solved_by(best_move),
+            "{}: search played {}",
+            record.id.unwrap_or(record.fen),
+            best_move.to_uci()
+        );
+    }
+}
@@ -0,0 +1,17 @@
+use thiserror::Error;
+use tokio::task::JoinError;
+use tokio_tungstenite::tungstenite;
+
+/// Anything that ends a connection or a game early. These are logged by the task that
+/// owns the connection, the rest of the server keeps running.
+#[derive(Error, Debug)]
+pub enum ServerError {
+    #[error("websocket error: {0}")]
+    WebSocket(#[from] tungstenite::Error),
+    #[error("connection closed before a game was requested")]
+    ClosedBeforeGame,
+    #[error("match making is no longer running")]
+    MatchMakingClosed,
+    #[error("engine search task failed: {0}")]
+    SearchTask(#[from] JoinError),
+}
@@ -1,19 +1,58 @@
 use config::Config;
-use log::{debug, info};
+use log::{debug, info, warn};
 use nanoserde::{DeBin, DeBinErr, SerBin};
 use rand::{thread_rng, Rng};
-use std::io::Error;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+use std::sync::{
+    atomic::{AtomicU32, Ordering},
+    Arc,
+};
+use std::time::{Duration, Instant};
 
-use futures::join;
-use futures_util::{stream::select, SinkExt, StreamExt};
+use futures::{join, FutureExt};
+use futures_util::{
+    stream::{select, SplitSink},
+    SinkExt, StreamExt,
+};
 use tokio::{
     net::{TcpListener, TcpStream},
     sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
+    time::{interval, sleep},
 };
 use tokio_tungstenite::{tungstenite::Message, WebSocketStream};
 
-use chess_client::types::{Move, PlayerColor, PlayerMessage, ServerResponse};
+use crate::error::ServerError;
+use chess_client::types::{
+    to_client_move, to_engine_move, Difficulty, Move, PlayerColor, PlayerMessage, ServerResponse,
+    TimeControl,
+};
+use chess_engine::{
+    color::Color,
+    gamestate::{GameResult, Gamestate as EngineGamestate},
+    opening_book::{OpeningBook, DEFAULT_BOOK_PLIES},
+    search::search_with_book,
+};
+
+mod error;
+
+const DEFAULT_EASY_MS: u64 = 50;
+const DEFAULT_MEDIUM_MS: u64 = 250;
+const DEFAULT_HARD_MS: u64 = 1500;
+const DEFAULT_EASY_JITTER_CP: i32 = 60;
+const DEFAULT_MEDIUM_JITTER_CP: i32 = 20;
+const DEFAULT_HARD_JITTER_CP: i32 = 0;
+
+/// How often players are pinged so that connections that silently died are noticed
+const PING_INTERVAL: Duration = Duration::from_secs(5);
+/// A player that hasn't sent anything, pongs included, for this long has disconnected
+const PONG_TIMEOUT: Duration = Duration::from_secs(15);
+/// Seconds a player waits for a human opponent before being offered a game against the computer
+const DEFAULT_BOT_OFFER_SECS: u64 = 30;
+/// How often match making checks for players that have waited too long
+const BOT_OFFER_CHECK_INTERVAL: Duration = Duration::from_secs(1);
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
@@ -27,78 +66,543 @@ async fn main() -> Result<(), Error> {
     let debug_level: String = settings
         .get("debug_level")
         .expect("Could not get debug_level from confifg");
+    let difficulty_settings = DifficultySettings::from_config(&settings);
 
     let mut builder = env_logger::Builder::new();
     builder.parse_filters(&debug_level).init();
 
-    run_server(&websocket_url).await
+    let opening_book = match settings.get::<String>("opening_book_dir") {
+        Ok(dir) => {
+            let book = OpeningBook::from_pgn_dir(Path::new(&dir), DEFAULT_BOOK_PLIES)
+                .map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+            info!("Loaded opening book with {} positions", book.len());
+            book
+        }
+        Err(_) => OpeningBook::new(),
+    };
+
+    let bot_offer_after = Duration::from_secs(
+        settings
+            .get::<u64>("bot_offer_secs")
+            .unwrap_or(DEFAULT_BOT_OFFER_SECS),
+    );
+
+    run_server(
+        &websocket_url,
+        difficulty_settings,
+        Arc::new(opening_book),
+        bot_offer_after,
+    )
+    .await
+}
+
+/// What every connection needs to start the game it asks for
+#[derive(Clone)]
+struct ServerContext {
+    queue_tx: UnboundedSender<QueuedPlayer>,
+    difficulty_settings: DifficultySettings,
+    opening_book: Arc<OpeningBook>,
+    stats: Arc<ServerStats>,
+}
+
+/// Counters sent to whoever asks with PlayerMessage::Stats. Games update them as they start
+/// and end, and match making keeps players_waiting in sync with its waiting room.
+#[derive(Default)]
+struct ServerStats {
+    active_games: AtomicU32,
+    players_waiting: AtomicU32,
+    games_played: AtomicU32,
+    /// Only used to hand out GameIds
+    games_started: AtomicU32,
+}
+
+impl ServerStats {
+    /// Count a game as active until the returned guard is dropped, however the game ends.
+    /// The guard also holds the id the game's log lines are prefixed with.
+    fn start_game(self: &Arc<Self>) -> ActiveGame {
+        self.active_games.fetch_add(1, Ordering::Relaxed);
+        let id = GameId(self.games_started.fetch_add(1, Ordering::Relaxed) + 1);
+        ActiveGame {
+            stats: Arc::clone(self),
+            id,
+        }
+    }
+
+    fn to_response(&self) -> ServerResponse {
+        ServerResponse::Stats {
+            active_games: self.active_games.load(Ordering::Relaxed),
+            players_waiting: self.players_waiting.load(Ordering::Relaxed),
+            games_played: self.games_played.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Held by the task running a game, see ServerStats::start_game
+struct ActiveGame {
+    stats: Arc<ServerStats>,
+    id: GameId,
+}
+
+impl Drop for ActiveGame {
+    fn drop(&mut self) {
+        self.stats.active_games.fetch_sub(1, Ordering::Relaxed);
+        self.stats.games_played.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Tells games apart in the logs, every log line about a game starts with its id so one game
+/// can be followed on a busy server
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct GameId(u32);
+
+impl fmt::Display for GameId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "game {}", self.0)
+    }
+}
+
+/// A player looking for a human opponent with the same time control
+struct QueuedPlayer {
+    socket: WebSocketStream<TcpStream>,
+    time_control: Option<TimeControl>,
 }
 
-async fn run_server(url: &str) -> Result<(), Error> {
-    let (queue_tx, queue_rx) = mpsc::unbounded_channel::<WebSocketStream<TcpStream>>();
-    let queue_tx = Arc::new(queue_tx);
+async fn run_server(
+    url: &str,
+    difficulty_settings: DifficultySettings,
+    opening_book: Arc<OpeningBook>,
+    bot_offer_after: Duration,
+) -> Result<(), Error> {
+    let (queue_tx, queue_rx) = mpsc::unbounded_channel::<QueuedPlayer>();
+    let context = ServerContext {
+        queue_tx,
+        difficulty_settings,
+        opening_book,
+        stats: Arc::new(ServerStats::default()),
+    };
 
-    tokio::spawn(run_match_making(queue_rx));
+    tokio::spawn(run_match_making(queue_rx, context.clone(), bot_offer_after));
 
-    let listener = TcpListener::bind(url).await.expect("Failed to bind");
+    let listener = TcpListener::bind(url).await?;
     info!("Listening on {}", url);
 
     while let Ok((stream, addr)) = listener.accept().await {
         debug!("received new stream from {:#?}", addr);
-        let socket = tokio_tungstenite::accept_async(stream)
-            .await
-            .expect("Error during the websocket handshake occurred");
-        tokio::spawn(process_socket(socket, Arc::clone(&queue_tx)));
+        let context = context.clone();
+        tokio::spawn(async move {
+            if let Err(err) = process_socket(stream, context).await {
+                warn!("connection from {:#?} closed: {}", addr, err);
+            }
+        });
     }
     Ok(())
 }
 
-async fn run_match_making(mut queue_rx: UnboundedReceiver<WebSocketStream<TcpStream>>) {
+/// Pairs players that asked for the same time control. Games against the computer never go
+/// through here. Anyone left waiting for bot_offer_after is offered a game against the
+/// computer instead, see offer_bot_game.
+async fn run_match_making(
+    mut queue_rx: UnboundedReceiver<QueuedPlayer>,
+    context: ServerContext,
+    bot_offer_after: Duration,
+) {
     info!("running match making");
-    let mut waiting_room: Option<WebSocketStream<TcpStream>> = None;
-    while let Some(socket) = queue_rx.recv().await {
-        match waiting_room {
-            Some(queue_socket) => {
-                debug!("starting game");
-                tokio::spawn(start_game_with_human(socket, queue_socket));
-                waiting_room = None;
+    let mut waiting_room: HashMap<Option<TimeControl>, (WebSocketStream<TcpStream>, Instant)> =
+        HashMap::new();
+    let mut bot_offer_check = interval(BOT_OFFER_CHECK_INTERVAL);
+    loop {
+        tokio::select! {
+            player = queue_rx.recv() => {
+                let player = match player {
+                    Some(player) => player,
+                    None => break,
+                };
+                let queued = match waiting_room.remove(&player.time_control) {
+                    Some((mut queue_socket, queued_at)) => {
+                        if is_connected(&mut queue_socket).await {
+                            Some((queue_socket, queued_at))
+                        } else {
+                            debug!(
+                                "dropping disconnected player waiting with time control {:?}",
+                                player.time_control
+                            );
+                            None
+                        }
+                    }
+                    None => None,
+                };
+                match queued {
+                    Some((queue_socket, _)) => {
+                        let active_game = context.stats.start_game();
+                        let game_id = active_game.id;
+                        debug!(
+                            "{}: starting game between humans with time control {:?}",
+                            game_id, player.time_control
+                        );
+                        tokio::spawn(async move {
+                            let _active_game = active_game;
+                            if let Err(err) = start_game_with_human(
+                                game_id,
+                                player.socket,
+                                queue_socket,
+                                player.time_control,
+                            )
+                            .await
+                            {
+                                warn!("{}: game between humans closed: {}", game_id, err);
+                            }
+                        });
+                    }
+                    None => {
+                        debug!("waiting for opponent with time control {:?}", player.time_control);
+                        waiting_room.insert(player.time_control, (player.socket, Instant::now()));
+                    }
+                }
             }
-            None => {
-                debug!("setting waiting_room");
-                waiting_room = Some(socket);
+            _ = bot_offer_check.tick() => {
+                let mut disconnected = Vec::new();
+                for (time_control, (socket, _)) in waiting_room.iter_mut() {
+                    if !is_connected(socket).await {
+                        disconnected.push(*time_control);
+                    }
+                }
+                for time_control in disconnected {
+                    debug!(
+                        "dropping disconnected player waiting with time control {:?}",
+                        time_control
+                    );
+                    waiting_room.remove(&time_control);
+                }
+                let timed_out: Vec<Option<TimeControl>> = waiting_room
+                    .iter()
+                    .filter(|(_, (_, queued_at))| queued_at.elapsed() >= bot_offer_after)
+                    .map(|(time_control, _)| *time_control)
+                    .collect();
+                for time_control in timed_out {
+                    let (socket, _) = waiting_room
+                        .remove(&time_control)
+                        .expect("timed out players were just found in the waiting room");
+                    let context = context.clone();
+                    tokio::spawn(async move {
+                        if let Err(err) = offer_bot_game(socket, context).await {
+                            warn!("player offered a bot game closed: {}", err);
+                        }
+                    });
+                }
             }
         }
+        context
+            .stats
+            .players_waiting
+            .store(waiting_room.len() as u32, Ordering::Relaxed);
+    }
+}
+
+/// Whether a player in the waiting room is still there. Nobody reads their socket while they
+/// wait, so anything they sent is dropped here and a disconnect shows up as a Close frame or
+/// the end of the stream. Connections that died without either fail once pinged often enough.
+async fn is_connected(socket: &mut WebSocketStream<TcpStream>) -> bool {
+    while let Some(msg) = socket.next().now_or_never() {
+        match msg {
+            Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return false,
+            Some(Ok(_)) => continue,
+        }
     }
+    socket.send(Message::Ping(vec![])).await.is_ok()
 }
 
-async fn process_socket(
+async fn process_socket(stream: TcpStream, context: ServerContext) -> Result<(), ServerError> {
+    let socket = tokio_tungstenite::accept_async(stream).await?;
+    handle_game_request(socket, context).await
+}
+
+/// Nobody asked for the same time control in time, so offer the player a game against the
+/// computer. They answer with a new game request, which can also be to keep waiting.
+async fn offer_bot_game(
     mut socket: WebSocketStream<TcpStream>,
-    queue_tx: Arc<UnboundedSender<WebSocketStream<TcpStream>>>,
-) {
-    let msg: Message = socket.next().await.unwrap().unwrap();
-    let msg: PlayerMessage = try_decode_msg(msg).unwrap();
+    context: ServerContext,
+) -> Result<(), ServerError> {
+    socket
+        .send(encode_resp(ServerResponse::BotGameOffered))
+        .await?;
+    handle_game_request(socket, context).await
+}
+
+/// Start whatever game the player's next message asks for. Stats requests are answered
+/// while waiting for it.
+async fn handle_game_request(
+    mut socket: WebSocketStream<TcpStream>,
+    context: ServerContext,
+) -> Result<(), ServerError> {
+    let msg = loop {
+        match next_player_msg(&mut socket, None).await {
+            PlayerEvent::Message(PlayerMessage::Stats) => {
+                socket
+                    .send(encode_resp(context.stats.to_response()))
+                    .await?;
+            }
+            PlayerEvent::Message(msg) => break msg,
+            PlayerEvent::OutOfTime | PlayerEvent::Disconnected => {
+                return Err(ServerError::ClosedBeforeGame)
+            }
+        }
+    };
     match msg {
-        PlayerMessage::GameVsComputer => {
-            start_game_with_computer(socket).await;
+        PlayerMessage::GameVsComputer {
+            difficulty,
+            color,
+            time_control,
+        } => {
+            let active_game = context.stats.start_game();
+            let game_id = active_game.id;
+            debug!(
+                "{}: starting game with computer on {:?}",
+                game_id, difficulty
+            );
+            if let Err(err) = start_game_with_computer(
+                game_id,
+                socket,
+                context.difficulty_settings.get(difficulty),
+                color,
+                time_control,
+                context.opening_book,
+            )
+            .await
+            {
+                warn!("{}: game with computer closed: {}", game_id, err);
+            }
         }
-        PlayerMessage::GameVsHuman => {
-            queue_tx.send(socket).unwrap();
+        PlayerMessage::GameVsHuman { time_control } => {
+            context
+                .queue_tx
+                .send(QueuedPlayer {
+                    socket,
+                    time_control,
+                })
+                .map_err(|_| ServerError::MatchMakingClosed)?;
         }
         _ => {
-            socket.close(None).await.unwrap();
+            socket.close(None).await?;
         }
     }
+    Ok(())
 }
 
-async fn start_game_with_computer(mut socket: WebSocketStream<TcpStream>) {
-    socket.close(None).await.unwrap();
+/// Only the player's time is limited when there is a time control, the computer's
+/// thinking time comes from the difficulty
+async fn start_game_with_computer(
+    game_id: GameId,
+    mut socket: WebSocketStream<TcpStream>,
+    search_settings: SearchSettings,
+    color: Option<PlayerColor>,
+    time_control: Option<TimeControl>,
+    opening_book: Arc<OpeningBook>,
+) -> Result<(), ServerError> {
+    let player_color = color.unwrap_or_else(|| {
+        if thread_rng().gen_bool(0.5) {
+            PlayerColor::White
+        } else {
+            PlayerColor::Black
+        }
+    });
+    let computer_color = PlayerColor::from(Color::from(player_color).opponent());
+
+    let mut engine_game = EngineGamestate::default();
+    let mut active_color = PlayerColor::White;
+    let mut player_clock = time_control.map(Clock::new);
+
+    socket
+        .send(encode_resp(ServerResponse::GameStarted(player_color)))
+        .await?;
+    socket
+        .send(encode_resp(position_resp(&mut engine_game)))
+        .await?;
+
+    loop {
+        if let Some(resp) = game_result(&mut engine_game) {
+            socket.send(encode_resp(resp)).await?;
+            break;
+        }
+
+        if active_color == computer_color {
+            // Searching is CPU bound so keep it off of the async worker threads
+            let opening_book = Arc::clone(&opening_book);
+            let (game, result) = tokio::task::spawn_blocking(move || {
+                let result = search_with_book(
+                    &mut engine_game,
+                    &opening_book,
+                    search_settings.budget,
+                    search_settings.jitter_cp,
+                );
+                (engine_game, result)
+            })
+            .await?;
+            engine_game = game;
+
+            let engine_move = result
+                .expect("gamestate should be valid while the game is not over")
+                .best_move
+                .expect("computer should have a legal move while the game is not over");
+            let outcome = engine_game
+                .make_move_full(engine_move)
+                .expect("moves found by the search should be legal");
+            debug!("{}: computer played {}", game_id, outcome.san);
+            active_color = player_color;
+            if let Some(clock) = player_clock.as_mut() {
+                clock.start_turn();
+            }
+
+            let resp = encode_resp(ServerResponse::MoveMade {
+                player: computer_color,
+                move_: to_client_move(engine_move),
+            });
+            socket.send(resp).await?;
+            socket
+                .send(encode_resp(position_resp(&mut engine_game)))
+                .await?;
+            continue;
+        }
+
+        let turn_time = player_clock.map(|clock| clock.remaining());
+        let msg = match next_player_msg(&mut socket, turn_time).await {
+            PlayerEvent::Message(msg) => msg,
+            PlayerEvent::OutOfTime => {
+                debug!("{}: player ran out of time", game_id);
+                let resp = encode_resp(ServerResponse::GameWon {
+                    winner: computer_color,
+                    disconnected: false,
+                });
+                socket.send(resp).await?;
+                break;
+            }
+            PlayerEvent::Disconnected => {
+                debug!("{}: player disconnected", game_id);
+                return Ok(());
+            }
+        };
+        match msg {
+            PlayerMessage::MovePiece(move_) => {
+                let outcome = to_engine_move(&mut engine_game, move_)
+                    .and_then(|engine_move| engine_game.make_move_full(engine_move).ok());
+                match &outcome {
+                    Some(outcome) => debug!("{}: player played {}", game_id, outcome.san),
+                    None => debug!("{}: player tried illegal move {:?}", game_id, move_),
+                }
+                let is_legal = outcome.is_some();
+                let resp = match is_legal {
+                    true => {
+                        active_color = computer_color;
+                        if let Some(clock) = player_clock.as_mut() {
+                            clock.end_turn();
+                        }
+                        ServerResponse::MoveMade {
+                            player: player_color,
+                            move_,
+                        }
+                    }
+                    false => ServerResponse::MoveRejected {
+                        move_,
+                        fen: engine_game.to_fen(),
+                    },
+                };
+                socket.send(encode_resp(resp)).await?;
+                if is_legal {
+                    socket
+                        .send(encode_resp(position_resp(&mut engine_game)))
+                        .await?;
+                }
+            }
+            PlayerMessage::Resign => {
+                let resp = encode_resp(ServerResponse::GameWon {
+                    winner: computer_color,
+                    disconnected: false,
+                });
+                socket.send(resp).await?;
+                break;
+            }
+            _ => {}
+        }
+    }
+    socket.close(None).await?;
+    Ok(())
 }
 
+type PlayerSink = SplitSink<WebSocketStream<TcpStream>, Message>;
+
+/// Write halves of both players' sockets in a human game. Whenever a player can't be
+/// reached the game is over, and their opponent is told they won by disconnection.
+struct Players {
+    white: PlayerSink,
+    black: PlayerSink,
+    game_id: GameId,
+}
+
+impl Players {
+    fn sink(&mut self, color: PlayerColor) -> &mut PlayerSink {
+        match color {
+            PlayerColor::White => &mut self.white,
+            PlayerColor::Black => &mut self.black,
+        }
+    }
+
+    async fn send_to(
+        &mut self,
+        color: PlayerColor,
+        resp: ServerResponse,
+    ) -> Result<(), ServerError> {
+        if let Err(err) = self.sink(color).send(encode_resp(resp)).await {
+            self.forfeit(color).await;
+            return Err(err.into());
+        }
+        Ok(())
+    }
+
+    async fn broadcast(&mut self, resp: ServerResponse) -> Result<(), ServerError> {
+        let resp = encode_resp(resp);
+        let (white, black) = join!(self.white.send(resp.clone()), self.black.send(resp));
+        match (white, black) {
+            (Ok(()), Ok(())) => Ok(()),
+            (Err(err), Ok(())) => {
+                self.forfeit(PlayerColor::White).await;
+                Err(err.into())
+            }
+            (Ok(()), Err(err)) => {
+                self.forfeit(PlayerColor::Black).await;
+                Err(err.into())
+            }
+            (Err(err), Err(_)) => Err(err.into()),
+        }
+    }
+
+    /// Tell color's opponent they won because color disconnected. If the opponent is gone
+    /// too there's nobody left to tell, so that's only logged.
+    async fn forfeit(&mut self, color: PlayerColor) {
+        let resp = encode_resp(ServerResponse::GameWon {
+            winner: !color,
+            disconnected: true,
+        });
+        if let Err(err) = self.sink(!color).send(resp).await {
+            debug!(
+                "{}: couldn't tell {:?} they won by disconnection: {}",
+                self.game_id, !color, err
+            );
+        }
+    }
+
+    /// Close both connections once the game is over, they may already be gone
+    async fn close(&mut self) {
+        let _ = join!(self.white.close(), self.black.close());
+    }
+}
+
+/// With a time control both clocks are enforced, a player who runs out of time loses
 async fn start_game_with_human(
+    game_id: GameId,
     left_socket: WebSocketStream<TcpStream>,
     right_socket: WebSocketStream<TcpStream>,
-) {
-    let (mut white_socket, mut black_socket) = {
+    time_control: Option<TimeControl>,
+) -> Result<(), ServerError> {
+    let (white_socket, black_socket) = {
         let mut rng = thread_rng();
         if rng.gen_bool(0.5) {
             (left_socket, right_socket)
@@ -108,47 +612,211 @@ async fn start_game_with_human(
     };
 
     let mut game = Gamestate::new();
-    let white_resp = encode_resp(ServerResponse::GameStarted(PlayerColor::White));
-    let black_resp = encode_resp(ServerResponse::GameStarted(PlayerColor::Black));
 
-    let (x, y) = join!(white_socket.send(white_resp), black_socket.send(black_resp));
-    x.unwrap();
-    y.unwrap();
+    let (white_write, white_read) = white_socket.split();
+    let (black_write, black_read) = black_socket.split();
+    let mut players = Players {
+        white: white_write,
+        black: black_write,
+        game_id,
+    };
 
-    let (mut white_write, white_read) = white_socket.split();
-    let (mut black_write, black_read) = black_socket.split();
+    players
+        .send_to(
+            PlayerColor::White,
+            ServerResponse::GameStarted(PlayerColor::White),
+        )
+        .await?;
+    players
+        .send_to(
+            PlayerColor::Black,
+            ServerResponse::GameStarted(PlayerColor::Black),
+        )
+        .await?;
+    players.broadcast(game.position()).await?;
 
-    let white_read =
-        white_read.map(|msg| (PlayerColor::White, try_decode_msg(msg.unwrap()).unwrap()));
-    let black_read =
-        black_read.map(|msg| (PlayerColor::Black, try_decode_msg(msg.unwrap()).unwrap()));
+    let white_read = white_read.map(|msg| (PlayerColor::White, msg));
+    let black_read = black_read.map(|msg| (PlayerColor::Black, msg));
 
     let mut player_msg_stream = select(white_read, black_read);
 
-    while let Some(msg) = player_msg_stream.next().await {
-        match msg {
-            (color, PlayerMessage::MovePiece(move_)) => {
-                if color == game.active_color {
-                    game.history.push(move_);
-                    game.active_color = !color;
-                    let resp = encode_resp(ServerResponse::MoveMade {
-                        player: color,
-                        move_,
+    // NOTE: these are indexed by PlayerColor. Anything a player sends (pongs included) shows
+    // they're still there.
+    let mut last_seen = [Instant::now(); 2];
+    let mut clocks = time_control.map(|time_control| [Clock::new(time_control); 2]);
+    let mut heartbeat = interval(PING_INTERVAL);
+    loop {
+        let turn_time = clocks.map(|clocks| clocks[game.active_color as usize].remaining());
+        let (color, msg) = tokio::select! {
+            msg = player_msg_stream.next() => match msg {
+                Some(msg) => msg,
+                None => break,
+            },
+            _ = clock_runs_out(turn_time) => {
+                debug!("{}: {:?} ran out of time", game_id, game.active_color);
+                players
+                    .broadcast(ServerResponse::GameWon {
+                        winner: !game.active_color,
+                        disconnected: false,
+                    })
+                    .await?;
+                break;
+            }
+            _ = heartbeat.tick() => {
+                let (x, y) = join!(
+                    players.white.send(Message::Ping(vec![])),
+                    players.black.send(Message::Ping(vec![]))
+                );
+                let disconnected = [(PlayerColor::White, x), (PlayerColor::Black, y)]
+                    .into_iter()
+                    .find(|(color, sent)| {
+                        sent.is_err() || last_seen[*color as usize].elapsed() > PONG_TIMEOUT
                     });
-                    let (x, y) = join!(white_write.send(resp.clone()), black_write.send(resp));
-                    x.unwrap();
-                    y.unwrap();
+                if let Some((color, _)) = disconnected {
+                    debug!("{}: {:?} stopped responding", game_id, color);
+                    players.forfeit(color).await;
+                    break;
+                }
+                continue;
+            }
+        };
+        last_seen[color as usize] = Instant::now();
+
+        let msg = match msg {
+            Ok(msg @ Message::Binary(_)) => match try_decode_msg(msg) {
+                Ok(msg) => msg,
+                Err(err) => {
+                    debug!("{}: ignoring message from {:?}: {:?}", game_id, color, err);
+                    continue;
+                }
+            },
+            Ok(Message::Close(_)) | Err(_) => {
+                debug!("{}: {:?} disconnected", game_id, color);
+                players.forfeit(color).await;
+                break;
+            }
+            // Pongs and other control frames
+            Ok(_) => continue,
+        };
+        match (color, msg) {
+            (color, PlayerMessage::MovePiece(move_)) => {
+                let responses = game.play_move(color, move_);
+                debug!(
+                    "{}: {:?} played {:?}, legal: {}",
+                    game_id,
+                    color,
+                    move_,
+                    !responses.is_empty()
+                );
+                if responses.is_empty() {
+                    players.send_to(color, game.rejection(move_)).await?;
+                    continue;
+                }
+                if let Some(clocks) = clocks.as_mut() {
+                    clocks[color as usize].end_turn();
+                    clocks[game.active_color as usize].start_turn();
+                }
+                let game_over = responses.iter().any(|resp| {
+                    matches!(
+                        resp,
+                        ServerResponse::GameWon { .. } | ServerResponse::GameDrawn { .. }
+                    )
+                });
+                for resp in responses {
+                    players.broadcast(resp).await?;
+                }
+                if game_over {
+                    break;
                 }
             }
             (color, PlayerMessage::Resign) => {
-                let resp = encode_resp(ServerResponse::GameWon(!color));
-                let (x, y) = join!(white_write.send(resp.clone()), black_write.send(resp));
-                x.unwrap();
-                y.unwrap();
+                players
+                    .broadcast(ServerResponse::GameWon {
+                        winner: !color,
+                        disconnected: false,
+                    })
+                    .await?;
+                break;
+            }
+            (color, PlayerMessage::RequestTakeback) => {
+                if let Some(resp) = game.request_takeback(color) {
+                    players.broadcast(resp).await?;
+                }
+            }
+            (color, PlayerMessage::AcceptTakeback) => {
+                if let Some(resp) = game.accept_takeback(color) {
+                    if let Some(clocks) = clocks.as_mut() {
+                        clocks[game.active_color as usize].start_turn();
+                    }
+                    players.broadcast(resp).await?;
+                    if let Some(resp) = game.result() {
+                        players.broadcast(resp).await?;
+                        break;
+                    }
+                    players.broadcast(game.position()).await?;
+                }
             }
             _ => {}
         }
     }
+    players.close().await;
+    Ok(())
+}
+
+/// What happened while waiting for a player in next_player_msg
+enum PlayerEvent {
+    Message(PlayerMessage),
+    OutOfTime,
+    Disconnected,
+}
+
+/// Wait for the next message from a player, giving up after turn_time if their clock is
+/// running. The player is pinged meanwhile so that a connection that silently died doesn't
+/// keep the game waiting forever. Frames that aren't a PlayerMessage are skipped.
+async fn next_player_msg(
+    socket: &mut WebSocketStream<TcpStream>,
+    turn_time: Option<Duration>,
+) -> PlayerEvent {
+    let out_of_time = clock_runs_out(turn_time);
+    tokio::pin!(out_of_time);
+
+    let mut last_seen = Instant::now();
+    let mut heartbeat = interval(PING_INTERVAL);
+    loop {
+        tokio::select! {
+            msg = socket.next() => {
+                last_seen = Instant::now();
+                match msg {
+                    Some(Ok(msg @ Message::Binary(_))) => {
+                        if let Ok(msg) = try_decode_msg(msg) {
+                            return PlayerEvent::Message(msg);
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => {
+                        return PlayerEvent::Disconnected
+                    }
+                    // Pongs and other control frames
+                    Some(Ok(_)) => {}
+                }
+            }
+            _ = heartbeat.tick() => {
+                if last_seen.elapsed() > PONG_TIMEOUT
+                    || socket.send(Message::Ping(vec![])).await.is_err()
+                {
+                    return PlayerEvent::Disconnected;
+                }
+            }
+            _ = &mut out_of_time => return PlayerEvent::OutOfTime,
+        }
+    }
+}
+
+/// Completes once turn_time has passed, or never for untimed games
+async fn clock_runs_out(turn_time: Option<Duration>) {
+    match turn_time {
+        Some(turn_time) => sleep(turn_time).await,
+        None => std::future::pending().await,
+    }
 }
 
 fn try_decode_msg(msg: Message) -> Result<PlayerMessage, DeBinErr> {
@@ -159,10 +827,130 @@ fn encode_resp(msg: ServerResponse) -> Message {
     Message::Binary(msg.serialize_bin())
 }
 
+/// The position to send players whenever it changes, see ServerResponse::Position
+fn position_resp(engine_game: &mut EngineGamestate) -> ServerResponse {
+    let legal_moves = engine_game
+        .gen_legal_moves()
+        .expect("gamestate should be valid during a game")
+        .moves
+        .into_iter()
+        .flatten()
+        .map(to_client_move)
+        .collect();
+    ServerResponse::Position {
+        fen: engine_game.to_fen(),
+        legal_moves,
+    }
+}
+
+/// The response that ends the game if the side to move has no legal moves, the fifty move rule
+/// applies or neither side can win anymore
+fn game_result(engine_game: &mut EngineGamestate) -> Option<ServerResponse> {
+    match engine_game.status() {
+        Ok(Some(GameResult::Checkmate { winner })) => Some(ServerResponse::GameWon {
+            winner: winner.into(),
+            disconnected: false,
+        }),
+        Ok(Some(GameResult::Stalemate)) => Some(ServerResponse::GameDrawn {
+            reason: "stalemate".to_string(),
+        }),
+        Ok(Some(GameResult::DrawFiftyMove)) => Some(ServerResponse::GameDrawn {
+            reason: "fifty move rule".to_string(),
+        }),
+        Ok(Some(GameResult::DrawDeadPosition)) => Some(ServerResponse::GameDrawn {
+            reason: "dead position".to_string(),
+        }),
+        Ok(None) => None,
+        Err(err) => panic!("gamestate became invalid during game: {err}"),
+    }
+}
+
+/// How the engine searches each move at a given difficulty
+#[derive(Debug, Clone, Copy)]
+struct SearchSettings {
+    budget: Duration,
+    /// Moves within this many centipawns of the best one may be played instead of it
+    jitter_cp: i32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct DifficultySettings {
+    easy: SearchSettings,
+    medium: SearchSettings,
+    hard: SearchSettings,
+}
+
+impl DifficultySettings {
+    fn from_config(settings: &Config) -> DifficultySettings {
+        let get_search_settings = |level: &str, default_ms: u64, default_jitter_cp: i32| {
+            let budget_ms = settings
+                .get::<u64>(&format!("difficulty.{level}_ms"))
+                .unwrap_or(default_ms);
+            let jitter_cp = settings
+                .get::<i32>(&format!("difficulty.{level}_jitter_cp"))
+                .unwrap_or(default_jitter_cp);
+
+            SearchSettings {
+                budget: Duration::from_millis(budget_ms),
+                jitter_cp,
+            }
+        };
+
+        DifficultySettings {
+            easy: get_search_settings("easy", DEFAULT_EASY_MS, DEFAULT_EASY_JITTER_CP),
+            medium: get_search_settings("medium", DEFAULT_MEDIUM_MS, DEFAULT_MEDIUM_JITTER_CP),
+            hard: get_search_settings("hard", DEFAULT_HARD_MS, DEFAULT_HARD_JITTER_CP),
+        }
+    }
+
+    fn get(&self, difficulty: Difficulty) -> SearchSettings {
+        match difficulty {
+            Difficulty::Easy => self.easy,
+            Difficulty::Medium => self.medium,
+            Difficulty::Hard => self.hard,
+        }
+    }
+}
+
+/// Time left for one player. The clock runs from start_turn until end_turn, which also
+/// adds the increment.
+#[derive(Debug, Clone, Copy)]
+struct Clock {
+    remaining: Duration,
+    increment: Duration,
+    turn_started: Instant,
+}
+
+impl Clock {
+    fn new(time_control: TimeControl) -> Clock {
+        Clock {
+            remaining: Duration::from_secs(time_control.minutes as u64 * 60),
+            increment: Duration::from_secs(time_control.increment_secs as u64),
+            turn_started: Instant::now(),
+        }
+    }
+
+    fn start_turn(&mut self) {
+        self.turn_started = Instant::now();
+    }
+
+    fn remaining(&self) -> Duration {
+        self.remaining.saturating_sub(self.turn_started.elapsed())
+    }
+
+    fn end_turn(&mut self) {
+        self.remaining = self.remaining() + self.increment;
+    }
+}
+
 #[derive(Debug)]
 struct Gamestate {
     active_color: PlayerColor,
     history: Vec<Move>,
+    /// Used to check that the moves players send are legal
+    engine_game: EngineGamestate,
+    /// Color of the player waiting for their takeback request to be accepted
+    takeback_offer: Option<PlayerColor>,
 }
 
 impl Gamestate {
@@ -170,6 +958,393 @@ impl Gamestate {
         Gamestate {
             active_color: PlayerColor::White,
             history: Vec::new(),
+            engine_game: EngineGamestate::default(),
+            takeback_offer: None,
+        }
+    }
+
+    /// Apply a move sent by a player and return the responses to send to both players.
+    /// Moves sent out of turn and illegal moves (including a missing or wrong promotion)
+    /// are ignored. If the move ends the game the result follows the move, otherwise the new
+    /// position does.
+    fn play_move(&mut self, color: PlayerColor, move_: Move) -> Vec<ServerResponse> {
+        if color != self.active_color {
+            return vec![];
+        }
+        let outcome = to_engine_move(&mut self.engine_game, move_)
+            .and_then(|engine_move| self.engine_game.make_move_full(engine_move).ok());
+        let Some(outcome) = outcome else {
+            return vec![];
+        };
+
+        self.history.push(move_);
+        self.active_color = self.engine_game.active_color().into();
+        // NOTE: playing on means the opponent declined the takeback
+        self.takeback_offer = None;
+        let mut responses = vec![ServerResponse::MoveMade {
+            player: color,
+            move_,
+        }];
+        // NOTE: make_move_full already found out about checkmate
+        let result = match outcome.is_checkmate {
+            true => Some(ServerResponse::GameWon {
+                winner: color,
+                disconnected: false,
+            }),
+            false => self.result(),
+        };
+        match result {
+            Some(resp) => responses.push(resp),
+            None => responses.push(self.position()),
+        }
+        responses
+    }
+
+    /// The response that ends the game if it's over, see game_result. Between humans the
+    /// game is also drawn automatically as soon as a position repeats three times.
+    fn result(&mut self) -> Option<ServerResponse> {
+        game_result(&mut self.engine_game).or_else(|| {
+            self.engine_game
+                .is_threefold_repetition()
+                .then(|| ServerResponse::GameDrawn {
+                    reason: "threefold repetition".to_string(),
+                })
+        })
+    }
+
+    fn position(&mut self) -> ServerResponse {
+        position_resp(&mut self.engine_game)
+    }
+
+    /// Number of moves to undo so that it's color's turn again right before their last move
+    fn takeback_len(&self, color: PlayerColor) -> usize {
+        if color == self.active_color {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// Offer the opponent to take back color's last move. Requests are ignored if color
+    /// hasn't made a move yet.
+    fn request_takeback(&mut self, color: PlayerColor) -> Option<ServerResponse> {
+        if self.history.len() < self.takeback_len(color) {
+            return None;
+        }
+        self.takeback_offer = Some(color);
+        Some(ServerResponse::TakebackOffered(color))
+    }
+
+    /// Take back the move the opponent of color asked for. Ignored if they didn't ask.
+    fn accept_takeback(&mut self, color: PlayerColor) -> Option<ServerResponse> {
+        let requester = self
+            .takeback_offer
+            .filter(|requester| *requester == !color)?;
+        self.takeback_offer = None;
+        for _ in 0..self.takeback_len(requester) {
+            self.engine_game
+                .undo_move()
+                .expect("request_takeback checked there are enough moves to undo");
+            self.history.pop();
+        }
+        self.active_color = self.engine_game.active_color().into();
+        Some(ServerResponse::TakebackAccepted {
+            fen: self.engine_game.to_fen(),
+        })
+    }
+
+    /// Response for a player whose move play_move ignored, so their client can resync
+    fn rejection(&self, move_: Move) -> ServerResponse {
+        ServerResponse::MoveRejected {
+            move_,
+            fen: self.engine_game.to_fen(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chess_client::types::Square;
+    use tokio_tungstenite::MaybeTlsStream;
+
+    type ClientSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+    fn client_move(from: (u32, u32), to: (u32, u32)) -> Move {
+        Move {
+            from: Square {
+                file: from.0,
+                rank: from.1,
+            },
+            to: Square {
+                file: to.0,
+                rank: to.1,
+            },
+            promotion: None,
+        }
+    }
+
+    #[test]
+    fn test_server_stats_counts_games_until_they_end() {
+        let stats = Arc::new(ServerStats::default());
+        let first_game = stats.start_game();
+        let second_game = stats.start_game();
+        drop(first_game);
+
+        let output = matches!(
+            stats.to_response(),
+            ServerResponse::Stats {
+                active_games: 1,
+                players_waiting: 0,
+                games_played: 1,
+            }
+        );
+        assert!(output);
+        drop(second_game);
+    }
+
+    #[test]
+    fn test_server_stats_gives_each_game_its_own_id() {
+        let stats = Arc::new(ServerStats::default());
+        let first_game = stats.start_game();
+        drop(first_game);
+        let second_game = stats.start_game();
+        let third_game = stats.start_game();
+
+        let output = (second_game.id, third_game.id);
+        let expected = (GameId(2), GameId(3));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_play_move_ends_game_on_checkmate() {
+        let mut game = Gamestate::new();
+        // fool's mate
+        let moves = [
+            (PlayerColor::White, client_move((5, 1), (5, 2))),
+            (PlayerColor::Black, client_move((4, 6), (4, 4))),
+            (PlayerColor::White, client_move((6, 1), (6, 3))),
+            (PlayerColor::Black, client_move((3, 7), (7, 3))),
+        ];
+
+        let mut responses = vec![];
+        for (color, move_) in moves {
+            responses = game.play_move(color, move_);
+        }
+
+        let output = matches!(
+            responses.as_slice(),
+            [
+                ServerResponse::MoveMade { .. },
+                ServerResponse::GameWon {
+                    winner: PlayerColor::Black,
+                    disconnected: false,
+                },
+            ]
+        );
+        assert!(output);
+    }
+
+    #[test]
+    fn test_play_move_draws_on_threefold_repetition() {
+        let mut game = Gamestate::new();
+        // Ng1-f3, Ng8-f6, Nf3-g1, Nf6-g8 twice brings back the starting position a third time
+        let knight_shuffle = [
+            (PlayerColor::White, client_move((6, 0), (5, 2))),
+            (PlayerColor::Black, client_move((6, 7), (5, 5))),
+            (PlayerColor::White, client_move((5, 2), (6, 0))),
+            (PlayerColor::Black, client_move((5, 5), (6, 7))),
+        ];
+
+        let mut responses = vec![];
+        for (color, move_) in knight_shuffle.iter().chain(knight_shuffle.iter()) {
+            responses = game.play_move(*color, *move_);
+            assert!(matches!(
+                responses.first(),
+                Some(ServerResponse::MoveMade { .. })
+            ));
+        }
+
+        let output = matches!(
+            responses.last(),
+            Some(ServerResponse::GameDrawn { reason }) if reason == "threefold repetition"
+        );
+        assert!(output);
+    }
+
+    #[test]
+    fn test_play_move_illegal_move_rejection_has_current_fen() {
+        let mut game = Gamestate::new();
+        game.play_move(PlayerColor::White, client_move((4, 1), (4, 3)));
+        // the e-pawn can't move backwards
+        let illegal_move = client_move((4, 6), (4, 7));
+        let output = game.play_move(PlayerColor::Black, illegal_move);
+        assert!(output.is_empty());
+
+        let output = match game.rejection(illegal_move) {
+            ServerResponse::MoveRejected { move_, fen } => (move_, fen),
+            resp => panic!("expected MoveRejected, got {resp:?}"),
+        };
+        let expected = (
+            illegal_move,
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1".to_string(),
+        );
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_clock_end_turn_adds_increment() {
+        let mut clock = Clock::new(TimeControl {
+            minutes: 0,
+            increment_secs: 2,
+        });
+        clock.end_turn();
+        let output = clock.remaining;
+        let expected = Duration::from_secs(2);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_takeback_returns_to_requesters_turn() {
+        let mut game = Gamestate::new();
+        game.play_move(PlayerColor::White, client_move((4, 1), (4, 3)));
+        game.play_move(PlayerColor::Black, client_move((4, 6), (4, 4)));
+        assert!(matches!(
+            game.request_takeback(PlayerColor::White),
+            Some(ServerResponse::TakebackOffered(PlayerColor::White))
+        ));
+
+        let output = match game.accept_takeback(PlayerColor::Black) {
+            Some(ServerResponse::TakebackAccepted { fen }) => fen,
+            resp => panic!("expected TakebackAccepted, got {resp:?}"),
+        };
+        let expected = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert_eq!(output, expected);
+        assert_eq!(game.active_color, PlayerColor::White);
+        assert!(game.history.is_empty());
+    }
+
+    #[test]
+    fn test_takeback_of_opponents_turn_undoes_one_move() {
+        let mut game = Gamestate::new();
+        game.play_move(PlayerColor::White, client_move((4, 1), (4, 3)));
+        game.request_takeback(PlayerColor::White);
+        game.accept_takeback(PlayerColor::Black);
+        let output = (game.active_color, game.history.len());
+        let expected = (PlayerColor::White, 0);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_takeback_ignored_without_move_to_undo() {
+        let mut game = Gamestate::new();
+        assert!(game.request_takeback(PlayerColor::White).is_none());
+        game.play_move(PlayerColor::White, client_move((4, 1), (4, 3)));
+        // Black hasn't moved yet so there is nothing of theirs to take back
+        assert!(game.request_takeback(PlayerColor::Black).is_none());
+    }
+
+    #[test]
+    fn test_takeback_needs_request_from_opponent() {
+        let mut game = Gamestate::new();
+        game.play_move(PlayerColor::White, client_move((4, 1), (4, 3)));
+        game.request_takeback(PlayerColor::White);
+        // the requester can't accept their own takeback
+        assert!(game.accept_takeback(PlayerColor::White).is_none());
+        // and playing on declines it
+        game.play_move(PlayerColor::Black, client_move((4, 6), (4, 4)));
+        let output = game.accept_takeback(PlayerColor::Black);
+        assert!(output.is_none());
+    }
+
+    #[test]
+    fn test_play_move_followed_by_position() {
+        let mut game = Gamestate::new();
+        let responses = game.play_move(PlayerColor::White, client_move((4, 1), (4, 3)));
+
+        let output = match responses.as_slice() {
+            [ServerResponse::MoveMade { .. }, ServerResponse::Position { fen, legal_moves }] => {
+                (fen.as_str(), legal_moves.len())
+            }
+            responses => panic!("expected MoveMade and Position, got {responses:?}"),
+        };
+        let expected = (
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            20,
+        );
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_play_move_ignores_move_out_of_turn() {
+        let mut game = Gamestate::new();
+        let output = game
+            .play_move(PlayerColor::Black, client_move((6, 7), (5, 5)))
+            .len();
+        let expected = 0;
+        assert_eq!(output, expected);
+    }
+
+    /// A connected client and the server's end of its socket
+    async fn connect_client() -> (ClientSocket, WebSocketStream<TcpStream>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let (client, server) = join!(tokio_tungstenite::connect_async(url), async {
+            tokio_tungstenite::accept_async(listener.accept().await.unwrap().0).await
+        });
+        (client.unwrap().0, server.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_match_making_skips_disconnected_waiting_player() {
+        let (queue_tx, queue_rx) = mpsc::unbounded_channel();
+        let context = ServerContext {
+            queue_tx: queue_tx.clone(),
+            difficulty_settings: DifficultySettings::from_config(&Config::default()),
+            opening_book: Arc::new(OpeningBook::new()),
+            stats: Arc::new(ServerStats::default()),
+        };
+        tokio::spawn(run_match_making(queue_rx, context, Duration::from_secs(60)));
+
+        let (mut gone, socket) = connect_client().await;
+        queue_tx
+            .send(QueuedPlayer {
+                socket,
+                time_control: None,
+            })
+            .unwrap();
+        gone.close(None).await.unwrap();
+
+        let mut clients = Vec::new();
+        for _ in 0..2 {
+            let (client, socket) = connect_client().await;
+            queue_tx
+                .send(QueuedPlayer {
+                    socket,
+                    time_control: None,
+                })
+                .unwrap();
+            clients.push(client);
+        }
+
+        let mut output = Vec::new();
+        for client in clients.iter_mut() {
+            // Waiting players are pinged before they're paired
+            let first_response = async {
+                loop {
+                    match client.next().await {
+                        Some(Ok(Message::Ping(_))) => continue,
+                        Some(Ok(msg)) => {
+                            break ServerResponse::deserialize_bin(&msg.into_data()).ok()
+                        }
+                        _ => break None,
+                    }
+                }
+            };
+            let response = tokio::time::timeout(Duration::from_secs(5), first_response).await;
+            output.push(matches!(response, Ok(Some(ServerResponse::GameStarted(_)))));
         }
+        let expected = vec![true, true];
+        assert_eq!(output, expected);
     }
 }